@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+
+
+/// The round primitives of an AES evaluation strategy, the round loop of [`super::run_aes`] being
+/// generic over it.
+///
+/// The linear layers work on [`AESBackend::State`] and SubBytes on [`AESBackend::SBoxState`], as
+/// the hybrid odd strategy switches between bits and nibbles around every S-box. A strategy with a
+/// single representation takes the same type for both and moves the state through the conversions.
+pub trait AESBackend{
+    type ClientKey;
+    type State;
+    type SBoxState;
+
+    /// Encrypts the 128 bits of a block, most significant bit of each byte first.
+    fn encrypt(m : &[u64], client_key : &Self::ClientKey) -> Self::State;
+
+    fn decrypt(state : &Self::State, client_key : &Self::ClientKey) -> Vec<u64>;
+
+    fn add_round_key(&self, state : &Self::State, round_key : &[bool]) -> Self::State;
+
+    fn shift_rows(&self, state : &Self::State) -> Self::State;
+
+    fn mix_columns(&self, state : &Self::State) -> Self::State;
+
+    /// Brings the state to the representation of the S-box, timed as the recomposition layer.
+    fn to_sbox_state(&self, state : Self::State) -> Self::SBoxState;
+
+    fn sub_bytes(&self, state : &Self::SBoxState) -> Self::SBoxState;
+
+    /// Brings the state back to the representation of the linear layers, timed as the
+    /// decomposition layer.
+    fn leave_sbox_state(&self, state : Self::SBoxState) -> Self::State;
+
+    //Debug : print the intermediate states when the backend is able to decrypt them
+    fn print_debug(&self, _state : &Self::State, _expected : &str){}
+
+    fn print_debug_sbox(&self, _state : &Self::SBoxState, _expected : &str){}
+}
+
+
+/// The evaluation strategies the demo can be run with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind{
+    /// Bits with the parity encoding for the linear layers, nibbles in Z17 for the S-box.
+    Odd,
+    /// Boyar-Peralta circuit with gate bootstrapping on tfhe::boolean.
+    Gates,
+    /// Lookup tables on 4-bit shortint nibbles.
+    FullLut,
+}
+
+impl BackendKind{
+    pub const ALL : [BackendKind;3] = [BackendKind::Odd, BackendKind::Gates, BackendKind::FullLut];
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            BackendKind::Odd => "odd",
+            BackendKind::Gates => "gates",
+            BackendKind::FullLut => "full-lut",
+        }
+    }
+}
+
+impl fmt::Display for BackendKind{
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BackendKind{
+    type Err = String;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err>{
+        BackendKind::ALL.into_iter()
+                        .find(|kind| kind.name() == s)
+                        .ok_or_else(|| format!("Unknown AES backend {}, expected one of {}",
+                                                s,
+                                                BackendKind::ALL.map(|kind| kind.name()).join(", ")))
+    }
+}
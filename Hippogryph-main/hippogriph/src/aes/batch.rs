@@ -0,0 +1,156 @@
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tfhe::keycache::NamedParam;
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use super::{aes_utils::key_expansion, backend::AESBackend, format_output, run_aes_with_round_keys, test_vector_bits, OddHybridBackend, EXPECTED_OUTPUT};
+
+
+/// Wall time of a batch of AES blocks. The layers of the blocks overlap, so no per-layer timing
+/// is kept.
+#[derive(Clone, Debug)]
+pub struct BatchReport{
+    pub parameters : String,
+    pub blocks : usize,
+    pub max_in_flight : usize,
+    pub threads : usize,
+    pub elapsed : Duration,
+}
+
+impl BatchReport{
+    pub fn new(parameters : &str) -> Self{
+        BatchReport { parameters : parameters.to_string(), blocks : 0, max_in_flight : 0, threads : rayon::current_num_threads(), elapsed : Duration::ZERO }
+    }
+
+    pub fn blocks_per_second(&self) -> f64{
+        self.blocks as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn to_json(&self) -> String{
+        json!({
+            "parameters" : self.parameters,
+            "blocks" : self.blocks,
+            "max_in_flight" : self.max_in_flight,
+            "threads" : self.threads,
+            "total_ms" : self.elapsed.as_secs_f64() * 1000.,
+            "blocks_per_second" : self.blocks_per_second(),
+        }).to_string()
+    }
+}
+
+
+
+/// Evaluates the AES on many blocks under the same key, the round keys being expanded once.
+///
+/// Every block runs its rounds as a task of the rayon pool, so the parallel layers of all the
+/// blocks in flight share one work queue and the cores stay busy between the layers of a single
+/// block. At most `max_in_flight` blocks are in flight, which bounds the memory taken by their
+/// intermediate states : as many lanes are spawned in a `rayon::scope`, and each one takes the
+/// next waiting block as soon as its current one is done, so a slow block never holds the others
+/// back.
+pub fn run_aes_batch<B>(states : Vec<B::State>, backend : &B, aes_key : Vec<bool>, max_in_flight : usize, report : &mut BatchReport) -> Vec<B::State>
+where
+    B : AESBackend + Sync,
+    B::State : Send,
+{
+    assert!(max_in_flight > 0, "At least one block should be in flight");
+    let round_keys = key_expansion(aes_key);
+
+    let start = Instant::now();
+    let block_count = states.len();
+    let waiting = Mutex::new(states.into_iter().enumerate());
+    let results : Mutex<Vec<Option<B::State>>> = Mutex::new((0..block_count).map(|_| None).collect());
+    rayon::scope(|scope| {
+        for _ in 0..max_in_flight.min(block_count){
+            scope.spawn(|_| loop{
+                let next = waiting.lock().unwrap().next();
+                let Some((i, state)) = next else {
+                    break;
+                };
+                let output = run_aes_with_round_keys(&state, backend, &round_keys, None);
+                results.lock().unwrap()[i] = Some(output);
+            });
+        }
+    });
+
+    report.blocks += block_count;
+    report.max_in_flight = max_in_flight;
+    report.elapsed += start.elapsed();
+    results.into_inner()
+           .unwrap()
+           .into_iter()
+           .map(|state| state.unwrap())
+           .collect()
+}
+
+
+
+const BATCH_BLOCKS : usize = 16;
+const BATCH_MAX_IN_FLIGHT : usize = 8;
+
+
+/// Runs the odd AES on copies of the test vector through the batch driver.
+pub fn demo_aes_batch(){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let (plaintext_bits, aes_key_bits) = test_vector_bits();
+    let states = (0..BATCH_BLOCKS).map(|_| OddHybridBackend::encrypt(&plaintext_bits, client_key)).collect();
+
+    let backend = OddHybridBackend { server_key };
+    let mut report = BatchReport::new(&parameters.name());
+    let results = run_aes_batch(states, &backend, aes_key_bits, BATCH_MAX_IN_FLIGHT, &mut report);
+
+    let correct = results.iter()
+                         .filter(|state| format_output(&OddHybridBackend::decrypt(state, client_key)) == EXPECTED_OUTPUT)
+                         .count();
+    println!("Correct blocks : {}/{}", correct, results.len());
+    println!("{:.3} blocks/s", report.blocks_per_second());
+    println!("{}", report.to_json());
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::aes::run_aes;
+    use crate::aes::timing::TimingReport;
+
+    #[test]
+    fn test_batch_report_to_json(){
+        let mut report = BatchReport::new("\"quoted\" parameters");
+        report.blocks = 4;
+        report.elapsed = Duration::from_secs(2);
+
+        let json : serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["parameters"], "\"quoted\" parameters");
+        assert_eq!(json["blocks"], 4);
+        assert_eq!(json["blocks_per_second"], 2.);
+    }
+
+    #[test]
+    fn test_batch_matches_run_aes(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let backend = OddHybridBackend { server_key };
+
+        // three blocks in two lanes, so that one lane runs two of them
+        let (plaintext_bits, aes_key_bits) = test_vector_bits();
+        let blocks : Vec<Vec<u64>> = (0..3).map(|i| plaintext_bits.iter().enumerate().map(|(j, b)| b ^ (j % (i + 2) == 0) as u64).collect()).collect();
+
+        let states = blocks.iter().map(|block| OddHybridBackend::encrypt(block, client_key)).collect();
+        let mut report = BatchReport::new("test");
+        let results = run_aes_batch(states, &backend, aes_key_bits.clone(), 2, &mut report);
+        assert_eq!(report.blocks, 3);
+
+        for (block, result) in blocks.iter().zip(&results){
+            let mut block_report = TimingReport::new("test");
+            let expected = run_aes(&OddHybridBackend::encrypt(block, client_key), &backend, aes_key_bits.clone(), &mut block_report);
+            assert_eq!(OddHybridBackend::decrypt(result, client_key), OddHybridBackend::decrypt(&expected, client_key));
+        }
+    }
+}
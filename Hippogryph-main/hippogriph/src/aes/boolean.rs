@@ -0,0 +1,170 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use tfhe::boolean::prelude::{BinaryBooleanGates, Ciphertext, ClientKey, ServerKey};
+
+use super::backend::AESBackend;
+use super::clear::{mixcolumns, post_circuit, pre_circuit, s_box_boyar, BitGates};
+
+
+// Every gate but the NOT is a bootstrapping
+impl BitGates for ServerKey{
+    type Bit = Ciphertext;
+
+    fn xor(&self, a : &Ciphertext, b : &Ciphertext) -> Ciphertext{
+        BinaryBooleanGates::xor(self, a, b)
+    }
+
+    fn and(&self, a : &Ciphertext, b : &Ciphertext) -> Ciphertext{
+        BinaryBooleanGates::and(self, a, b)
+    }
+
+    fn not(&self, a : &Ciphertext) -> Ciphertext{
+        ServerKey::not(self, a)
+    }
+}
+
+
+/// The AES state as 128 gate bootstrapping ciphertexts, in the same order as [`super::AESStateBoolean`].
+pub struct AESStateGates{
+    pub bits : Vec<Ciphertext>
+}
+
+
+impl AESStateGates{
+    pub fn tfhe_encryption_bits(m : &[u64], client_key : &ClientKey) -> Self{
+        assert_eq!(m.len(), 128);
+        Self { bits : m.iter().map(|b| client_key.encrypt(*b == 1)).collect() }
+    }
+
+
+    pub fn tfhe_decryption_bits(&self, client_key : &ClientKey) -> Vec<u64>{
+        self.bits.iter().map(|c| client_key.decrypt(c) as u64).collect()
+    }
+
+
+    //getter
+    pub fn square_getter(&self, row : usize, col : usize, bit : usize) -> &Ciphertext{
+        &self.bits[col * 8 * 4 + row * 8 + bit]
+    }
+}
+
+
+
+fn sub_bytes(state : &AESStateGates, server_key : &ServerKey) -> AESStateGates{
+    AESStateGates {
+        bits : (0..16).into_par_iter()
+                    .map(|i| {
+                        let byte = &state.bits[i*8..(i+1)*8];
+                        post_circuit(server_key, &s_box_boyar(server_key, &pre_circuit(server_key, byte)))
+                    })
+                    .collect::<Vec<Vec<Ciphertext>>>()
+                    .concat()
+    }
+}
+
+
+
+fn add_round_key(state : &AESStateGates, round_key : &[bool], server_key : &ServerKey) -> AESStateGates{
+    assert_eq!(state.bits.len(), 128);
+    assert_eq!(round_key.len(), 128);
+    AESStateGates { bits: state.bits.iter()
+                                .zip(round_key)
+                                .map(|(c, k)| if *k {server_key.not(c)} else {c.to_owned()})
+                                .collect()
+            }
+}
+
+
+
+fn shift_rows(state : &AESStateGates) -> AESStateGates{
+    AESStateGates { bits: (0..4).map(|col|
+        (0..4).map(|row|
+            (0..8).map(|i_bit|
+                state.square_getter(row, (col + row) % 4, i_bit).to_owned()
+            ).collect()
+        ).collect::<Vec<Vec<Ciphertext>>>().concat()
+    ).collect::<Vec<Vec<Ciphertext>>>().concat()
+    }
+}
+
+
+
+// The circuit takes the bytes least significant bit first
+fn mix_columns(state : &AESStateGates, server_key : &ServerKey) -> AESStateGates{
+    let reverse_bytes = |bits : &[Ciphertext]| bits.chunks(8)
+                                                    .flat_map(|byte| byte.iter().rev().cloned())
+                                                    .collect::<Vec<Ciphertext>>();
+    AESStateGates {
+        bits : (0..4).into_par_iter()
+                    .map(|col| reverse_bytes(&mixcolumns(server_key, &reverse_bytes(&state.bits[col*32..(col + 1)*32]))))
+                    .collect::<Vec<Vec<Ciphertext>>>()
+                    .concat()
+    }
+}
+
+
+
+/// Gate bootstrapping evaluation, the state keeps the same representation for the S-box.
+pub struct GatesBackend<'a>{
+    pub server_key : &'a ServerKey
+}
+
+
+impl AESBackend for GatesBackend<'_>{
+    type ClientKey = ClientKey;
+    type State = AESStateGates;
+    type SBoxState = AESStateGates;
+
+    fn encrypt(m : &[u64], client_key : &ClientKey) -> AESStateGates{
+        AESStateGates::tfhe_encryption_bits(m, client_key)
+    }
+
+    fn decrypt(state : &AESStateGates, client_key : &ClientKey) -> Vec<u64>{
+        state.tfhe_decryption_bits(client_key)
+    }
+
+    fn add_round_key(&self, state : &AESStateGates, round_key : &[bool]) -> AESStateGates{
+        add_round_key(state, round_key, self.server_key)
+    }
+
+    fn shift_rows(&self, state : &AESStateGates) -> AESStateGates{
+        shift_rows(state)
+    }
+
+    fn mix_columns(&self, state : &AESStateGates) -> AESStateGates{
+        mix_columns(state, self.server_key)
+    }
+
+    fn to_sbox_state(&self, state : AESStateGates) -> AESStateGates{
+        state
+    }
+
+    fn sub_bytes(&self, state : &AESStateGates) -> AESStateGates{
+        sub_bytes(state, self.server_key)
+    }
+
+    fn leave_sbox_state(&self, state : AESStateGates) -> AESStateGates{
+        state
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use tfhe::boolean::{keycache::KEY_CACHE, parameters::DEFAULT_PARAMETERS};
+    use crate::aes::{format_output, run_aes, test_vector_bits, timing::TimingReport, EXPECTED_OUTPUT};
+
+    #[test]
+    fn test_gates_run_aes(){
+        let keys = KEY_CACHE.get_from_param(DEFAULT_PARAMETERS);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        let (plaintext_bits, aes_key_bits) = test_vector_bits();
+        let state = GatesBackend::encrypt(&plaintext_bits, client_key);
+        let mut report = TimingReport::new("test");
+        let result = run_aes(&state, &GatesBackend { server_key }, aes_key_bits, &mut report);
+
+        assert_eq!(format_output(&GatesBackend::decrypt(&result, client_key)), EXPECTED_OUTPUT);
+    }
+}
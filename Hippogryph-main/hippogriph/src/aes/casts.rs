@@ -2,7 +2,7 @@ use tfhe::odd::prelude::*;
 
 
 
-pub fn recomposer(bits : &Vec<Ciphertext>, encoding_out : &Encoding, server_key : &ServerKey, client_key_debug : &ClientKey)->Ciphertext{
+pub fn recomposer(bits : &Vec<Ciphertext>, encoding_out : &Encoding, server_key : &ServerKey)->Ciphertext{
     let new_p = encoding_out.get_modulus();
     let inv3_mod_p = match new_p {
         17 => 6,
@@ -41,7 +41,6 @@ pub fn decomposer(
     input: &Ciphertext,
     encoding_out: &Encoding,
     server_key: &ServerKey,
-    _client_key_debug: &ClientKey,
 ) -> Vec<Ciphertext> {
     let encoding_in = match input {
         Ciphertext::EncodingEncrypted(_, enc) => enc,
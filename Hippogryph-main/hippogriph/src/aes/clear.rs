@@ -1,310 +1,372 @@
-pub fn clear_s_box_boyar(y: &Vec<bool>) -> Vec<bool> {
+use super::aes_utils::key_expansion;
+
+
+/// The gates the AES circuits are written with, so that the same circuits run in the clear and
+/// on encrypted bits.
+pub trait BitGates {
+    type Bit: Clone;
+
+    fn xor(&self, a: &Self::Bit, b: &Self::Bit) -> Self::Bit;
+    fn and(&self, a: &Self::Bit, b: &Self::Bit) -> Self::Bit;
+    fn not(&self, a: &Self::Bit) -> Self::Bit;
+}
+
+
+pub struct Clear;
+
+impl BitGates for Clear {
+    type Bit = bool;
+
+    fn xor(&self, a: &bool, b: &bool) -> bool {
+        a ^ b
+    }
+
+    fn and(&self, a: &bool, b: &bool) -> bool {
+        a & b
+    }
+
+    fn not(&self, a: &bool) -> bool {
+        !a
+    }
+}
+
+
+
+/// The nonlinear middle part of the Boyar–Peralta S-box, from the 22 outputs of [`pre_circuit`]
+/// to the 18 inputs of [`post_circuit`].
+pub fn s_box_boyar<G: BitGates>(g: &G, y: &[G::Bit]) -> Vec<G::Bit> {
     assert_eq!(y.len(), 22);
 
     // Store frequently accessed y values in local variables
-    let y0 = y[0];
-    let y1 = y[1];
-    let y2 = y[2];
-    let y3 = y[3];
-    let y4 = y[4];
-    let y5 = y[5];
-    let y6 = y[6];
-    let y7 = y[7];
-    let y8 = y[8];
-    let y9 = y[9];
-    let y10 = y[10];
-    let y11 = y[11];
-    let y12 = y[12];
-    let y13 = y[13];
-    let y14 = y[14];
-    let y15 = y[15];
-    let y16 = y[16];
-    let y17 = y[17];
-    let y18 = y[18];
-    let y19 = y[19];
-    let y20 = y[20];
-    let y21 = y[21];
+    let y0 = &y[0];
+    let y1 = &y[1];
+    let y2 = &y[2];
+    let y3 = &y[3];
+    let y4 = &y[4];
+    let y5 = &y[5];
+    let y6 = &y[6];
+    let y7 = &y[7];
+    let y8 = &y[8];
+    let y9 = &y[9];
+    let y10 = &y[10];
+    let y11 = &y[11];
+    let y12 = &y[12];
+    let y13 = &y[13];
+    let y14 = &y[14];
+    let y15 = &y[15];
+    let y16 = &y[16];
+    let y17 = &y[17];
+    let y18 = &y[18];
+    let y19 = &y[19];
+    let y20 = &y[20];
+    let y21 = &y[21];
 
     // Simplify intermediate variables to reduce redundancy
-    let t2 = y12 & y15;
-    let t3 = y3 & y6;
-    let t4 = t3 ^ t2;
-    let t5 = y4 & y0;
-    let t6 = t5 ^ t2;
-    let t7 = y13 & y16;
-    let t8 = y5 & y1;
-    let t9 = t8 ^ t7;
-    let t10 = y2 & y7;
-    let t11 = t10 ^ t7;
-    let t12 = y9 & y11;
-    let t13 = y14 & y17;
-    let t14 = t13 ^ t12;
-    let t15 = y8 & y10;
-    let t16 = t15 ^ t12;
-    let t17 = t4 ^ t14;
-    let t18 = t6 ^ t16;
-    let t19 = t9 ^ t14;
-    let t20 = t11 ^ t16;
-    let t21 = t17 ^ y20;
-    let t22 = t18 ^ y19;
-    let t23 = t19 ^ y21;
-    let t24 = t20 ^ y18;
-    let t25 = t21 ^ t22;
-    let t26 = t21 & t23;
-    let t27 = t24 ^ t26;
-    let t28 = t25 & t27;
-    let t29 = t28 ^ t22;
-    let t30 = t23 ^ t24;
-    let t31 = t22 ^ t26;
-    let t32 = t31 & t30;
-    let t33 = t32 ^ t24;
-    let t34 = t23 ^ t33;
-    let t35 = t27 ^ t33;
-    let t36 = t24 & t35;
-    let t37 = t36 ^ t34;
-    let t38 = t27 ^ t36;
-    let t39 = t29 & t38;
-    let t40 = t25 ^ t39;
-    let t41 = t40 ^ t37;
-    let t42 = t29 ^ t33;
-    let t43 = t29 ^ t40;
-    let t44 = t33 ^ t37;
-    let t45 = t42 ^ t41;
+    let t2 = g.and(y12, y15);
+    let t3 = g.and(y3, y6);
+    let t4 = g.xor(&t3, &t2);
+    let t5 = g.and(y4, y0);
+    let t6 = g.xor(&t5, &t2);
+    let t7 = g.and(y13, y16);
+    let t8 = g.and(y5, y1);
+    let t9 = g.xor(&t8, &t7);
+    let t10 = g.and(y2, y7);
+    let t11 = g.xor(&t10, &t7);
+    let t12 = g.and(y9, y11);
+    let t13 = g.and(y14, y17);
+    let t14 = g.xor(&t13, &t12);
+    let t15 = g.and(y8, y10);
+    let t16 = g.xor(&t15, &t12);
+    let t17 = g.xor(&t4, &t14);
+    let t18 = g.xor(&t6, &t16);
+    let t19 = g.xor(&t9, &t14);
+    let t20 = g.xor(&t11, &t16);
+    let t21 = g.xor(&t17, y20);
+    let t22 = g.xor(&t18, y19);
+    let t23 = g.xor(&t19, y21);
+    let t24 = g.xor(&t20, y18);
+    let t25 = g.xor(&t21, &t22);
+    let t26 = g.and(&t21, &t23);
+    let t27 = g.xor(&t24, &t26);
+    let t28 = g.and(&t25, &t27);
+    let t29 = g.xor(&t28, &t22);
+    let t30 = g.xor(&t23, &t24);
+    let t31 = g.xor(&t22, &t26);
+    let t32 = g.and(&t31, &t30);
+    let t33 = g.xor(&t32, &t24);
+    let t34 = g.xor(&t23, &t33);
+    let t35 = g.xor(&t27, &t33);
+    let t36 = g.and(&t24, &t35);
+    let t37 = g.xor(&t36, &t34);
+    let t38 = g.xor(&t27, &t36);
+    let t39 = g.and(&t29, &t38);
+    let t40 = g.xor(&t25, &t39);
+    let t41 = g.xor(&t40, &t37);
+    let t42 = g.xor(&t29, &t33);
+    let t43 = g.xor(&t29, &t40);
+    let t44 = g.xor(&t33, &t37);
+    let t45 = g.xor(&t42, &t41);
 
     // Store the results directly in a new vector
     vec![
-        t44 & y15, t37 & y6, t33 & y0, t43 & y16, t40 & y1,
-        t29 & y7, t42 & y11, t45 & y17, t41 & y10, t44 & y12,
-        t37 & y3, t33 & y4, t43 & y13, t40 & y5, t29 & y2,
-        t42 & y9, t45 & y14, t41 & y8
+        g.and(&t44, y15), g.and(&t37, y6), g.and(&t33, y0), g.and(&t43, y16), g.and(&t40, y1),
+        g.and(&t29, y7), g.and(&t42, y11), g.and(&t45, y17), g.and(&t41, y10), g.and(&t44, y12),
+        g.and(&t37, y3), g.and(&t33, y4), g.and(&t43, y13), g.and(&t40, y5), g.and(&t29, y2),
+        g.and(&t42, y9), g.and(&t45, y14), g.and(&t41, y8)
     ]
 }
 
 
 
-pub fn clear_pre_circuit(x: &Vec<bool>) -> Vec<bool> {
+
+/// The top linear layer of the Boyar–Peralta S-box, the input byte being most significant bit first.
+pub fn pre_circuit<G: BitGates>(g: &G, x: &[G::Bit]) -> Vec<G::Bit> {
     assert_eq!(x.len(), 8);
 
     // Store frequently used values to reduce indexing redundancy
-    let x0 = x[0];
-    let x1 = x[1];
-    let x2 = x[2];
-    let x3 = x[3];
-    let x4 = x[4];
-    let x5 = x[5];
-    let x6 = x[6];
-    let x7 = x[7];
+    let x0 = &x[0];
+    let x1 = &x[1];
+    let x2 = &x[2];
+    let x3 = &x[3];
+    let x4 = &x[4];
+    let x5 = &x[5];
+    let x6 = &x[6];
+    let x7 = &x[7];
 
     // Intermediate variables
-    let y14 = x3 ^ x5;
-    let y13 = x0 ^ x6;
-    let y9 = x0 ^ x3;
-    let y8 = x0 ^ x5;
-    let t0 = x1 ^ x2;
-    let y1 = t0 ^ x7;
-    let y4 = y1 ^ x3;
-    let y12 = y13 ^ y14;
-    let y2 = y1 ^ x0;
-    let y5 = y1 ^ x6;
-    let y3 = y5 ^ y8;
-    let t1 = x4 ^ y12;
-    let y15 = t1 ^ x5;
-    let y20 = t1 ^ x1;
-    let y6 = y15 ^ x7;
-    let y10 = y15 ^ t0;
-    let y11 = y20 ^ y9;
-    let y7 = x7 ^ y11;
-    let y17 = y10 ^ y11;
-    let y19 = y10 ^ y8;
-    let y16 = t0 ^ y11;
-    let y21 = y13 ^ y16;
-    let y18 = x0 ^ y16;
+    let y14 = g.xor(x3, x5);
+    let y13 = g.xor(x0, x6);
+    let y9 = g.xor(x0, x3);
+    let y8 = g.xor(x0, x5);
+    let t0 = g.xor(x1, x2);
+    let y1 = g.xor(&t0, x7);
+    let y4 = g.xor(&y1, x3);
+    let y12 = g.xor(&y13, &y14);
+    let y2 = g.xor(&y1, x0);
+    let y5 = g.xor(&y1, x6);
+    let y3 = g.xor(&y5, &y8);
+    let t1 = g.xor(x4, &y12);
+    let y15 = g.xor(&t1, x5);
+    let y20 = g.xor(&t1, x1);
+    let y6 = g.xor(&y15, x7);
+    let y10 = g.xor(&y15, &t0);
+    let y11 = g.xor(&y20, &y9);
+    let y7 = g.xor(x7, &y11);
+    let y17 = g.xor(&y10, &y11);
+    let y19 = g.xor(&y10, &y8);
+    let y16 = g.xor(&t0, &y11);
+    let y21 = g.xor(&y13, &y16);
+    let y18 = g.xor(x0, &y16);
 
     // Return the result vector directly
     vec![
-        x7, y1, y2, y3, y4, y5, y6, y7, y8, y9, y10, y11, y12, y13, y14, 
-        y15, y16, y17, y18, y19, y20, y21
+        x7.clone(), y1, y2, y3, y4, y5, y6, y7, y8, y9, y10, y11, y12, y13, y14, y15, y16, y17,
+        y18, y19, y20, y21
     ]
 }
 
 
 
-pub fn clear_post_circuit(x : &Vec<bool>)->Vec<bool>{
-    let t46 = x[15] ^ x[16];
-    let t47 = x[10] ^ x[11];
-    let t48 = x[5] ^ x[13];
-    let t49 = x[9] ^ x[10];
-    let t50 = x[2] ^ x[12];
-    let t51 = x[2] ^ x[5];
-    let t52 = x[7] ^ x[8];
-    let t53 = x[0] ^ x[3];
-    let t54 = x[6] ^ x[7];
-    let t55 = x[16] ^ x[17];
-    let t56 = x[12] ^ t48;
-    let t57 = t50 ^ t53;
-    let t58 = x[4] ^ t46;
-    let t59 = x[3] ^ t54;
-    let t60 = t46 ^ t57;
-    let t61 = x[14] ^ t57;
-    let t62 = t52 ^ t58;
-    let t63 = t49 ^ t58;
-    let t64 = x[4] ^ t59;
-    let t65 = t61 ^ t62;
-    let t66 = x[1] ^ t63;
-    let y0 = t59 ^ t63;
-    let y6 = !(t56 ^ t62);
-    let y7 = !(t48 ^ t60);
-    let t67 = t64 ^ t65;
-    let y3 = t53 ^ t66;
-    let y4 = t51 ^ t66;
-    let y5 = t47 ^ t65;
-    let y1 = !(t64 ^ y3);
-    let y2 = !(t55 ^ t67);
+
+/// The bottom linear layer of the Boyar–Peralta S-box, the output byte being most significant bit
+/// first.
+pub fn post_circuit<G: BitGates>(g: &G, x: &[G::Bit]) -> Vec<G::Bit> {
+    let t46 = g.xor(&x[15], &x[16]);
+    let t47 = g.xor(&x[10], &x[11]);
+    let t48 = g.xor(&x[5], &x[13]);
+    let t49 = g.xor(&x[9], &x[10]);
+    let t50 = g.xor(&x[2], &x[12]);
+    let t51 = g.xor(&x[2], &x[5]);
+    let t52 = g.xor(&x[7], &x[8]);
+    let t53 = g.xor(&x[0], &x[3]);
+    let t54 = g.xor(&x[6], &x[7]);
+    let t55 = g.xor(&x[16], &x[17]);
+    let t56 = g.xor(&x[12], &t48);
+    let t57 = g.xor(&t50, &t53);
+    let t58 = g.xor(&x[4], &t46);
+    let t59 = g.xor(&x[3], &t54);
+    let t60 = g.xor(&t46, &t57);
+    let t61 = g.xor(&x[14], &t57);
+    let t62 = g.xor(&t52, &t58);
+    let t63 = g.xor(&t49, &t58);
+    let t64 = g.xor(&x[4], &t59);
+    let t65 = g.xor(&t61, &t62);
+    let t66 = g.xor(&x[1], &t63);
+    let y0 = g.xor(&t59, &t63);
+    let y6 = g.not(&g.xor(&t56, &t62));
+    let y7 = g.not(&g.xor(&t48, &t60));
+    let t67 = g.xor(&t64, &t65);
+    let y3 = g.xor(&t53, &t66);
+    let y4 = g.xor(&t51, &t66);
+    let y5 = g.xor(&t47, &t65);
+    let y1 = g.not(&g.xor(&t64, &y3));
+    let y2 = g.not(&g.xor(&t55, &t67));
     vec![y0, y1, y2, y3, y4, y5, y6, y7]
 }
 
 
 
 
-pub fn clear_mixcolumns(x: &[bool]) -> Vec<bool> {
+/// MixColumns on a column of 4 bytes, each byte being least significant bit first.
+pub fn mixcolumns<G: BitGates>(g: &G, x: &[G::Bit]) -> Vec<G::Bit> {
     assert_eq!(x.len(), 32);
 
     // Temporary variables reused multiple times
-    let t0 = x[0] ^ x[8];
-    let t1 = x[16] ^ x[24];
-    let t2 = x[1] ^ x[9];
-    let t3 = x[17] ^ x[25];
-    let t4 = x[2] ^ x[10];
-    let t5 = x[18] ^ x[26];
-    let t6 = x[3] ^ x[11];
-    let t7 = x[19] ^ x[27];
-    let t8 = x[4] ^ x[12];
-    let t9 = x[20] ^ x[28];
-    let t10 = x[5] ^ x[13];
-    let t11 = x[21] ^ x[29];
-    let t12 = x[6] ^ x[14];
-    let t13 = x[22] ^ x[30];
-    let t14 = x[23] ^ x[31];
-    let t15 = x[7] ^ x[15];
+    let t0 = g.xor(&x[0], &x[8]);
+    let t1 = g.xor(&x[16], &x[24]);
+    let t2 = g.xor(&x[1], &x[9]);
+    let t3 = g.xor(&x[17], &x[25]);
+    let t4 = g.xor(&x[2], &x[10]);
+    let t5 = g.xor(&x[18], &x[26]);
+    let t6 = g.xor(&x[3], &x[11]);
+    let t7 = g.xor(&x[19], &x[27]);
+    let t8 = g.xor(&x[4], &x[12]);
+    let t9 = g.xor(&x[20], &x[28]);
+    let t10 = g.xor(&x[5], &x[13]);
+    let t11 = g.xor(&x[21], &x[29]);
+    let t12 = g.xor(&x[6], &x[14]);
+    let t13 = g.xor(&x[22], &x[30]);
+    let t14 = g.xor(&x[23], &x[31]);
+    let t15 = g.xor(&x[7], &x[15]);
 
     // Derived and reused intermediate values
-    let t16 = x[8] ^ t1;
-    let y0 = t15 ^ t16;
+    let t16 = g.xor(&x[8], &t1);
+    let y0 = g.xor(&t15, &t16);
 
-    let t17 = x[7] ^ x[23];
-    let t18 = x[24] ^ t0;
-    let y16 = t14 ^ t18;
+    let t17 = g.xor(&x[7], &x[23]);
+    let t18 = g.xor(&x[24], &t0);
+    let y16 = g.xor(&t14, &t18);
 
-    let t19 = t1 ^ y16;
-    let y24 = t17 ^ t19;
+    let t19 = g.xor(&t1, &y16);
+    let y24 = g.xor(&t17, &t19);
 
-    let t20 = x[27] ^ t14;
-    let t21 = t0 ^ y0;
-    let y8 = t17 ^ t21;
+    let t20 = g.xor(&x[27], &t14);
+    let t21 = g.xor(&t0, &y0);
+    let y8 = g.xor(&t17, &t21);
 
-    let t22 = t5 ^ t20;
-    let y19 = t6 ^ t22;
+    let t22 = g.xor(&t5, &t20);
+    let y19 = g.xor(&t6, &t22);
 
-    let t23 = x[11] ^ t15;
-    let t24 = t7 ^ t23;
-    let y3 = t4 ^ t24;
+    let t23 = g.xor(&x[11], &t15);
+    let t24 = g.xor(&t7, &t23);
+    let y3 = g.xor(&t4, &t24);
 
-    let t25 = x[2] ^ x[18];
-    let t26 = t17 ^ t25;
-    let t27 = t9 ^ t23;
-    let t28 = t8 ^ t20;
-    let t29 = x[10] ^ t2;
-    let y2 = t5 ^ t29;
+    let t25 = g.xor(&x[2], &x[18]);
+    let t26 = g.xor(&t17, &t25);
+    let t27 = g.xor(&t9, &t23);
+    let t28 = g.xor(&t8, &t20);
+    let t29 = g.xor(&x[10], &t2);
+    let y2 = g.xor(&t5, &t29);
 
-    let t30 = x[26] ^ t3;
-    let y18 = t4 ^ t30;
+    let t30 = g.xor(&x[26], &t3);
+    let y18 = g.xor(&t4, &t30);
 
-    let t31 = x[9] ^ x[25];
-    let t32 = t25 ^ t31;
-    let y10 = t30 ^ t32;
-    let y26 = t29 ^ t32;
+    let t31 = g.xor(&x[9], &x[25]);
+    let t32 = g.xor(&t25, &t31);
+    let y10 = g.xor(&t30, &t32);
+    let y26 = g.xor(&t29, &t32);
 
-    let t33 = x[1] ^ t18;
-    let t34 = x[30] ^ t11;
-    let y22 = t12 ^ t34;
+    let t33 = g.xor(&x[1], &t18);
+    let t34 = g.xor(&x[30], &t11);
+    let y22 = g.xor(&t12, &t34);
 
-    let t35 = x[14] ^ t13;
-    let y6 = t10 ^ t35;
+    let t35 = g.xor(&x[14], &t13);
+    let y6 = g.xor(&t10, &t35);
 
-    let t36 = x[5] ^ x[21];
-    let t37 = x[30] ^ t17;
-    let t38 = x[17] ^ t16;
-    let t39 = x[13] ^ t8;
-    let y5 = t11 ^ t39;
+    let t36 = g.xor(&x[5], &x[21]);
+    let t37 = g.xor(&x[30], &t17);
+    let t38 = g.xor(&x[17], &t16);
+    let t39 = g.xor(&x[13], &t8);
+    let y5 = g.xor(&t11, &t39);
 
-    let t40 = x[12] ^ t36;
-    let t41 = x[29] ^ t9;
-    let y21 = t10 ^ t41;
+    let t40 = g.xor(&x[12], &t36);
+    let t41 = g.xor(&x[29], &t9);
+    let y21 = g.xor(&t10, &t41);
 
-    let t42 = x[28] ^ t40;
-    let y13 = t41 ^ t42;
-    let y29 = t39 ^ t42;
+    let t42 = g.xor(&x[28], &t40);
+    let y13 = g.xor(&t41, &t42);
+    let y29 = g.xor(&t39, &t42);
 
-    let t43 = x[15] ^ t12;
-    let y7 = t14 ^ t43;
+    let t43 = g.xor(&x[15], &t12);
+    let y7 = g.xor(&t14, &t43);
 
-    let t44 = x[14] ^ t37;
-    let y31 = t43 ^ t44;
+    let t44 = g.xor(&x[14], &t37);
+    let y31 = g.xor(&t43, &t44);
 
-    let t45 = x[31] ^ t13;
-    let y15 = t44 ^ t45;
-    let y23 = t15 ^ t45;
+    let t45 = g.xor(&x[31], &t13);
+    let y15 = g.xor(&t44, &t45);
+    let y23 = g.xor(&t15, &t45);
 
-    let t46 = t12 ^ t36;
-    let y14 = y6 ^ t46;
+    let t46 = g.xor(&t12, &t36);
+    let y14 = g.xor(&y6, &t46);
 
-    let t47 = t31 ^ t33;
-    let y17 = t19 ^ t47;
+    let t47 = g.xor(&t31, &t33);
+    let y17 = g.xor(&t19, &t47);
 
-    let t48 = t6 ^ y3;
-    let y11 = t26 ^ t48;
+    let t48 = g.xor(&t6, &y3);
+    let y11 = g.xor(&t26, &t48);
 
-    let t49 = t2 ^ t38;
-    let y25 = y24 ^ t49;
+    let t49 = g.xor(&t2, &t38);
+    let y25 = g.xor(&y24, &t49);
 
-    let t50 = t7 ^ y19;
-    let y27 = t26 ^ t50;
+    let t50 = g.xor(&t7, &y19);
+    let y27 = g.xor(&t26, &t50);
 
-    let t51 = x[22] ^ t46;
-    let y30 = t11 ^ t51;
+    let t51 = g.xor(&x[22], &t46);
+    let y30 = g.xor(&t11, &t51);
 
-    let t52 = x[19] ^ t28;
-    let y20 = x[28] ^ t52;
+    let t52 = g.xor(&x[19], &t28);
+    let y20 = g.xor(&x[28], &t52);
 
-    let t53 = x[3] ^ t27;
-    let y4 = x[12] ^ t53;
+    let t53 = g.xor(&x[3], &t27);
+    let y4 = g.xor(&x[12], &t53);
 
-    let t54 = t3 ^ t33;
-    let y9 = y8 ^ t54;
+    let t54 = g.xor(&t3, &t33);
+    let y9 = g.xor(&y8, &t54);
 
-    let t55 = t21 ^ t31;
-    let y1 = t38 ^ t55;
+    let t55 = g.xor(&t21, &t31);
+    let y1 = g.xor(&t38, &t55);
 
-    let t56 = x[4] ^ t17;
-    let t57 = x[19] ^ t56;
-    let y12 = t27 ^ t57;
+    let t56 = g.xor(&x[4], &t17);
+    let t57 = g.xor(&x[19], &t56);
+    let y12 = g.xor(&t27, &t57);
 
-    let t58 = x[3] ^ t28;
-    let t59 = t17 ^ t58;
-    let y28 = x[20] ^ t59;
+    let t58 = g.xor(&x[3], &t28);
+    let t59 = g.xor(&t17, &t58);
+    let y28 = g.xor(&x[20], &t59);
 
     // Construct result vector
     vec![
-        y0, y1, y2, y3, y4, y5, y6, y7,
-        y8, y9, y10, y11, y12, y13, y14, y15,
-        y16, y17, y18, y19, y20, y21, y22, y23,
-        y24, y25, y26, y27, y28, y29, y30, y31,
+        y0, y1, y2, y3, y4, y5, y6, y7, y8, y9, y10, y11, y12, y13, y14, y15, y16, y17, y18, y19,
+        y20, y21, y22, y23, y24, y25, y26, y27, y28, y29, y30, y31
     ]
 }
 
 
 
 
+pub fn clear_s_box_boyar(y: &Vec<bool>) -> Vec<bool> {
+    s_box_boyar(&Clear, y)
+}
+
+
+pub fn clear_pre_circuit(x: &Vec<bool>) -> Vec<bool> {
+    pre_circuit(&Clear, x)
+}
+
+
+pub fn clear_post_circuit(x : &Vec<bool>)->Vec<bool>{
+    post_circuit(&Clear, x)
+}
+
+
+pub fn clear_mixcolumns(x: &[bool]) -> Vec<bool> {
+    mixcolumns(&Clear, x)
+}
+
+
+
+
 const S_BOX: [u8; 256] = [
     0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
     0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
@@ -329,3 +391,60 @@ pub fn clear_sub_bytes(x: u64) -> u64 {
     debug_assert!(x < 256);
     S_BOX[x as usize].into()
 }
+
+
+
+#[test]
+fn test_boyar_peralta_s_box(){
+    for x in 0..256u64{
+        let bits : Vec<bool> = (0..8).map(|i| (x >> (7 - i)) % 2 == 1).collect();
+        let s_bits = clear_post_circuit(&clear_s_box_boyar(&clear_pre_circuit(&bits)));
+        let s = s_bits.iter().fold(0, |acc, b| 2 * acc + *b as u64);
+        assert_eq!(s, clear_sub_bytes(x));
+    }
+}
+
+
+
+fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if x & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+
+/// AES-128 on bytes in the clear, the state being read column by column as in FIPS-197.
+pub fn clear_aes(block: &[u8; 16], key: &[u8; 16]) -> [u8; 16] {
+    let key_bits: Vec<bool> = key.iter().flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)).collect();
+    let round_keys: Vec<Vec<u8>> = key_expansion(key_bits)
+        .iter()
+        .map(|round_key| round_key.chunks(8).map(|byte| byte.iter().fold(0, |acc, b| 2 * acc + *b as u8)).collect())
+        .collect();
+    let add_round_key = |state: &mut [u8; 16], round_key: &[u8]| state.iter_mut().zip(round_key).for_each(|(s, k)| *s ^= k);
+
+    let mut state = *block;
+    add_round_key(&mut state, &round_keys[0]);
+    for r in 1..11 {
+        state = state.map(|byte| clear_sub_bytes(byte as u64) as u8);
+        state = core::array::from_fn(|i| state[(i + 4 * (i % 4)) % 16]);
+        if r < 10 {
+            for column in state.chunks_mut(4) {
+                let all = column.iter().fold(0, |acc, byte| acc ^ byte);
+                let first = column[0];
+                for i in 0..4 {
+                    let next = if i == 3 { first } else { column[i + 1] };
+                    column[i] ^= all ^ xtime(column[i] ^ next);
+                }
+            }
+        }
+        add_round_key(&mut state, &round_keys[r]);
+    }
+    state
+}
+
+
+#[test]
+fn test_clear_aes(){
+    let block = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff];
+    let key = core::array::from_fn(|i| i as u8);
+    let expected = [0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a];
+    assert_eq!(clear_aes(&block, &key), expected);
+}
@@ -0,0 +1,239 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::ParallelSlice;
+use tfhe::shortint::server_key::{BivariateLookupTableOwned, LookupTableOwned};
+use tfhe::shortint::{Ciphertext, ClientKey, ServerKey};
+
+use super::backend::AESBackend;
+use super::clear::clear_sub_bytes;
+
+
+/// The AES state as 32 shortint nibbles, each byte being its most significant nibble followed by
+/// the least significant one, the bytes in the same order as [`super::AESStateBoolean`].
+///
+/// Every operation is a lookup table on one or two nibbles, so the messages take 4 bits and the
+/// bivariate PBS needs 4 more bits of carry.
+pub struct AESStateNibbles{
+    pub nibbles : Vec<Ciphertext>
+}
+
+
+impl AESStateNibbles{
+    pub fn tfhe_encryption_bits(m : &[u64], client_key : &ClientKey) -> Self{
+        assert_eq!(m.len(), 128);
+        assert!(client_key.parameters.message_modulus().0 >= 16);
+        Self { nibbles : m.chunks(4)
+                            .map(|bits| client_key.encrypt(bits.iter().fold(0, |acc, b| 2 * acc + b)))
+                            .collect()
+        }
+    }
+
+
+    pub fn tfhe_decryption_bits(&self, client_key : &ClientKey) -> Vec<u64>{
+        self.nibbles.iter()
+                    .flat_map(|c| {
+                        let nibble = client_key.decrypt(c);
+                        (0..4).rev().map(move |i| (nibble >> i) & 1)
+                    })
+                    .collect()
+    }
+
+
+    //getter
+    pub fn byte_getter(&self, row : usize, col : usize) -> &[Ciphertext]{
+        &self.nibbles[(col * 4 + row) * 2..(col * 4 + row + 1) * 2]
+    }
+}
+
+
+/// The lookup tables of the rounds, generated once for the whole evaluation.
+struct AESLookupTables{
+    sbox : [BivariateLookupTableOwned;2],
+    xtime : [BivariateLookupTableOwned;2],
+    xor : BivariateLookupTableOwned,
+    // the xor with every constant nibble
+    xor_constant : Vec<LookupTableOwned>,
+}
+
+
+impl AESLookupTables{
+    fn new(server_key : &ServerKey) -> Self{
+        let xtime = |x : u64| if x & 0x80 != 0 {((x << 1) ^ 0x1b) & 0xff} else {x << 1};
+        AESLookupTables{
+            sbox : [
+                server_key.generate_lookup_table_bivariate(|hi, lo| clear_sub_bytes(16 * hi + lo) >> 4),
+                server_key.generate_lookup_table_bivariate(|hi, lo| clear_sub_bytes(16 * hi + lo) & 15),
+            ],
+            xtime : [
+                server_key.generate_lookup_table_bivariate(move |hi, lo| xtime(16 * hi + lo) >> 4),
+                server_key.generate_lookup_table_bivariate(move |hi, lo| xtime(16 * hi + lo) & 15),
+            ],
+            xor : server_key.generate_lookup_table_bivariate(|x, y| x ^ y),
+            xor_constant : (0..16).map(|k| server_key.generate_lookup_table(move |x| x ^ k)).collect(),
+        }
+    }
+}
+
+
+
+fn xor(a : &Ciphertext, b : &Ciphertext, server_key : &ServerKey, luts : &AESLookupTables) -> Ciphertext{
+    server_key.apply_lookup_table_bivariate(a, b, &luts.xor)
+}
+
+
+// A byte to byte function, one bivariate PBS per output nibble
+fn byte_lut(byte : &[Ciphertext], tables : &[BivariateLookupTableOwned;2], server_key : &ServerKey) -> Vec<Ciphertext>{
+    tables.par_iter()
+          .map(|lut| server_key.apply_lookup_table_bivariate(&byte[0], &byte[1], lut))
+          .collect()
+}
+
+
+
+fn sub_bytes(state : &AESStateNibbles, server_key : &ServerKey, luts : &AESLookupTables) -> AESStateNibbles{
+    AESStateNibbles {
+        nibbles : (0..16).into_par_iter()
+                    .map(|i| byte_lut(&state.nibbles[i*2..(i+1)*2], &luts.sbox, server_key))
+                    .collect::<Vec<Vec<Ciphertext>>>()
+                    .concat()
+    }
+}
+
+
+
+fn add_round_key(state : &AESStateNibbles, round_key : &[bool], server_key : &ServerKey, luts : &AESLookupTables) -> AESStateNibbles{
+    assert_eq!(state.nibbles.len(), 32);
+    assert_eq!(round_key.len(), 128);
+    AESStateNibbles { nibbles: state.nibbles.par_iter()
+                                .zip(round_key.par_chunks(4))
+                                .map(|(c, k)| match k.iter().fold(0, |acc, b| 2 * acc + *b as usize){
+                                    0 => c.to_owned(),
+                                    k => server_key.apply_lookup_table(c, &luts.xor_constant[k]),
+                                })
+                                .collect()
+            }
+}
+
+
+
+fn shift_rows(state : &AESStateNibbles) -> AESStateNibbles{
+    AESStateNibbles { nibbles: (0..4).map(|col|
+        (0..4).map(|row|
+            state.byte_getter(row, (col + row) % 4).to_vec()
+        ).collect::<Vec<Vec<Ciphertext>>>().concat()
+    ).collect::<Vec<Vec<Ciphertext>>>().concat()
+    }
+}
+
+
+
+// b_r = a_r ^ t ^ xtime(a_r ^ a_{r+1}) with t = a_0 ^ a_1 ^ a_2 ^ a_3
+fn mix_column(column : &[Ciphertext], server_key : &ServerKey, luts : &AESLookupTables) -> Vec<Ciphertext>{
+    let byte = |r : usize| &column[(r % 4) * 2..(r % 4 + 1) * 2];
+    let xor_bytes = |a : &[Ciphertext], b : &[Ciphertext]| -> Vec<Ciphertext>{
+        a.par_iter().zip(b.par_iter()).map(|(x, y)| xor(x, y, server_key, luts)).collect()
+    };
+
+    let (t, sums) = rayon::join(
+        || {
+            let (t01, t23) = rayon::join(|| xor_bytes(byte(0), byte(1)), || xor_bytes(byte(2), byte(3)));
+            xor_bytes(&t01, &t23)
+        },
+        || (0..4).into_par_iter()
+                 .map(|r| byte_lut(&xor_bytes(byte(r), byte(r + 1)), &luts.xtime, server_key))
+                 .collect::<Vec<Vec<Ciphertext>>>()
+    );
+
+    (0..4).into_par_iter()
+          .map(|r| xor_bytes(&xor_bytes(byte(r), &t), &sums[r]))
+          .collect::<Vec<Vec<Ciphertext>>>()
+          .concat()
+}
+
+
+fn mix_columns(state : &AESStateNibbles, server_key : &ServerKey, luts : &AESLookupTables) -> AESStateNibbles{
+    AESStateNibbles {
+        nibbles : (0..4).into_par_iter()
+                    .map(|col| mix_column(&state.nibbles[col*8..(col + 1)*8], server_key, luts))
+                    .collect::<Vec<Vec<Ciphertext>>>()
+                    .concat()
+    }
+}
+
+
+
+/// Full lookup table evaluation, the state keeps the same representation for the S-box.
+pub struct FullLutBackend<'a>{
+    pub server_key : &'a ServerKey,
+    luts : AESLookupTables,
+}
+
+
+impl<'a> FullLutBackend<'a>{
+    pub fn new(server_key : &'a ServerKey) -> Self{
+        FullLutBackend { server_key, luts : AESLookupTables::new(server_key) }
+    }
+}
+
+
+impl AESBackend for FullLutBackend<'_>{
+    type ClientKey = ClientKey;
+    type State = AESStateNibbles;
+    type SBoxState = AESStateNibbles;
+
+    fn encrypt(m : &[u64], client_key : &ClientKey) -> AESStateNibbles{
+        AESStateNibbles::tfhe_encryption_bits(m, client_key)
+    }
+
+    fn decrypt(state : &AESStateNibbles, client_key : &ClientKey) -> Vec<u64>{
+        state.tfhe_decryption_bits(client_key)
+    }
+
+    fn add_round_key(&self, state : &AESStateNibbles, round_key : &[bool]) -> AESStateNibbles{
+        add_round_key(state, round_key, self.server_key, &self.luts)
+    }
+
+    fn shift_rows(&self, state : &AESStateNibbles) -> AESStateNibbles{
+        shift_rows(state)
+    }
+
+    fn mix_columns(&self, state : &AESStateNibbles) -> AESStateNibbles{
+        mix_columns(state, self.server_key, &self.luts)
+    }
+
+    fn to_sbox_state(&self, state : AESStateNibbles) -> AESStateNibbles{
+        state
+    }
+
+    fn sub_bytes(&self, state : &AESStateNibbles) -> AESStateNibbles{
+        sub_bytes(state, self.server_key, &self.luts)
+    }
+
+    fn leave_sbox_state(&self, state : AESStateNibbles) -> AESStateNibbles{
+        state
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use tfhe::shortint::keycache::KEY_CACHE;
+    use tfhe::shortint::parameters::V0_11_PARAM_MESSAGE_4_CARRY_4_KS_PBS_GAUSSIAN_2M64;
+    use crate::aes::{format_output, run_aes, test_vector_bits, timing::TimingReport, EXPECTED_OUTPUT};
+
+    // A few thousand PBS with 8 bits of message and carry
+    #[test]
+    #[ignore]
+    fn test_full_lut_run_aes(){
+        let keys = KEY_CACHE.get_from_param(V0_11_PARAM_MESSAGE_4_CARRY_4_KS_PBS_GAUSSIAN_2M64);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        let (plaintext_bits, aes_key_bits) = test_vector_bits();
+        let state = FullLutBackend::encrypt(&plaintext_bits, client_key);
+        let mut report = TimingReport::new("test");
+        let result = run_aes(&state, &FullLutBackend::new(server_key), aes_key_bits, &mut report);
+
+        assert_eq!(format_output(&FullLutBackend::decrypt(&result, client_key)), EXPECTED_OUTPUT);
+    }
+}
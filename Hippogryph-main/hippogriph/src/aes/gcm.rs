@@ -0,0 +1,430 @@
+use std::time::Instant;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tfhe::keycache::NamedParam;
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use super::aes_utils::{u8_to_vec_bool, RC};
+use super::clear::Clear;
+use super::timing::{Layer, TimingReport};
+use super::{aes_rounds, sub_bytes, AESStateBoolean, OddHybridBackend};
+
+
+/// The bit operations GHASH is written with. A block of 128 bits is stored as in GCM : the bit i is
+/// the coefficient of x^i, that is the bit `7 - i % 8` of the byte `i / 8`, as for the AES state.
+pub trait GhashGates : Sync{
+    type Bit : Clone + Send + Sync;
+
+    fn xor(&self, a : &Self::Bit, b : &Self::Bit) -> Self::Bit;
+
+    fn not(&self, a : &Self::Bit) -> Self::Bit;
+
+    fn constant(&self, value : bool) -> Self::Bit;
+
+    /// The products `x[i] AND h[j]`, indexed by i then j.
+    fn and_table(&self, x : &[Self::Bit], h : &[Self::Bit]) -> Vec<Vec<Self::Bit>>;
+}
+
+
+impl GhashGates for Clear{
+    type Bit = bool;
+
+    fn xor(&self, a : &bool, b : &bool) -> bool{
+        a ^ b
+    }
+
+    fn not(&self, a : &bool) -> bool{
+        !a
+    }
+
+    fn constant(&self, value : bool) -> bool{
+        value
+    }
+
+    fn and_table(&self, x : &[bool], h : &[bool]) -> Vec<Vec<bool>>{
+        x.iter().map(|x_i| h.iter().map(|h_j| x_i & h_j).collect()).collect()
+    }
+}
+
+
+/// GHASH on odd : the bits are parity encoded, so that the XORs are free, and each AND of the
+/// multiplications is a gadget in Z3 whose inputs are switched once per multiplication.
+pub struct OddGhashGates<'a>{
+    pub server_key : &'a ServerKey,
+}
+
+
+impl OddGhashGates<'_>{
+    // from the parity encoding, {1, 2} is reached in Z3, then shifted to {0, 1}
+    fn to_gadget_input(&self, bits : &[Ciphertext]) -> Vec<Ciphertext>{
+        let encoding = Encoding::new_canonical(2, vec![1, 2], 3);
+        bits.par_iter()
+            .map(|bit| {
+                let switched = self.server_key.encoding_switching_lut(bit, &encoding);
+                self.server_key.encoding_switching_sum_constant(&switched, 2, 3)
+            })
+            .collect()
+    }
+}
+
+
+impl GhashGates for OddGhashGates<'_>{
+    type Bit = Ciphertext;
+
+    fn xor(&self, a : &Ciphertext, b : &Ciphertext) -> Ciphertext{
+        self.server_key.simple_sum(&vec![a.to_owned(), b.to_owned()])
+    }
+
+    fn not(&self, a : &Ciphertext) -> Ciphertext{
+        self.server_key.simple_plaintext_sum(a, 1, 2)
+    }
+
+    fn constant(&self, value : bool) -> Ciphertext{
+        self.server_key.trivial_encrypt_arithmetic(value as u64, &Encoding::parity_encoding())
+    }
+
+    fn and_table(&self, x : &[Ciphertext], h : &[Ciphertext]) -> Vec<Vec<Ciphertext>>{
+        let (x, h) = rayon::join(|| self.to_gadget_input(x), || self.to_gadget_input(h));
+        let and = Gadget::and();
+        x.par_iter()
+         .map(|x_i| h.par_iter()
+                     .map(|h_j| self.server_key.apply_gadget(&and, &[x_i.to_owned(), h_j.to_owned()], &Encoding::parity_encoding()))
+                     .collect())
+         .collect()
+    }
+}
+
+
+
+// Reduces a product of degree at most 254 modulo x^128 + x^7 + x^2 + x + 1, with XORs only
+fn reduce<G : GhashGates>(gates : &G, mut z : Vec<G::Bit>) -> Vec<G::Bit>{
+    for k in (128..z.len()).rev(){
+        for shift in [7, 2, 1, 0]{
+            z[k - 128 + shift] = gates.xor(&z[k - 128 + shift], &z[k]);
+        }
+    }
+    z.truncate(128);
+    z
+}
+
+
+/// The product of two encrypted blocks in GF(2^128) : 128 x 128 ANDs, the rest being XORs.
+pub fn gf128_mul<G : GhashGates>(gates : &G, x : &[G::Bit], h : &[G::Bit]) -> Vec<G::Bit>{
+    assert_eq!(x.len(), 128);
+    assert_eq!(h.len(), 128);
+    let products = gates.and_table(x, h);
+    let z = (0..255usize).map(|k| {
+                        let i_range = k.saturating_sub(127)..=k.min(127);
+                        i_range.map(|i| &products[i][k - i])
+                               .fold(gates.constant(false), |acc, p| gates.xor(&acc, p))
+                    })
+                    .collect();
+    reduce(gates, z)
+}
+
+
+/// The product of a clear block and an encrypted one, which is linear.
+pub fn gf128_mul_clear<G : GhashGates>(gates : &G, x : &[bool], h : &[G::Bit]) -> Vec<G::Bit>{
+    assert_eq!(x.len(), 128);
+    assert_eq!(h.len(), 128);
+    let z = (0..255usize).map(|k| {
+                        let i_range = k.saturating_sub(127)..=k.min(127);
+                        i_range.filter(|i| x[*i])
+                               .fold(gates.constant(false), |acc, i| gates.xor(&acc, &h[k - i]))
+                    })
+                    .collect();
+    reduce(gates, z)
+}
+
+
+fn xor_clear<G : GhashGates>(gates : &G, a : &[G::Bit], b : &[bool]) -> Vec<G::Bit>{
+    a.iter().zip(b).map(|(a, b)| if *b {gates.not(a)} else {a.to_owned()}).collect()
+}
+
+
+// most significant bit of each byte first, the last block being padded with zeros
+fn bytes_to_blocks(bytes : &[u8]) -> Vec<Vec<bool>>{
+    bytes.chunks(16)
+         .map(|block| (0..128).map(|i| block.get(i / 8).is_some_and(|byte| (byte >> (7 - i % 8)) & 1 == 1)).collect())
+         .collect()
+}
+
+
+/// GHASH of the public additional data and ciphertext of a GCM message under an encrypted hash
+/// subkey H, by Horner's rule. The first block is clear, so its multiplication by H is free.
+pub fn ghash<G : GhashGates>(gates : &G, h : &[G::Bit], aad : &[u8], ciphertext : &[u8]) -> Vec<G::Bit>{
+    let lengths = [(8 * aad.len() as u64).to_be_bytes(), (8 * ciphertext.len() as u64).to_be_bytes()].concat();
+    let blocks = [bytes_to_blocks(aad), bytes_to_blocks(ciphertext), bytes_to_blocks(&lengths)].concat();
+
+    let mut y = gf128_mul_clear(gates, &blocks[0], h);
+    for block in &blocks[1..]{
+        y = gf128_mul(gates, &xor_clear(gates, &y, block), h);
+    }
+    y
+}
+
+
+/// The GCM tag : GHASH masked by the encryption of the initial counter block.
+pub fn gcm_tag<G : GhashGates>(gates : &G, h : &[G::Bit], encrypted_j0 : &[G::Bit], aad : &[u8], ciphertext : &[u8]) -> Vec<G::Bit>{
+    let s = ghash(gates, h, aad, ciphertext);
+    s.iter().zip(encrypted_j0).map(|(s, e)| gates.xor(s, e)).collect()
+}
+
+
+/// The counter blocks of a 96-bit IV : the initial one, for the tag, then one per block of data.
+pub fn counter_blocks(iv : &[u8;12], block_count : usize) -> Vec<[u8;16]>{
+    (1..=block_count as u32 + 1).map(|counter| {
+                                    let mut block = [0;16];
+                                    block[..12].copy_from_slice(iv);
+                                    block[12..].copy_from_slice(&counter.to_be_bytes());
+                                    block
+                                })
+                                .collect()
+}
+
+
+
+fn xor_bits(server_key : &ServerKey, a : &[Ciphertext], b : &[Ciphertext]) -> Vec<Ciphertext>{
+    a.par_iter().zip(b.par_iter()).map(|(a, b)| server_key.simple_sum(&vec![a.to_owned(), b.to_owned()])).collect()
+}
+
+
+/// The AES key schedule on an encrypted key in the parity encoding : the XORs are sums, and
+/// SubWord goes through the full tree bootstrapping of SubBytes, one word per round. The round
+/// constants are public.
+pub fn encrypted_key_expansion(key : &[Ciphertext], server_key : &ServerKey) -> Vec<Vec<Ciphertext>>{
+    assert_eq!(key.len(), 128);
+    let mut words : Vec<Vec<Ciphertext>> = key.chunks(32).map(|word| word.to_vec()).collect();
+    for i in 4..44{
+        let last = if i % 4 == 0{
+            let mut rotated = words[i - 1].clone();
+            rotated.rotate_left(8);
+            let nibbles = AESStateBoolean { bits : rotated }.aes_recomposer(server_key);
+            let mut substituted = sub_bytes(&nibbles, server_key).aes_decomposer(server_key).bits;
+            for (bit, rc) in substituted.iter_mut().zip(u8_to_vec_bool(RC[i / 4] as u8)){
+                if rc{
+                    *bit = server_key.simple_plaintext_sum(bit, 1, 2);
+                }
+            }
+            substituted
+        }
+        else{
+            words[i - 1].clone()
+        };
+        let word = xor_bits(server_key, &words[i - 4], &last);
+        words.push(word);
+    }
+    words.chunks(4).map(|round_key| round_key.concat()).collect()
+}
+
+
+/// The AES of a public block under encrypted round keys. The block only flips bits of the first
+/// round key, and the other AddRoundKey layers are sums of parity bits.
+pub fn run_aes_encrypted_key(block : &[u8;16], round_keys : &[Vec<Ciphertext>], backend : &OddHybridBackend, report : &mut TimingReport) -> AESStateBoolean{
+    assert_eq!(round_keys.len(), 11);
+    let server_key = backend.server_key;
+
+    let state = report.time(0, Layer::AddRoundKey, || AESStateBoolean { bits : xor_clear(&OddGhashGates { server_key }, &round_keys[0], &bytes_to_blocks(block)[0]) });
+
+    aes_rounds(state,
+               backend,
+               &round_keys[1..],
+               |state : &AESStateBoolean, round_key : &Vec<Ciphertext>| AESStateBoolean { bits : xor_bits(server_key, &state.bits, round_key) },
+               Some(report))
+}
+
+
+
+pub struct GcmTestVector{
+    pub key : &'static str,
+    pub iv : &'static str,
+    pub plaintext : &'static str,
+    pub aad : &'static str,
+    pub ciphertext : &'static str,
+    pub tag : &'static str,
+}
+
+
+// Test cases 2 and 4 of the GCM specification of McGrew and Viega, the second one with additional
+// data and a partial last block
+pub const GCM_TEST_VECTORS : [GcmTestVector;2] = [
+    GcmTestVector {
+        key : "00000000000000000000000000000000",
+        iv : "000000000000000000000000",
+        plaintext : "00000000000000000000000000000000",
+        aad : "",
+        ciphertext : "0388dace60b6a392f328c2b971b2fe78",
+        tag : "ab6e47d42cec13bdf53a67b21257bddf",
+    },
+    GcmTestVector {
+        key : "feffe9928665731c6d6a8f9467308308",
+        iv : "cafebabefacedbaddecaf888",
+        plaintext : "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        aad : "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+        ciphertext : "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+        tag : "5bc94fbc3221a5db94fae95ae7121a47",
+    },
+];
+
+
+fn hex_to_bytes(hex : &str) -> Vec<u8>{
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+fn bits_to_hex(bits : &[bool]) -> String{
+    bits.chunks(8).map(|byte| format!("{:02x}", byte.iter().fold(0, |acc, b| 2 * acc + *b as u8))).collect()
+}
+
+
+
+/// Transciphers the GCM ciphertext of the second test vector. The client only sends its AES key,
+/// encrypted : the server expands it, derives the hash subkey H and the keystream from the public
+/// counter blocks with the odd AES, turns the public ciphertext into an encrypted plaintext and
+/// recomputes the tag. The client decrypts the tag and compares it to the one received with the
+/// message.
+pub fn demo_aes_gcm(){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let vector = &GCM_TEST_VECTORS[1];
+    let ciphertext = hex_to_bytes(vector.ciphertext);
+    let aad = hex_to_bytes(vector.aad);
+    let counters = counter_blocks(&hex_to_bytes(vector.iv).try_into().unwrap(), ciphertext.len().div_ceil(16));
+
+    // client side
+    let parity_encoding = Encoding::parity_encoding();
+    let key : Vec<Ciphertext> = bytes_to_blocks(&hex_to_bytes(vector.key))[0].iter()
+                                                                           .map(|b| client_key.encrypt_arithmetic(*b as u64, &parity_encoding))
+                                                                           .collect();
+
+    // server side
+    let backend = OddHybridBackend { server_key };
+    let gates = OddGhashGates { server_key };
+
+    let start = Instant::now();
+    let round_keys = encrypted_key_expansion(&key, server_key);
+    println!("Key expansion : {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let mut report = TimingReport::new(&parameters.name());
+    let h = run_aes_encrypted_key(&[0;16], &round_keys, &backend, &mut report).bits;
+    let keystream : Vec<Vec<Ciphertext>> = counters.iter()
+                                                   .map(|counter| run_aes_encrypted_key(counter, &round_keys, &backend, &mut report).bits)
+                                                   .collect();
+    println!("AES on H and {} counter blocks : {:?}", counters.len(), start.elapsed());
+
+    let n = 8 * ciphertext.len();
+    let plaintext = xor_clear(&gates, &keystream[1..].concat()[..n], &bytes_to_blocks(&ciphertext).concat()[..n]);
+
+    let start = Instant::now();
+    let tag = gcm_tag(&gates, &h, &keystream[0], &aad, &ciphertext);
+    println!("GCM tag : {:?}", start.elapsed());
+
+    // client side
+    let decrypt = |bits : &[Ciphertext]| bits_to_hex(&bits.iter().map(|c| client_key.decrypt(c) == 1).collect::<Vec<bool>>());
+    println!("Plaintext :");
+    println!("{}", decrypt(&plaintext));
+    println!("Expected:");
+    println!("{}", vector.plaintext);
+    let tag = decrypt(&tag);
+    println!("Tag : {}", tag);
+    println!("Received tag : {}", vector.tag);
+    println!("Authentic : {}", tag == vector.tag);
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::aes::aes_utils::key_expansion;
+    use crate::aes::clear::clear_aes;
+
+    #[test]
+    fn test_gcm_test_vectors(){
+        for vector in &GCM_TEST_VECTORS{
+            let key : [u8;16] = hex_to_bytes(vector.key).try_into().unwrap();
+            let plaintext = hex_to_bytes(vector.plaintext);
+            let h = bytes_to_blocks(&clear_aes(&[0;16], &key)).concat();
+
+            let counters = counter_blocks(&hex_to_bytes(vector.iv).try_into().unwrap(), plaintext.len().div_ceil(16));
+            let keystream : Vec<u8> = counters.iter().flat_map(|block| clear_aes(block, &key)).collect();
+            let ciphertext : Vec<u8> = plaintext.iter().zip(&keystream[16..]).map(|(p, k)| p ^ k).collect();
+            assert_eq!(ciphertext, hex_to_bytes(vector.ciphertext));
+
+            let encrypted_j0 = bytes_to_blocks(&keystream[..16]).concat();
+            let tag = gcm_tag(&Clear, &h, &encrypted_j0, &hex_to_bytes(vector.aad), &ciphertext);
+            assert_eq!(bits_to_hex(&tag), vector.tag);
+        }
+    }
+
+
+    #[test]
+    fn test_odd_gf128_mul(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let gates = OddGhashGates { server_key };
+
+        let x = bytes_to_blocks(&hex_to_bytes("0388dace60b6a392f328c2b971b2fe78")).concat();
+        let h = bytes_to_blocks(&hex_to_bytes("66e94bd4ef8a2c3b884cfa59ca342b2e")).concat();
+        let encrypt = |bits : &[bool]| bits.iter().map(|b| client_key.encrypt_arithmetic(*b as u64, &Encoding::parity_encoding())).collect::<Vec<Ciphertext>>();
+
+        let product = gf128_mul(&gates, &encrypt(&x), &encrypt(&h));
+        let product : Vec<bool> = product.iter().map(|c| client_key.decrypt(c) == 1).collect();
+        assert_eq!(product, gf128_mul(&Clear, &x, &h));
+    }
+
+    #[test]
+    fn test_encrypted_key_expansion(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        let key = bytes_to_blocks(&hex_to_bytes(GCM_TEST_VECTORS[1].key)).concat();
+        let encrypted_key : Vec<Ciphertext> = key.iter().map(|b| client_key.encrypt_arithmetic(*b as u64, &Encoding::parity_encoding())).collect();
+
+        let round_keys : Vec<Vec<bool>> = encrypted_key_expansion(&encrypted_key, server_key).iter()
+                                                                                              .map(|round_key| round_key.iter().map(|c| client_key.decrypt(c) == 1).collect())
+                                                                                              .collect();
+        assert_eq!(round_keys, key_expansion(key));
+    }
+
+    #[test]
+    fn test_run_aes_encrypted_key(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        let key : [u8;16] = hex_to_bytes(GCM_TEST_VECTORS[1].key).try_into().unwrap();
+        let block = counter_blocks(&hex_to_bytes(GCM_TEST_VECTORS[1].iv).try_into().unwrap(), 1)[1];
+        let round_keys : Vec<Vec<Ciphertext>> = key_expansion(bytes_to_blocks(&key).concat()).iter()
+                                                    .map(|round_key| round_key.iter().map(|b| client_key.encrypt_arithmetic(*b as u64, &Encoding::parity_encoding())).collect())
+                                                    .collect();
+
+        let mut report = TimingReport::new("test");
+        let result = run_aes_encrypted_key(&block, &round_keys, &OddHybridBackend { server_key }, &mut report);
+        let result : Vec<bool> = result.bits.iter().map(|c| client_key.decrypt(c) == 1).collect();
+        assert_eq!(result, bytes_to_blocks(&clear_aes(&block, &key)).concat());
+    }
+
+    // H and the encrypted initial counter block as they come out of the AES on the server
+    #[test]
+    fn test_odd_gcm_tag(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let gates = OddGhashGates { server_key };
+
+        let vector = &GCM_TEST_VECTORS[0];
+        let key : [u8;16] = hex_to_bytes(vector.key).try_into().unwrap();
+        let j0 = counter_blocks(&hex_to_bytes(vector.iv).try_into().unwrap(), 0)[0];
+        let encrypt = |bytes : &[u8]| bytes_to_blocks(bytes).concat()
+                                                        .iter()
+                                                        .map(|b| client_key.encrypt_arithmetic(*b as u64, &Encoding::parity_encoding()))
+                                                        .collect::<Vec<Ciphertext>>();
+        let h = encrypt(&clear_aes(&[0;16], &key));
+        let encrypted_j0 = encrypt(&clear_aes(&j0, &key));
+
+        let tag = gcm_tag(&gates, &h, &encrypted_j0, &hex_to_bytes(vector.aad), &hex_to_bytes(vector.ciphertext));
+        let tag : Vec<bool> = tag.iter().map(|c| client_key.decrypt(c) == 1).collect();
+        assert_eq!(bits_to_hex(&tag), vector.tag);
+    }
+}
@@ -1,6 +1,4 @@
 use tfhe::odd::prelude::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use once_cell::sync::Lazy;
 
 // A structure implementing the parsing of a "circuit file" from the papers and executing the circuit with the appropriate containers
@@ -20,7 +18,7 @@ struct CircuitOperation {
 }
 
 // Store circuit definition
-struct CircuitDefinition {
+pub struct CircuitDefinition {
     header: Vec<usize>, 
     operations: Vec<CircuitOperation>,
 }
@@ -183,119 +181,129 @@ static MIXCOLUMNS2_CIRCUIT: Lazy<CircuitDefinition> = Lazy::new(|| {
     }
 });
 
+impl CircuitDefinition {
+    /// Parses a circuit in the format of the circuit files : a header line with the number of
+    /// inputs, intermediate values and outputs, each followed by the offset of its indices, then
+    /// one operation per line, `t0 = x1 + x2`, `y0 = t0 XNOR x3`, or a copy `y1 = x0`.
+    pub fn parse(text: &str) -> Self {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header: Vec<usize> = lines.next().unwrap().split_whitespace().map(|n| n.parse::<usize>().unwrap()).collect();
+        assert_eq!(header.len(), 6, "Invalid circuit header");
+
+        let operations = lines.map(|line| {
+            let elmts: Vec<&str> = line.split_whitespace().collect();
+            match elmts.len() {
+                3 => CircuitOperation { target: elmts[0].to_string(), op1: elmts[2].to_string(), operation: "=".to_string(), op2: String::new() },
+                5 => CircuitOperation { target: elmts[0].to_string(), op1: elmts[2].to_string(), operation: elmts[3].to_string(), op2: elmts[4].to_string() },
+                _ => panic!("Invalid circuit line: {}", line),
+            }
+        }).collect();
+
+        Self { header, operations }
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.header[0]
+    }
+
+    /// Runs the circuit on clear bits.
+    #[cfg(test)]
+    pub fn evaluate_clear(&self, x: &[bool]) -> Vec<bool> {
+        assert_eq!(x.len(), self.header[0]);
+        let mut t = vec![false; self.header[2]];
+        let mut y = vec![false; self.header[4]];
+        let (offset_x, offset_t, offset_y) = (self.header[1], self.header[3], self.header[5]);
+
+        for op in &self.operations {
+            let operand = |name: &str, t: &Vec<bool>, y: &Vec<bool>| {
+                let idx = name[1..].parse::<usize>().unwrap();
+                match &name[..1] {
+                    "x" => x[idx - offset_x],
+                    "t" => t[idx - offset_t],
+                    "y" => y[idx - offset_y],
+                    _ => panic!("Invalid operand: {}", name),
+                }
+            };
+            let value = match op.operation.as_str() {
+                "=" => operand(&op.op1, &t, &y),
+                "XNOR" => !(operand(&op.op1, &t, &y) ^ operand(&op.op2, &t, &y)),
+                _ => operand(&op.op1, &t, &y) ^ operand(&op.op2, &t, &y),
+            };
+            let idx = op.target[1..].parse::<usize>().unwrap();
+            match &op.target[..1] {
+                "t" => t[idx - offset_t] = value,
+                "y" => y[idx - offset_y] = value,
+                _ => panic!("Invalid target: {}", op.target),
+            }
+        }
+        y
+    }
+}
+
 impl LinearCircuit {
     pub fn new(state_slice: &Vec<Ciphertext>) -> Self {
         Self { x: state_slice.to_vec(), t: vec![], y: vec![] }
     }
 
     // Modified to use predefined circuit data
-    pub fn execute_circuit(&mut self, server_key: &ServerKey, file_path: &str, client_key_debug: &ClientKey) {
+    pub fn execute_circuit(&mut self, server_key: &ServerKey, file_path: &str) {
         // Select the appropriate circuit definition based on file path
-        let circuit_def = if file_path.contains("mixcolumns2.txt") {
-            &MIXCOLUMNS2_CIRCUIT
+        if file_path.contains("mixcolumns2.txt") {
+            self.execute_definition(server_key, &MIXCOLUMNS2_CIRCUIT)
         } else if file_path.contains("mixcolumns.txt") {
-            &MIXCOLUMNS_CIRCUIT
+            self.execute_definition(server_key, &MIXCOLUMNS_CIRCUIT)
         } else {
             // Fallback to file reading for other circuit files
-            return self.execute_circuit_from_file(server_key, file_path, client_key_debug);
-        };
-        
+            self.execute_circuit_from_file(server_key, file_path)
+        }
+    }
+
+    fn operand(&self, name: &str, circuit_def: &CircuitDefinition) -> &Ciphertext {
+        if name.contains('x') {
+            &self.x[name[1..].parse::<usize>().unwrap() - circuit_def.header[1]]
+        } else if name.contains('t') {
+            &self.t[name[1..].parse::<usize>().unwrap() - circuit_def.header[3]]
+        } else if name.contains('y') {
+            &self.y[name[1..].parse::<usize>().unwrap() - circuit_def.header[5]]
+        } else {
+            panic!("Invalid operand: {}", name);
+        }
+    }
+
+    /// Runs a parsed circuit, the XORs being sums of the parity encoded bits.
+    pub fn execute_definition(&mut self, server_key: &ServerKey, circuit_def: &CircuitDefinition) {
         // Initialize containers using header information
         assert_eq!(self.x.len(), circuit_def.header[0]);
         self.t = vec![Ciphertext::Trivial(0); circuit_def.header[2]];
         self.y = vec![Ciphertext::Trivial(0); circuit_def.header[4]];
-        let (offset_x, offset_t, offset_y) = (
-            circuit_def.header[1], 
-            circuit_def.header[3], 
-            circuit_def.header[5]
-        );
 
         // Process each operation
         for op in &circuit_def.operations {
-            // Get the first operand
-            let op1 = if op.op1.contains('x') {
-                &self.x[op.op1[1..].parse::<usize>().unwrap() - offset_x]
-            } else if op.op1.contains('t') {
-                &self.t[op.op1[1..].parse::<usize>().unwrap() - offset_t]
-            } else if op.op1.contains('y') {
-                &self.y[op.op1[1..].parse::<usize>().unwrap() - offset_y]
-            } else {
-                panic!("Invalid operand: {}", op.op1);
-            };
-
-            // Get the second operand
-            let op2 = if op.op2.contains('x') {
-                &self.x[op.op2[1..].parse::<usize>().unwrap() - offset_x]
-            } else if op.op2.contains('t') {
-                &self.t[op.op2[1..].parse::<usize>().unwrap() - offset_t]
-            } else if op.op2.contains('y') {
-                &self.y[op.op2[1..].parse::<usize>().unwrap() - offset_y]
+            let mut value = if op.operation == "=" {
+                self.operand(&op.op1, circuit_def).to_owned()
             } else {
-                panic!("Invalid operand: {}", op.op2);
+                let op1 = self.operand(&op.op1, circuit_def);
+                let op2 = self.operand(&op.op2, circuit_def);
+                server_key.simple_sum(&vec![op1.to_owned(), op2.to_owned()])
             };
+            if op.operation == "XNOR" {
+                value = server_key.simple_plaintext_sum(&value, 1, 2);
+            }
 
             // Apply operation to the target
             if op.target.contains('y') {
-                let idx = op.target[1..].parse::<usize>().unwrap() - offset_y;
-                self.y[idx] = server_key.simple_sum(&vec![op1.to_owned(), op2.to_owned()]);
-                if op.operation == "XNOR" {
-                    self.y[idx] = server_key.simple_plaintext_sum(&self.y[idx], 1, 2);
-                }
+                self.y[op.target[1..].parse::<usize>().unwrap() - circuit_def.header[5]] = value;
             } else if op.target.contains('t') {
-                let idx = op.target[1..].parse::<usize>().unwrap() - offset_t;
-                self.t[idx] = server_key.simple_sum(&vec![op1.to_owned(), op2.to_owned()]);
-                if op.operation == "XNOR" {
-                    self.t[idx] = server_key.simple_plaintext_sum(&self.t[idx], 1, 2);
-                }
+                self.t[op.target[1..].parse::<usize>().unwrap() - circuit_def.header[3]] = value;
             } else {
                 panic!("Invalid target: {}", op.target);
             }
         }
     }
 
-    // Original method as fallback for other circuit files
-    fn execute_circuit_from_file(&mut self, server_key: &ServerKey, file_path: &str, client_key_debug: &ClientKey) {
-        let file = File::open(file_path).unwrap();
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
-        let header: Vec<usize> = lines.next().unwrap().unwrap().split_whitespace().map(|n| n.parse::<usize>().unwrap()).collect();
-        
-        // Same implementation as original execute_circuit
-        assert_eq!(self.x.len(), header[0]);
-        self.t = vec![Ciphertext::Trivial(0);header[2]];
-        self.y = vec![Ciphertext::Trivial(0);header[4]];
-        let (offset_x, offset_t, offset_y) = (header[1], header[3], header[5]);
-
-        // Process each line from the file
-        for line in lines {
-            let elmts: Vec<String> = line.unwrap().split_whitespace().map(|s| s.to_string()).collect();
-            let op1 = if elmts[2].contains('x') {
-                &self.x[elmts[2][1..].parse::<usize>().unwrap() - offset_x]
-            } else if elmts[2].contains('t') {
-                &self.t[elmts[2][1..].parse::<usize>().unwrap() - offset_t]
-            } else if elmts[2].contains('y') {
-                &self.y[elmts[2][1..].parse::<usize>().unwrap() - offset_y]
-            } else { panic!() };
-            
-            let op2 = if elmts[4].contains('x') {
-                &self.x[elmts[4][1..].parse::<usize>().unwrap() - offset_x]
-            } else if elmts[4].contains('t') {
-                &self.t[elmts[4][1..].parse::<usize>().unwrap() - offset_t]
-            } else if elmts[4].contains('y') {
-                &self.y[elmts[4][1..].parse::<usize>().unwrap() - offset_y]
-            } else { panic!() };
-            
-            if elmts[0].contains('y') {
-                self.y[elmts[0][1..].parse::<usize>().unwrap() - offset_y] = server_key.simple_sum(&vec![op1.to_owned(), op2.to_owned()]);
-                if elmts[3] == "XNOR" {
-                    self.y[elmts[0][1..].parse::<usize>().unwrap() - offset_y] = server_key.simple_plaintext_sum(&self.y[elmts[0][1..].parse::<usize>().unwrap() - offset_y], 1, 2);
-                }
-            } else if elmts[0].contains('t') {
-                self.t[elmts[0][1..].parse::<usize>().unwrap() - offset_t] = server_key.simple_sum(&vec![op1.to_owned(), op2.to_owned()]);
-                if elmts[3] == "XNOR" {
-                    self.t[elmts[0][1..].parse::<usize>().unwrap() - offset_t] = server_key.simple_plaintext_sum(&self.t[elmts[0][1..].parse::<usize>().unwrap() - offset_t], 1, 2);
-                }
-            } else { panic!() }
-        }
+    // Fallback for other circuit files
+    fn execute_circuit_from_file(&mut self, server_key: &ServerKey, file_path: &str) {
+        let text = std::fs::read_to_string(file_path).unwrap();
+        self.execute_definition(server_key, &CircuitDefinition::parse(&text));
     }
-}
\ No newline at end of file
+}
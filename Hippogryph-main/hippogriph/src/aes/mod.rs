@@ -1,17 +1,32 @@
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
-
 use clear::clear_sub_bytes;
+use std::time::Instant;
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
-use tfhe::{core_crypto::prelude::DynamicDistribution, odd::prelude::*};
+use tfhe::keycache::NamedParam;
+use tfhe::odd::{key_switching_key::OddToShortintKeySwitchingKey, keycache::KEY_CACHE, prelude::*};
+use tfhe::shortint::parameters::{PARAM_MESSAGE_2_CARRY_2_KS_PBS, V0_11_PARAM_MESSAGE_4_CARRY_4_KS_PBS_GAUSSIAN_2M64};
+use tfhe::boolean::parameters::DEFAULT_PARAMETERS;
+
+#[cfg(feature = "debug-oracle")]
+use std::sync::Arc;
+#[cfg(feature = "debug-oracle")]
+use tfhe::odd::debug_oracle::{debug_oracle, register_debug_oracle, ClientKeyOracle};
 
-use crate::aes::aes_utils::{pretty_print_clear, u8_to_vec_bool_integer};
+use crate::aes::aes_utils::u8_to_vec_bool_integer;
+#[cfg(feature = "debug-oracle")]
+use crate::aes::aes_utils::{pretty_print_clear, pretty_print_nibbles};
 
-use self::{aes_utils::{key_expansion, pretty_print_nibbles, u8_to_vec_bool, vec_bool_to_u8}, casts::{decomposer, recomposer}, linear_circuit::LinearCircuit};
+use self::{aes_utils::{key_expansion, u8_to_vec_bool, vec_bool_to_u8}, backend::{AESBackend, BackendKind}, boolean::GatesBackend, casts::{decomposer, recomposer}, full_lut::FullLutBackend, linear_circuit::LinearCircuit, timing::{Layer, TimingReport}};
 
 mod aes_utils;
-mod linear_circuit;
-mod casts;
-mod clear;
+pub mod backend;
+pub mod batch;
+pub mod boolean;
+pub mod full_lut;
+pub mod gcm;
+pub(crate) mod linear_circuit;
+pub(crate) mod casts;
+pub(crate) mod clear;
+pub mod timing;
 
 
 pub struct AESStateBoolean{
@@ -20,7 +35,7 @@ pub struct AESStateBoolean{
 
 
 impl AESStateBoolean{
-    pub fn tfhe_encryption_bits(m : &Vec<u64>, client_key : &ClientKey) -> Self{
+    pub fn tfhe_encryption_bits(m : &[u64], client_key : &ClientKey) -> Self{
         assert_eq!(m.len(), 128);
 
         let parity_encoding = Encoding::parity_encoding();
@@ -41,13 +56,24 @@ impl AESStateBoolean{
         &self.bits[col * 8 * 4 + row * 8 + bit]
     }
 
-    pub fn aes_recomposer(&self, server_key : &ServerKey, client_key_debug : &ClientKey)-> AESStateArithmetic{
+    /// Hand the state over to shortint : the bytes in order, each one as a radix integer of
+    /// shortint messages, least significant digit first. The number of bits of the messages
+    /// should divide 8.
+    pub fn to_shortint(&self, key_switching_key : &OddToShortintKeySwitchingKey) -> Vec<tfhe::shortint::Ciphertext>{
+        //the bits of a byte are stored most significant first
+        let bits_lsb_first = (0..16).map(|i| self.bits[i*8..(i+1)*8].iter().rev().cloned().collect())
+                                    .collect::<Vec<Vec<Ciphertext>>>()
+                                    .concat();
+        key_switching_key.pack_bits(&bits_lsb_first)
+    }
+
+    pub fn aes_recomposer(&self, server_key : &ServerKey)-> AESStateArithmetic{
         let encoding_arithmetic = Encoding::new_canonical(16, (0..16).collect(), 17);
         AESStateArithmetic{
-            nibbles : (0..32)
+            nibbles : (0..self.bits.len() / 4)
                     .into_par_iter() //comment this line to deactivate parallelization
                     .map(|i| self.bits[i*4..(i+1)*4].to_vec())
-                    .map(|v| recomposer(&v, &encoding_arithmetic, &server_key, &client_key_debug))
+                    .map(|v| recomposer(&v, &encoding_arithmetic, &server_key))
                     .collect(),
             encoding : encoding_arithmetic
         }
@@ -66,12 +92,12 @@ pub struct AESStateArithmetic{
 
 impl AESStateArithmetic{
 
-    pub fn aes_decomposer(&self, server_key : &ServerKey, client_key_debug : &ClientKey) -> AESStateBoolean{
+    pub fn aes_decomposer(&self, server_key : &ServerKey) -> AESStateBoolean{
         AESStateBoolean{
             bits : self.nibbles
             //.iter()
             .par_iter() //select the line to select parallelization
-            .map(|x| decomposer(x, &Encoding::parity_encoding(), server_key, client_key_debug))
+            .map(|x| decomposer(x, &Encoding::parity_encoding(), server_key))
             .collect::<Vec<Vec<Ciphertext>>>()
             .concat()
         }
@@ -81,19 +107,17 @@ impl AESStateArithmetic{
 
 
 
-fn sub_bytes(state : &AESStateArithmetic, server_key:&ServerKey, client_key_debug : &ClientKey) -> AESStateArithmetic{
-    assert_eq!(state.nibbles.len(), 32);
+// on any number of bytes, as the key schedule substitutes one word at a time
+fn sub_bytes(state : &AESStateArithmetic, server_key:&ServerKey) -> AESStateArithmetic{
+    assert_eq!(state.nibbles.len() % 2, 0);
+    let bytes : Vec<Vec<Ciphertext>> = (0..state.nibbles.len() / 2)
+                .map(|i| state.nibbles[i*2..(i+1)*2].to_vec())
+                .collect();
     AESStateArithmetic{
-        nibbles : (0..16)
-                .into_par_iter()    //comment this line to activate parallelisation
-                .map(|i| (i, state.nibbles[i*2..(i+1)*2].to_vec()))
-                .map(|(i, v)| server_key.full_tree_bootstrapping(&v, 
-                                                                        &vec![state.encoding.clone();2],
-                                                                        256,
-                                                                        &clear_sub_bytes, 
-                                                                        client_key_debug,
-                                                                        i == 0))
-                .collect::<Vec<Vec<Ciphertext>>>()
+        nibbles : server_key.full_tree_bootstrapping_batch(&bytes, 
+                                                            &vec![state.encoding.clone();2],
+                                                            256,
+                                                            &clear_sub_bytes)
                 .concat(),
         encoding : state.encoding.clone()
     }
@@ -102,7 +126,7 @@ fn sub_bytes(state : &AESStateArithmetic, server_key:&ServerKey, client_key_debu
 
 
 
-fn add_round_key(state : &AESStateBoolean, round_key : &Vec<bool>, server_key:&ServerKey) -> AESStateBoolean{
+fn add_round_key(state : &AESStateBoolean, round_key : &[bool], server_key:&ServerKey) -> AESStateBoolean{
     assert_eq!(state.bits.len(), 128);
     assert_eq!(round_key.len(), 128);
     AESStateBoolean { bits: state.bits.iter()
@@ -126,180 +150,341 @@ fn shift_rows(state : &AESStateBoolean) -> AESStateBoolean{
 }
 
 
-fn mix_columns(state : &AESStateBoolean, server_key:&ServerKey, client_key_debug : &ClientKey) -> AESStateBoolean{
+fn mix_columns(state : &AESStateBoolean, server_key:&ServerKey) -> AESStateBoolean{
     AESStateBoolean {
         bits : (0..4).map(|col| {
             let mut circuit = LinearCircuit::new(&state.bits[col*32..(col + 1)*32].to_vec());
-            circuit.execute_circuit(&server_key, "./src/aes/data/mixcolumns2.txt", &client_key_debug);
+            circuit.execute_circuit(&server_key, "./src/aes/data/mixcolumns2.txt");
             circuit.y
         }).collect::<Vec<Vec<Ciphertext>>>().concat()
     }
 }
 
 
-pub fn run_aes(state: &AESStateBoolean, server_key:&ServerKey, aes_key : Vec<bool>, client_key_debug : &ClientKey) -> AESStateBoolean{
-    //Debug
-    let print_debug = |state : &AESStateBoolean, expected : &str|{
-        let result_debug = state.tfhe_decryption_bits(&client_key_debug);
+//Debug : the intermediate states can only be decrypted when a debug oracle has been registered
+#[cfg(feature = "debug-oracle")]
+fn print_debug(state : &AESStateBoolean, expected : &str){
+    if let Some(oracle) = debug_oracle(){
+        let result_debug = state.bits.iter().map(|c| oracle.decrypt(c)).collect();
         pretty_print_clear(&result_debug);
         println!("Expected\n{}", expected);
         println!();
-    };
+    }
+}
 
+#[cfg(not(feature = "debug-oracle"))]
+fn print_debug(_state : &AESStateBoolean, _expected : &str){}
 
-    let print_debug_arith = |state_arith: &AESStateArithmetic, expected : &str|{
+
+#[cfg(feature = "debug-oracle")]
+fn print_debug_arith(state_arith: &AESStateArithmetic, expected : &str){
+    if let Some(oracle) = debug_oracle(){
         let result = state_arith.nibbles.iter()
-                                        .map(|nib| client_key_debug.decrypt(&nib))
+                                        .map(|nib| oracle.decrypt(nib))
                                         .collect();
         pretty_print_nibbles(&result);
         println!("Expected\n{}", expected);
         println!();
-    };
-
-
-    let expected = vec![
-        "00 10 20 30 40 50 60 70 80 90 a0 b0 c0 d0 e0 f0",
-        "89 d8 10 e8 85 5a ce 68 2d 18 43 d8 cb 12 8f e4",
-        "49 15 59 8f 55 e5 d7 a0 da ca 94 fa 1f 0a 63 f7",
-        "fa 63 6a 28 25 b3 39 c9 40 66 8a 31 57 24 4d 17",
-        "24 72 40 23 69 66 b3 fa 6e d2 75 32 88 42 5b 6c",
-        "c8 16 77 bc 9b 7a c9 3b 25 02 79 92 b0 26 19 96",
-        "c6 2f e1 09 f7 5e ed c3 cc 79 39 5d 84 f9 cf 5d",
-        "d1 87 6c 0f 79 c4 30 0a b4 55 94 ad d6 6f f4 1f",
-        "fd e3 ba d2 05 e5 d0 d7 35 47 96 4e f1 fe 37 f1",
-        "bd 6e 7c 3d f2 b5 77 9e 0b 61 21 6e 8b 10 b6 89"
-    ];
+    }
+}
+
+#[cfg(not(feature = "debug-oracle"))]
+fn print_debug_arith(_state_arith: &AESStateArithmetic, _expected : &str){}
+
+
+/// The hybrid odd evaluation : the linear layers on bits with the parity encoding, the S-box on
+/// nibbles in Z17.
+pub struct OddHybridBackend<'a>{
+    pub server_key : &'a ServerKey
+}
+
+
+impl AESBackend for OddHybridBackend<'_>{
+    type ClientKey = ClientKey;
+    type State = AESStateBoolean;
+    type SBoxState = AESStateArithmetic;
+
+    fn encrypt(m : &[u64], client_key : &ClientKey) -> AESStateBoolean{
+        AESStateBoolean::tfhe_encryption_bits(m, client_key)
+    }
+
+    fn decrypt(state : &AESStateBoolean, client_key : &ClientKey) -> Vec<u64>{
+        state.tfhe_decryption_bits(client_key)
+    }
+
+    fn add_round_key(&self, state : &AESStateBoolean, round_key : &[bool]) -> AESStateBoolean{
+        add_round_key(state, round_key, self.server_key)
+    }
+
+    fn shift_rows(&self, state : &AESStateBoolean) -> AESStateBoolean{
+        shift_rows(state)
+    }
+
+    fn mix_columns(&self, state : &AESStateBoolean) -> AESStateBoolean{
+        mix_columns(state, self.server_key)
+    }
+
+    fn to_sbox_state(&self, state : AESStateBoolean) -> AESStateArithmetic{
+        state.aes_recomposer(self.server_key)
+    }
+
+    fn sub_bytes(&self, state : &AESStateArithmetic) -> AESStateArithmetic{
+        sub_bytes(state, self.server_key)
+    }
+
+    fn leave_sbox_state(&self, state : AESStateArithmetic) -> AESStateBoolean{
+        state.aes_decomposer(self.server_key)
+    }
+
+    fn print_debug(&self, state : &AESStateBoolean, expected : &str){
+        print_debug(state, expected)
+    }
+
+    fn print_debug_sbox(&self, state : &AESStateArithmetic, expected : &str){
+        print_debug_arith(state, expected)
+    }
+}
+
+
 
+pub fn run_aes<B : AESBackend>(state : &B::State, backend : &B, aes_key : Vec<bool>, report : &mut TimingReport) -> B::State{
     // Key Expansion
     let round_keys = key_expansion(aes_key);
 
+    run_aes_with_round_keys(state, backend, &round_keys, Some(report))
+}
+
+
+
+/// The AES rounds on an already expanded key, so that the round keys can be shared by many blocks.
+///
+/// Without a report, the layers are not timed and neither the progress nor the debug hooks are
+/// printed, for the drivers running many blocks at once.
+pub fn run_aes_with_round_keys<B : AESBackend>(state : &B::State, backend : &B, round_keys : &[Vec<bool>], mut report : Option<&mut TimingReport>) -> B::State{
+    assert_eq!(round_keys.len(), 11);
+
     // Initial round key addition
-    let mut state_bool =  add_round_key(state, &round_keys[0], server_key);
-    print_debug(&state_bool, expected[0]); 
-    
-    let mut state_arith = state_bool.aes_recomposer(&server_key, &client_key_debug);
-    println!("After recomposition :");
-    print_debug_arith(&state_arith, expected[0]);
-    
-    //9 full rounds
-    for r in 0..9{
-        println!("TIMING START_ROUND {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-        println!("Round {}", r + 1);
-        state_arith = sub_bytes(&state_arith, server_key, client_key_debug);
-        println!("TIMING POST_SUB_BYTES {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-        print_debug_arith(&state_arith, "");
-        
-
-        state_bool = state_arith.aes_decomposer(&server_key, &client_key_debug);
-        println!("TIMING POST_BOOLEAN_DECOMPOSITION {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-        print_debug_arith(&state_arith, "");
-        
-
-        state_bool = shift_rows(&state_bool);
-        println!("TIMING POST_SHIFT_ROWS {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-
-        state_bool = mix_columns(&state_bool, server_key, client_key_debug);
-        println!("TIMING POST_MIX_COLUMNS {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-
-        state_bool = add_round_key(&state_bool, &round_keys[r + 1], server_key);
-        println!("TIMING POST_ADD_ROUND_KEYS {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-        
-        print_debug(&state_bool, expected[r+1]);
-
-
-        state_arith = state_bool.aes_recomposer(&server_key, &client_key_debug);
-        println!("TIMING POST_BOOLEAN_RECOMPOSITION {} {:?}", r+1, SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
-        print_debug_arith(&state_arith, expected[r+1]);
-        
-    }
-    state_arith = sub_bytes(&state_arith, server_key, client_key_debug);
-    
-    
-    state_bool = state_arith.aes_decomposer(&server_key, &client_key_debug);
-    
+    let state = time(&mut report, 0, Layer::AddRoundKey, || backend.add_round_key(state, &round_keys[0]));
 
-    state_bool = shift_rows(&state_bool);
-    state_bool = add_round_key(&state_bool, &round_keys[10], server_key);
-    
-    state_bool
+    aes_rounds(state, backend, &round_keys[1..], |state, round_key| backend.add_round_key(state, round_key), report)
 }
 
 
 
+// The intermediate states of the test vector after each AddRoundKey, printed by the debug hooks
+const EXPECTED_ROUND_STATES : [&str;10] = [
+    "00 10 20 30 40 50 60 70 80 90 a0 b0 c0 d0 e0 f0",
+    "89 d8 10 e8 85 5a ce 68 2d 18 43 d8 cb 12 8f e4",
+    "49 15 59 8f 55 e5 d7 a0 da ca 94 fa 1f 0a 63 f7",
+    "fa 63 6a 28 25 b3 39 c9 40 66 8a 31 57 24 4d 17",
+    "24 72 40 23 69 66 b3 fa 6e d2 75 32 88 42 5b 6c",
+    "c8 16 77 bc 9b 7a c9 3b 25 02 79 92 b0 26 19 96",
+    "c6 2f e1 09 f7 5e ed c3 cc 79 39 5d 84 f9 cf 5d",
+    "d1 87 6c 0f 79 c4 30 0a b4 55 94 ad d6 6f f4 1f",
+    "fd e3 ba d2 05 e5 d0 d7 35 47 96 4e f1 fe 37 f1",
+    "bd 6e 7c 3d f2 b5 77 9e 0b 61 21 6e 8b 10 b6 89"
+];
+
+
+/// The 10 AES rounds on a state whose initial round key addition is done, shared by all the AES
+/// drivers. `round_keys` are the keys of rounds 1 to 10, added by `add_round_key`, so that they
+/// can be clear or encrypted. The report works as for [`run_aes_with_round_keys`].
+pub(crate) fn aes_rounds<B : AESBackend, K>(state : B::State, backend : &B, round_keys : &[K], add_round_key : impl Fn(&B::State, &K) -> B::State, mut report : Option<&mut TimingReport>) -> B::State{
+    assert_eq!(round_keys.len(), 10);
+    let verbose = report.is_some();
+    if verbose{
+        backend.print_debug(&state, EXPECTED_ROUND_STATES[0]);
+    }
 
-pub const PARAMETERS_40: CustomOddParameters = CustomOddParameters {
-    lwe_dimension: LweDimension(754),
-    glwe_dimension: GlweDimension(1),
-    polynomial_size: PolynomialSize(1024),
-    lwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.0e-8)),
-    glwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.871712650082723e-15)),
-    pbs_base_log: DecompositionBaseLog(23),
-    pbs_level: DecompositionLevelCount(2),
-    ks_base_log: DecompositionBaseLog(4),
-    ks_level: DecompositionLevelCount(3),
-    encryption_key_choice: EncryptionKeyChoice::Big,
-};
+    let mut state_sbox = time(&mut report, 0, Layer::Recomposition, || backend.to_sbox_state(state));
+    if verbose{
+        backend.print_debug_sbox(&state_sbox, EXPECTED_ROUND_STATES[0]);
+    }
 
+    //9 full rounds
+    for (r, round_key) in (1..10).zip(round_keys){
+        if verbose{
+            println!("Round {}", r);
+        }
+        state_sbox = time(&mut report, r, Layer::SubBytes, || backend.sub_bytes(&state_sbox));
+        if verbose{
+            backend.print_debug_sbox(&state_sbox, "");
+        }
 
+        let mut state = time(&mut report, r, Layer::Decomposition, || backend.leave_sbox_state(state_sbox));
 
+        state = time(&mut report, r, Layer::ShiftRows, || backend.shift_rows(&state));
 
-pub const PARAMETERS_128: CustomOddParameters = CustomOddParameters {
-    lwe_dimension: LweDimension(900),
-    glwe_dimension: GlweDimension(1),
-    polynomial_size: PolynomialSize(4096),
-    lwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(6.8e-7)),
-    glwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(2.168404344971009e-19)),
-    pbs_base_log: DecompositionBaseLog(15),
-    pbs_level: DecompositionLevelCount(2),
-    ks_base_log: DecompositionBaseLog(3),
-    ks_level: DecompositionLevelCount(6),
-    encryption_key_choice: EncryptionKeyChoice::Big,
-};
+        state = time(&mut report, r, Layer::MixColumns, || backend.mix_columns(&state));
 
+        state = time(&mut report, r, Layer::AddRoundKey, || add_round_key(&state, round_key));
+        if verbose{
+            backend.print_debug(&state, EXPECTED_ROUND_STATES[r]);
+        }
 
-pub fn  demo_aes(){
-    println!("TIMING START_ALL {:?}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
+        state_sbox = time(&mut report, r, Layer::Recomposition, || backend.to_sbox_state(state));
+        if verbose{
+            backend.print_debug_sbox(&state_sbox, EXPECTED_ROUND_STATES[r]);
+        }
+    }
+    if verbose{
+        println!("Round 10");
+    }
+    state_sbox = time(&mut report, 10, Layer::SubBytes, || backend.sub_bytes(&state_sbox));
 
+    let mut state = time(&mut report, 10, Layer::Decomposition, || backend.leave_sbox_state(state_sbox));
+
+    state = time(&mut report, 10, Layer::ShiftRows, || backend.shift_rows(&state));
+    time(&mut report, 10, Layer::AddRoundKey, || add_round_key(&state, &round_keys[9]))
+}
 
-    let parameters = PARAMETERS_40;    //HERE SELECT THE PARAMETER SET
 
+// Times `f` as the given layer of the given round when there is a report to fill
+fn time<R>(report : &mut Option<&mut TimingReport>, round : usize, layer : Layer, f : impl FnOnce() -> R) -> R{
+    match report{
+        Some(report) => report.time(round, layer, f),
+        None => f(),
+    }
+}
 
-    let (client_key, server_key) = gen_keys(&parameters);
 
-    let plaintext = vec![
-        0x00, 0x11, 0x22, 0x33,
-        0x44, 0x55, 0x66, 0x77,
-        0x88, 0x99, 0xaa, 0xbb,
-        0xcc, 0xdd, 0xee, 0xff,
-    ];
-    let aes_key = [
-        0x00, 0x01, 0x02, 0x03,
-        0x04, 0x05, 0x06, 0x07,
-        0x08, 0x09, 0x0a, 0x0b,
-        0x0c, 0x0d, 0x0e, 0x0f,
-    ];
 
-    let plaintext_bits = plaintext.iter().map(|byte| u8_to_vec_bool_integer(*byte)).collect::<Vec<Vec<u64>>>().concat();
-    let aes_key_bits = aes_key.iter().map(|byte| u8_to_vec_bool(*byte)).collect::<Vec<Vec<bool>>>().concat();
 
-    let state = AESStateBoolean::tfhe_encryption_bits(&plaintext_bits, &client_key);
+// The FIPS-197 appendix C.1 test vector, shared by all the backends
+const PLAINTEXT : [u8;16] = [
+    0x00, 0x11, 0x22, 0x33,
+    0x44, 0x55, 0x66, 0x77,
+    0x88, 0x99, 0xaa, 0xbb,
+    0xcc, 0xdd, 0xee, 0xff,
+];
+const AES_KEY : [u8;16] = [
+    0x00, 0x01, 0x02, 0x03,
+    0x04, 0x05, 0x06, 0x07,
+    0x08, 0x09, 0x0a, 0x0b,
+    0x0c, 0x0d, 0x0e, 0x0f,
+];
+pub(crate) const EXPECTED_OUTPUT : &str = "69 c4 e0 d8 6a 7b 04 30 d8 cd b7 80 70 b4 c5 5a ";
 
-    let start = Instant::now();
-    let result = run_aes(&state, &server_key, aes_key_bits, &client_key);
-    let stop = start.elapsed();
-    println!("Time elapsed : {:?}", stop);
 
+fn test_vector_bits() -> (Vec<u64>, Vec<bool>){
+    let plaintext_bits = PLAINTEXT.iter().map(|byte| u8_to_vec_bool_integer(*byte)).collect::<Vec<Vec<u64>>>().concat();
+    let aes_key_bits = AES_KEY.iter().map(|byte| u8_to_vec_bool(*byte)).collect::<Vec<Vec<bool>>>().concat();
+    (plaintext_bits, aes_key_bits)
+}
 
-    let result_clear = result.tfhe_decryption_bits(&client_key);
+
+pub(crate) fn format_output(result_clear : &[u64]) -> String{
     let mut output = String::new();
     (0..16).for_each(|i| {
         output.push_str(&format!("{:02x} ", vec_bool_to_u8(&result_clear[i * 8..(i + 1) * 8].to_vec())));
     });
-    println!("{}", output);
-    
-    let expected = String::from("69 c4 e0 d8 6a 7b 04 30 d8 cd b7 80 70 b4 c5 5a ");
+    output
+}
+
+
+fn print_output(result_clear : &[u64]){
+    println!("{}", format_output(result_clear));
+
     println!("Expected:");
-    println!("{}", expected);
-    //assert_eq!(output, expected);
+    println!("{}", EXPECTED_OUTPUT);
+    //assert_eq!(format_output(result_clear), EXPECTED_OUTPUT);
+}
+
+
+
+
+// Encrypts the test vector, runs the AES with the given backend and prints the result and the report
+fn run_demo<B : AESBackend>(backend : &B, client_key : &B::ClientKey, parameters_name : &str) -> B::State{
+    let (plaintext_bits, aes_key_bits) = test_vector_bits();
+
+    let state = B::encrypt(&plaintext_bits, client_key);
+
+    let mut report = TimingReport::new(parameters_name);
+    let result = run_aes(&state, backend, aes_key_bits, &mut report);
+    println!("Time elapsed : {:?}", report.total());
+
+    print_output(&B::decrypt(&result, client_key));
+
+    println!("{}", report.to_json());
+
+    result
+}
+
+
+
+
+/// Runs the demo of the selected evaluation strategy.
+pub fn demo(kind : BackendKind){
+    println!("AES with the {} backend", kind);
+    match kind{
+        BackendKind::Odd => demo_aes(),
+        BackendKind::Gates => demo_aes_gates(),
+        BackendKind::FullLut => demo_aes_full_lut(),
+    }
+}
+
+
+
+
+pub fn  demo_aes(){
+    let parameters = PARAMETERS_40;    //HERE SELECT THE PARAMETER SET
+
+
+    // keys are cached on disk so that successive runs do not regenerate them
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    #[cfg(feature = "debug-oracle")]
+    register_debug_oracle(Arc::new(ClientKeyOracle::new(client_key.clone())));
+
+    let result = run_demo(&OddHybridBackend { server_key }, client_key, &parameters.name());
+
+
+    // transciphering : the AES output goes on to shortint, 2 bits per message
+    let shortint_keys = tfhe::shortint::keycache::KEY_CACHE.get_from_param(PARAM_MESSAGE_2_CARRY_2_KS_PBS);
+    let (shortint_client_key, shortint_server_key) = (shortint_keys.client_key(), shortint_keys.server_key());
+    let key_switching_key = OddToShortintKeySwitchingKey::new((client_key, server_key), shortint_client_key, PARAMETERS_KEYSWITCH_40);
+
+    let start = Instant::now();
+    let digits = result.to_shortint(&key_switching_key);
+    println!("Conversion to shortint : {:?}", start.elapsed());
+
+    // some downstream computation : the bitwise negation of every byte
+    let not_lut = shortint_server_key.generate_lookup_table(|x| 3 - x);
+    let negated_digits : Vec<tfhe::shortint::Ciphertext> = digits.par_iter().map(|d| shortint_server_key.apply_lookup_table(d, &not_lut)).collect();
+
+    let negated_bytes = negated_digits.chunks(4)
+                                      .map(|byte| byte.iter().rev().fold(0, |acc, d| 4 * acc + shortint_client_key.decrypt(d)))
+                                      .map(|byte| format!("{:02x} ", byte))
+                                      .collect::<String>();
+    println!("Negated output computed with shortint :");
+    println!("{}", negated_bytes);
 }
     
+
+
+
+
+/// The same AES evaluation with gate bootstrapping on tfhe::boolean, as a baseline for the odd one.
+pub fn demo_aes_gates(){
+    let parameters = DEFAULT_PARAMETERS;
+
+    let keys = tfhe::boolean::keycache::KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    run_demo(&GatesBackend { server_key }, client_key, &parameters.name());
+}
+
+
+
+
+/// The same AES evaluation with full lookup tables on 4-bit shortint nibbles, as the other baseline
+/// for the odd one.
+pub fn demo_aes_full_lut(){
+    let parameters = V0_11_PARAM_MESSAGE_4_CARRY_4_KS_PBS_GAUSSIAN_2M64;
+
+    let keys = tfhe::shortint::keycache::KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    run_demo(&FullLutBackend::new(server_key), client_key, &parameters.name());
+}
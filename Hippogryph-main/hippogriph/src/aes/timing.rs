@@ -0,0 +1,206 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "pbs-stats")]
+use tfhe::odd::server_key::pbs_stats;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer{
+    AddRoundKey,
+    Recomposition,
+    SubBytes,
+    Decomposition,
+    ShiftRows,
+    MixColumns,
+}
+
+impl Layer{
+    pub const ALL : [Layer;6] = [Layer::AddRoundKey, Layer::Recomposition, Layer::SubBytes, Layer::Decomposition, Layer::ShiftRows, Layer::MixColumns];
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            Layer::AddRoundKey => "add_round_key",
+            Layer::Recomposition => "recomposition",
+            Layer::SubBytes => "sub_bytes",
+            Layer::Decomposition => "decomposition",
+            Layer::ShiftRows => "shift_rows",
+            Layer::MixColumns => "mix_columns",
+        }
+    }
+}
+
+
+// Number of primitives run by the odd server key, only available with the pbs-stats feature
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperationCounts{
+    pub ks : u64,
+    pub pbs : u64,
+    pub mvb : u64,
+    pub packing_ks : u64,
+}
+
+impl OperationCounts{
+    #[cfg(feature = "pbs-stats")]
+    fn now() -> Self{
+        OperationCounts{
+            ks : pbs_stats::get_ks_count(),
+            pbs : pbs_stats::get_pbs_count(),
+            mvb : pbs_stats::get_mvb_count(),
+            packing_ks : pbs_stats::get_packing_ks_count(),
+        }
+    }
+
+    #[cfg(not(feature = "pbs-stats"))]
+    fn now() -> Self{
+        OperationCounts::default()
+    }
+
+    fn since(&self, start : &Self) -> Self{
+        OperationCounts{
+            ks : self.ks - start.ks,
+            pbs : self.pbs - start.pbs,
+            mvb : self.mvb - start.mvb,
+            packing_ks : self.packing_ks - start.packing_ks,
+        }
+    }
+
+    fn to_json(self) -> Value{
+        json!({ "ks" : self.ks, "pbs" : self.pbs, "mvb" : self.mvb, "packing_ks" : self.packing_ks })
+    }
+}
+
+
+// The operations counted per call site (file, line and operation) of the odd engine, only available
+// with the pbs-stats feature
+type CallSiteCounts = BTreeMap<(&'static str, u32, String), u64>;
+
+#[cfg(feature = "pbs-stats")]
+fn call_site_counts() -> CallSiteCounts{
+    pbs_stats::get_call_site_counts().into_iter()
+                                     .map(|c| ((c.file, c.line, format!("{:?}", c.operation)), c.count))
+                                     .collect()
+}
+
+#[cfg(not(feature = "pbs-stats"))]
+fn call_site_counts() -> CallSiteCounts{
+    CallSiteCounts::new()
+}
+
+
+#[derive(Clone, Debug)]
+pub struct LayerTiming{
+    pub round : usize,
+    pub layer : Layer,
+    pub duration : Duration,
+    pub counts : OperationCounts,
+}
+
+
+/// Duration (and operation counts with the `pbs-stats` feature) of every layer of an AES evaluation.
+///
+/// Round 0 is the initial key addition, rounds 1 to 10 are the AES rounds.
+#[derive(Clone, Debug)]
+pub struct TimingReport{
+    pub parameters : String,
+    pub layers : Vec<LayerTiming>,
+    // summed over the layers
+    call_sites : CallSiteCounts,
+}
+
+impl TimingReport{
+    pub fn new(parameters : &str) -> Self{
+        TimingReport { parameters: parameters.to_string(), layers: vec![], call_sites: CallSiteCounts::new() }
+    }
+
+    /// Run `f` and record its duration as the given layer of the given round.
+    pub fn time<R>(&mut self, round : usize, layer : Layer, f : impl FnOnce() -> R) -> R{
+        let start_call_sites = call_site_counts();
+        let start_counts = OperationCounts::now();
+        let start = Instant::now();
+        let result = f();
+        let duration = start.elapsed();
+        let counts = OperationCounts::now().since(&start_counts);
+        for (call_site, count) in call_site_counts(){
+            let start_count = start_call_sites.get(&call_site).copied().unwrap_or(0);
+            if count > start_count{
+                *self.call_sites.entry(call_site).or_insert(0) += count - start_count;
+            }
+        }
+        self.layers.push(LayerTiming { round, layer, duration, counts });
+        result
+    }
+
+    pub fn total(&self) -> Duration{
+        self.layers.iter().map(|l| l.duration).sum()
+    }
+
+    pub fn total_for_layer(&self, layer : Layer) -> Duration{
+        self.layers.iter().filter(|l| l.layer == layer).map(|l| l.duration).sum()
+    }
+
+    pub fn to_json(&self) -> String{
+        let to_ms = |d : Duration| d.as_secs_f64() * 1000.;
+
+        let layers : Vec<Value> = self.layers.iter()
+                                             .map(|l| json!({
+                                                 "round" : l.round,
+                                                 "layer" : l.layer.name(),
+                                                 "duration_ms" : to_ms(l.duration),
+                                                 "counts" : l.counts.to_json(),
+                                             }))
+                                             .collect();
+
+        let per_layer : serde_json::Map<String, Value> = Layer::ALL.iter()
+                                                                   .map(|layer| (layer.name().to_string(), json!(to_ms(self.total_for_layer(*layer)))))
+                                                                   .collect();
+
+        let call_sites : Vec<Value> = self.call_sites.iter()
+                                                     .map(|((file, line, operation), count)| json!({
+                                                         "file" : file,
+                                                         "line" : line,
+                                                         "operation" : operation,
+                                                         "count" : count,
+                                                     }))
+                                                     .collect();
+
+        json!({
+            "parameters" : self.parameters,
+            "pbs_stats" : cfg!(feature = "pbs-stats"),
+            "total_ms" : to_ms(self.total()),
+            "total_per_layer_ms" : per_layer,
+            "layers" : layers,
+            "call_sites" : call_sites,
+        }).to_string()
+    }
+}
+
+
+
+#[cfg(all(test, feature = "pbs-stats"))]
+mod tests{
+    use super::*;
+    use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+    #[test]
+    fn test_timing_report_counts(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let bit = client_key.encrypt_arithmetic(1, &Encoding::parity_encoding());
+
+        let mut report = TimingReport::new("test");
+        let switched = report.time(1, Layer::Recomposition, || server_key.encoding_switching_lut(&bit, &Encoding::new_canonical(2, vec![1, 2], 3)));
+        assert_eq!(client_key.decrypt(&switched), 1);
+
+        // the counters are global, other tests may add to them
+        let counts = report.layers[0].counts;
+        assert!(counts.ks >= 1);
+        assert!(counts.pbs >= 1);
+
+        let json : Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["layers"][0]["counts"]["pbs"], counts.pbs);
+        let call_sites = json["call_sites"].as_array().unwrap();
+        assert!(call_sites.iter().any(|c| c["operation"] == "Pbs" && c["count"].as_u64().unwrap() >= 1));
+    }
+}
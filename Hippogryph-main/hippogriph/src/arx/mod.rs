@@ -0,0 +1,49 @@
+use crate::aes::clear::Clear;
+
+pub mod odd;
+
+
+/// The 32-bit word operations of an ARX cipher.
+pub trait ArxGates : Sync{
+    type Word : Clone + Send + Sync;
+
+    /// Addition modulo 2^32.
+    fn add(&self, a : &Self::Word, b : &Self::Word) -> Self::Word;
+
+    fn xor(&self, a : &Self::Word, b : &Self::Word) -> Self::Word;
+
+    fn rotate_left(&self, a : &Self::Word, r : u32) -> Self::Word;
+
+    fn rotate_right(&self, a : &Self::Word, r : u32) -> Self::Word{
+        self.rotate_left(a, (32 - r) % 32)
+    }
+
+    fn shift_right(&self, a : &Self::Word, r : u32) -> Self::Word;
+
+    fn constant(&self, value : u32) -> Self::Word;
+}
+
+
+impl ArxGates for Clear{
+    type Word = u32;
+
+    fn add(&self, a : &u32, b : &u32) -> u32{
+        a.wrapping_add(*b)
+    }
+
+    fn xor(&self, a : &u32, b : &u32) -> u32{
+        a ^ b
+    }
+
+    fn rotate_left(&self, a : &u32, r : u32) -> u32{
+        a.rotate_left(r)
+    }
+
+    fn shift_right(&self, a : &u32, r : u32) -> u32{
+        a >> r
+    }
+
+    fn constant(&self, value : u32) -> u32{
+        value
+    }
+}
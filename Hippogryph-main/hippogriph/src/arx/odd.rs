@@ -0,0 +1,135 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tfhe::odd::prelude::*;
+
+use super::ArxGates;
+
+
+/// The modulus of the gadgets the bitwise functions of a word go through.
+pub(crate) const GADGET_MODULUS : u64 = 7;
+
+
+/// A 32-bit word, as 8 nibbles for the additions and as 32 parity encoded bits, least significant
+/// first, for the XORs and the rotations.
+///
+/// A word only holds the representations computed so far, the other one is derived when needed :
+/// in a quarter round, every word goes at most once from one to the other. The bits may also be
+/// held as inputs of the gadgets in Z7, for the words read by several gadgets.
+#[derive(Clone)]
+pub struct OddWord{
+    bits : Option<Vec<Ciphertext>>,
+    radix : Option<OddRadixCiphertext<8>>,
+    gadget_bits : Option<Vec<Ciphertext>>,
+}
+
+
+impl OddWord{
+    pub fn from_radix(radix : OddRadixCiphertext<8>) -> Self{
+        OddWord { bits : None, radix : Some(radix), gadget_bits : None }
+    }
+
+    pub fn from_bits(bits : Vec<Ciphertext>) -> Self{
+        assert_eq!(bits.len(), 32);
+        OddWord { bits : Some(bits), radix : None, gadget_bits : None }
+    }
+}
+
+
+/// The ARX words on odd : the additions are radix additions with the carries propagated
+/// from nibble to nibble, the XORs are free sums of parity bits and the rotations a re-indexing
+/// of the bits.
+pub struct OddArxGates<'a>{
+    pub(crate) server_key : &'a ServerKey,
+}
+
+
+impl<'a> OddArxGates<'a>{
+    pub fn new(server_key : &'a ServerKey) -> Self{
+        OddArxGates { server_key }
+    }
+
+    pub(crate) fn radix(&self, word : &OddWord) -> OddRadixCiphertext<8>{
+        match (&word.radix, &word.bits){
+            (Some(radix), _) => radix.clone(),
+            (None, Some(bits)) => self.server_key.radix_from_bits(bits),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    pub(crate) fn bits(&self, word : &OddWord) -> Vec<Ciphertext>{
+        match (&word.bits, &word.radix){
+            (Some(bits), _) => bits.clone(),
+            (None, Some(radix)) => self.server_key.radix_to_bits(radix, &Encoding::parity_encoding()),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// The bits of the word encoded as inputs of the gadgets in Z7.
+    pub(crate) fn gadget_bits(&self, word : &OddWord) -> Vec<Ciphertext>{
+        if let Some(gadget_bits) = &word.gadget_bits{
+            return gadget_bits.clone();
+        }
+        let gadget_encoding = Encoding::new_canonical_binary(1, GADGET_MODULUS);
+        match (&word.radix, &word.bits){
+            (Some(radix), _) => self.server_key.radix_to_bits(radix, &gadget_encoding),
+            // from the parity encoding, only {-h, h} can be reached, h being the inverse of 2
+            (None, Some(bits)) => {
+                let h = GADGET_MODULUS.div_ceil(2);
+                let encoding = Encoding::new_canonical(2, vec![GADGET_MODULUS - h, h], GADGET_MODULUS);
+                bits.par_iter()
+                    .map(|bit| {
+                        let switched = self.server_key.encoding_switching_lut(bit, &encoding);
+                        self.server_key.encoding_switching_sum_constant(&switched, h, GADGET_MODULUS)
+                    })
+                    .collect()
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// The word with both its parity bits and its gadget bits, for a word read by XORs and
+    /// gadgets several times.
+    pub(crate) fn with_all_bits(&self, word : &OddWord) -> OddWord{
+        let (bits, gadget_bits) = rayon::join(|| self.bits(word), || self.gadget_bits(word));
+        OddWord { bits : Some(bits), radix : word.radix.clone(), gadget_bits : Some(gadget_bits) }
+    }
+}
+
+
+impl ArxGates for OddArxGates<'_>{
+    type Word = OddWord;
+
+    fn add(&self, a : &OddWord, b : &OddWord) -> OddWord{
+        let (a, b) = rayon::join(|| self.radix(a), || self.radix(b));
+        OddWord::from_radix(self.server_key.radix_add(&a, &b))
+    }
+
+    fn xor(&self, a : &OddWord, b : &OddWord) -> OddWord{
+        let (a, b) = rayon::join(|| self.bits(a), || self.bits(b));
+        OddWord::from_bits(a.par_iter()
+                            .zip(b.par_iter())
+                            .map(|(x, y)| self.server_key.simple_sum(&vec![x.to_owned(), y.to_owned()]))
+                            .collect())
+    }
+
+    fn rotate_left(&self, a : &OddWord, r : u32) -> OddWord{
+        let bits = self.bits(a);
+        OddWord::from_bits((0..32).map(|i| bits[(i + 32 - r as usize) % 32].to_owned()).collect())
+    }
+
+    fn shift_right(&self, a : &OddWord, r : u32) -> OddWord{
+        let bits = self.bits(a);
+        let zero = self.server_key.trivial_encrypt_arithmetic(0, &Encoding::parity_encoding());
+        OddWord::from_bits((0..32).map(|i| bits.get(i + r as usize).unwrap_or(&zero).to_owned()).collect())
+    }
+
+    // every representation of a constant is noiseless
+    fn constant(&self, value : u32) -> OddWord{
+        let trivial_bits = |encoding : Encoding| (0..32).map(|i| self.server_key.trivial_encrypt_arithmetic(((value >> i) & 1) as u64, &encoding))
+                                                          .collect();
+        OddWord{
+            bits : Some(trivial_bits(Encoding::parity_encoding())),
+            radix : Some(self.server_key.trivial_encrypt_radix(value as u64)),
+            gadget_bits : Some(trivial_bits(Encoding::new_canonical_binary(1, GADGET_MODULUS))),
+        }
+    }
+}
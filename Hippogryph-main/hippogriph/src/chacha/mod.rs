@@ -0,0 +1,189 @@
+use std::time::Instant;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use crate::arx::{odd::{OddArxGates, OddWord}, ArxGates};
+
+use self::odd::keystream_bits;
+
+pub mod odd;
+
+
+// "expand 32-byte k"
+const SIGMA : [u32;4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+const COLUMNS : [[usize;4];4] = [[0, 4, 8, 12], [1, 5, 9, 13], [2, 6, 10, 14], [3, 7, 11, 15]];
+const DIAGONALS : [[usize;4];4] = [[0, 5, 10, 15], [1, 6, 11, 12], [2, 7, 8, 13], [3, 4, 9, 14]];
+
+
+fn quarter_round<G : ArxGates>(gates : &G, [a, b, c, d] : [G::Word;4]) -> [G::Word;4]{
+    let a = gates.add(&a, &b);
+    let d = gates.rotate_left(&gates.xor(&d, &a), 16);
+    let c = gates.add(&c, &d);
+    let b = gates.rotate_left(&gates.xor(&b, &c), 12);
+    let a = gates.add(&a, &b);
+    let d = gates.rotate_left(&gates.xor(&d, &a), 8);
+    let c = gates.add(&c, &d);
+    let b = gates.rotate_left(&gates.xor(&b, &c), 7);
+    [a, b, c, d]
+}
+
+
+// The four quarter rounds of a half round touch disjoint words and run in parallel
+fn half_round<G : ArxGates>(gates : &G, state : &mut [G::Word], quarters : &[[usize;4];4]){
+    let outputs : Vec<[G::Word;4]> = quarters.par_iter()
+                                             .map(|indices| quarter_round(gates, indices.map(|i| state[i].clone())))
+                                             .collect();
+    for (indices, words) in quarters.iter().zip(outputs){
+        for (i, word) in indices.iter().zip(words){
+            state[*i] = word;
+        }
+    }
+}
+
+
+/// The ChaCha20 block function of RFC 8439 : the 16 words of keystream for the 8 words of the
+/// key, the counter and the 3 words of the nonce. Only the key is encrypted, the counter and the
+/// nonce being public.
+pub fn chacha20_block<G : ArxGates>(gates : &G, key : &[G::Word], counter : u32, nonce : [u32;3]) -> Vec<G::Word>{
+    assert_eq!(key.len(), 8);
+    let initial_state : Vec<G::Word> = SIGMA.iter().map(|w| gates.constant(*w))
+                                            .chain(key.iter().cloned())
+                                            .chain([counter].iter().chain(nonce.iter()).map(|w| gates.constant(*w)))
+                                            .collect();
+
+    let mut state = initial_state.clone();
+    for r in 0..10{
+        println!("Double round {}", r + 1);
+        half_round(gates, &mut state, &COLUMNS);
+        half_round(gates, &mut state, &DIAGONALS);
+    }
+
+    state.par_iter()
+         .zip(initial_state.par_iter())
+         .map(|(w, w_0)| gates.add(w, w_0))
+         .collect()
+}
+
+
+
+// The words of the key and the nonce are read little endian from their bytes
+fn bytes_to_words(bytes : &[u8]) -> Vec<u32>{
+    bytes.chunks(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect()
+}
+
+
+pub struct ChaChaTestVector{
+    pub key : [u8;32],
+    pub counter : u32,
+    pub nonce : [u8;12],
+    pub keystream : [u8;64],
+}
+
+
+// RFC 8439, section 2.3.2 and appendix A.1 test vector #1
+pub const CHACHA20_TEST_VECTORS : [ChaChaTestVector;2] = [
+    ChaChaTestVector {
+        key : [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        ],
+        counter : 1,
+        nonce : [0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00],
+        keystream : [
+            0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20, 0x71, 0xc4,
+            0xc7, 0xd1, 0xf4, 0xc7, 0x33, 0xc0, 0x68, 0x03, 0x04, 0x22, 0xaa, 0x9a, 0xc3, 0xd4, 0x6c, 0x4e,
+            0xd2, 0x82, 0x64, 0x46, 0x07, 0x9f, 0xaa, 0x09, 0x14, 0xc2, 0xd7, 0x05, 0xd9, 0x8b, 0x02, 0xa2,
+            0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9, 0xcb, 0xd0, 0x83, 0xe8, 0xa2, 0x50, 0x3c, 0x4e,
+        ],
+    },
+    ChaChaTestVector {
+        key : [0;32],
+        counter : 0,
+        nonce : [0;12],
+        keystream : [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86, 0xbd, 0x28,
+            0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc, 0x8b, 0x77, 0x0d, 0xc7,
+            0xda, 0x41, 0x59, 0x7c, 0x51, 0x57, 0x48, 0x8d, 0x77, 0x24, 0xe0, 0x3f, 0xb8, 0xd8, 0x4a, 0x37,
+            0x6a, 0x43, 0xb8, 0xf4, 0x15, 0x18, 0xa1, 0x1c, 0xc3, 0x87, 0xb6, 0x69, 0xb2, 0xee, 0x65, 0x86,
+        ],
+    },
+];
+
+
+impl ChaChaTestVector{
+    pub fn key_words(&self) -> Vec<u32>{
+        bytes_to_words(&self.key)
+    }
+
+    pub fn nonce_words(&self) -> [u32;3]{
+        bytes_to_words(&self.nonce).try_into().unwrap()
+    }
+}
+
+
+
+/// Computes the keystream block of the first RFC 8439 test vector from an encrypted key.
+pub fn demo_chacha20(){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let vector = &CHACHA20_TEST_VECTORS[0];
+    let key : Vec<OddWord> = vector.key_words().iter()
+                                   .map(|w| OddWord::from_radix(client_key.encrypt_radix(*w as u64)))
+                                   .collect();
+
+    let gates = OddArxGates::new(server_key);
+
+    let start = Instant::now();
+    let block = chacha20_block(&gates, &key, vector.counter, vector.nonce_words());
+    let keystream = keystream_bits(&gates, &block);
+    println!("ChaCha20 block : {:?}", start.elapsed());
+
+    let output : String = keystream.chunks(8)
+                                   .map(|byte| byte.iter().fold(0, |acc, b| 2 * acc + client_key.decrypt(b)))
+                                   .map(|byte| format!("{:02x} ", byte))
+                                   .collect();
+    println!("{}", output);
+    println!("Expected:");
+    println!("{}", vector.keystream.iter().map(|byte| format!("{:02x} ", byte)).collect::<String>());
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::aes::clear::Clear;
+
+    #[test]
+    fn test_chacha20_test_vectors(){
+        for vector in &CHACHA20_TEST_VECTORS{
+            let block = chacha20_block(&Clear, &vector.key_words(), vector.counter, vector.nonce_words());
+            let keystream : Vec<u8> = block.iter().flat_map(|w| w.to_le_bytes()).collect();
+            assert_eq!(keystream, vector.keystream);
+        }
+    }
+
+    #[test]
+    fn test_odd_quarter_round(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let gates = OddArxGates::new(server_key);
+
+        // RFC 8439, section 2.1.1
+        let words : [u32;4] = [0x11111111, 0x01020304, 0x9b8d6f43, 0x01234567];
+        let encrypted_words = words.map(|w| OddWord::from_radix(client_key.encrypt_radix(w as u64)));
+
+        let expected = quarter_round(&Clear, words);
+        let result = quarter_round(&gates, encrypted_words);
+        let decrypted = result.map(|word| gates.bits(&word)
+                                               .iter()
+                                               .rev()
+                                               .fold(0, |acc, b| 2 * acc + client_key.decrypt(b) as u32));
+        assert_eq!(decrypted, expected);
+        assert_eq!(expected, [0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb]);
+    }
+}
@@ -0,0 +1,17 @@
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use tfhe::odd::prelude::*;
+
+use crate::arx::odd::{OddArxGates, OddWord};
+
+
+/// The keystream bits in the order of the serialized block : the words little endian, and every
+/// byte most significant bit first as the AES state.
+pub fn keystream_bits(gates : &OddArxGates, block : &[OddWord]) -> Vec<Ciphertext>{
+    block.par_iter()
+         .map(|word| {
+            let bits = gates.bits(word);
+            bits.chunks(8).flat_map(|byte| byte.iter().rev().cloned()).collect()
+         })
+         .collect::<Vec<Vec<Ciphertext>>>()
+         .concat()
+}
@@ -1,6 +1,44 @@
-use aes::demo_aes;
+use std::process::exit;
+
+use aes::{backend::BackendKind, batch::demo_aes_batch, demo, gcm::demo_aes_gcm};
+use chacha::demo_chacha20;
+use sha256::demo_sha256;
+use spn::{demo_spn, SpnCipher};
+use trivium::{demo_trivium, TriviumVariant};
 
 mod aes;
+mod arx;
+mod chacha;
+mod sha256;
+mod spn;
+mod trivium;
+
+// Usage : hippogriph [odd|gates|full-lut|present-80|skinny-64-64|trivium|kreyvium|chacha20|sha256|aes-gcm|aes-batch]...,
+// every AES backend is run one after the other when nothing is given
 fn main() {
-    demo_aes();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    if args.is_empty() {
+        BackendKind::ALL.into_iter().for_each(demo);
+    }
+    for arg in args{
+        if let Some(cipher) = SpnCipher::from_name(&arg) {
+            demo_spn(&cipher);
+        } else if let Some(variant) = TriviumVariant::from_name(&arg) {
+            demo_trivium(variant);
+        } else if arg == "chacha20" {
+            demo_chacha20();
+        } else if arg == "sha256" {
+            demo_sha256();
+        } else if arg == "aes-gcm" {
+            demo_aes_gcm();
+        } else if arg == "aes-batch" {
+            demo_aes_batch();
+        } else {
+            demo(arg.parse::<BackendKind>().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1)
+            }));
+        }
+    }
 }
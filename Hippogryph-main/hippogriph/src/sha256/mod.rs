@@ -0,0 +1,229 @@
+use std::time::Instant;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use crate::aes::clear::Clear;
+use crate::arx::{odd::{OddArxGates, OddWord}, ArxGates};
+
+pub mod odd;
+
+
+const INITIAL_HASH : [u32;8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS : [u32;64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+
+/// The ARX operations of SHA-256 and its two bitwise functions, each one gadget per bit on the odd
+/// side.
+pub trait Sha256Gates : ArxGates{
+    /// `(e AND f) XOR (NOT e AND g)`
+    fn ch(&self, e : &Self::Word, f : &Self::Word, g : &Self::Word) -> Self::Word;
+
+    /// The majority of `a`, `b` and `c`
+    fn maj(&self, a : &Self::Word, b : &Self::Word, c : &Self::Word) -> Self::Word;
+
+    /// Called on the new words `a` and `e` of every round, which are read by the next three
+    /// rounds : a word can be brought once to every representation it will be read in.
+    fn prepare(&self, word : Self::Word) -> Self::Word{
+        word
+    }
+}
+
+
+impl Sha256Gates for Clear{
+    fn ch(&self, e : &u32, f : &u32, g : &u32) -> u32{
+        (e & f) ^ (!e & g)
+    }
+
+    fn maj(&self, a : &u32, b : &u32, c : &u32) -> u32{
+        (a & b) ^ (a & c) ^ (b & c)
+    }
+}
+
+
+fn xor3<G : ArxGates>(gates : &G, a : &G::Word, b : &G::Word, c : &G::Word) -> G::Word{
+    gates.xor(&gates.xor(a, b), c)
+}
+
+fn big_sigma_0<G : ArxGates>(gates : &G, a : &G::Word) -> G::Word{
+    xor3(gates, &gates.rotate_right(a, 2), &gates.rotate_right(a, 13), &gates.rotate_right(a, 22))
+}
+
+fn big_sigma_1<G : ArxGates>(gates : &G, e : &G::Word) -> G::Word{
+    xor3(gates, &gates.rotate_right(e, 6), &gates.rotate_right(e, 11), &gates.rotate_right(e, 25))
+}
+
+fn small_sigma_0<G : ArxGates>(gates : &G, w : &G::Word) -> G::Word{
+    xor3(gates, &gates.rotate_right(w, 7), &gates.rotate_right(w, 18), &gates.shift_right(w, 3))
+}
+
+fn small_sigma_1<G : ArxGates>(gates : &G, w : &G::Word) -> G::Word{
+    xor3(gates, &gates.rotate_right(w, 17), &gates.rotate_right(w, 19), &gates.shift_right(w, 10))
+}
+
+
+// W_t + K_t for the 64 rounds
+fn message_schedule<G : Sha256Gates>(gates : &G, block : &[G::Word]) -> Vec<G::Word>{
+    let mut w = block.to_vec();
+    for t in 16..64{
+        let (x, y) = rayon::join(|| gates.add(&small_sigma_1(gates, &w[t - 2]), &w[t - 7]),
+                                 || gates.add(&small_sigma_0(gates, &w[t - 15]), &w[t - 16]));
+        w.push(gates.add(&x, &y));
+    }
+    w.par_iter()
+     .zip(ROUND_CONSTANTS.par_iter())
+     .map(|(w, k)| gates.add(w, &gates.constant(*k)))
+     .collect()
+}
+
+
+/// The SHA-256 compression function on a block of 16 message words.
+pub fn compress<G : Sha256Gates>(gates : &G, hash : &[G::Word], block : &[G::Word]) -> Vec<G::Word>{
+    assert_eq!(hash.len(), 8);
+    assert_eq!(block.len(), 16);
+    let schedule = message_schedule(gates, block);
+
+    let mut state : Vec<G::Word> = hash.to_vec();
+    for (t, wk) in schedule.iter().enumerate(){
+        if t % 16 == 0{
+            println!("Round {}", t + 1);
+        }
+        let [a, b, c, d, e, f, g, h] : [G::Word;8] = state.try_into().ok().unwrap();
+        let (t_1, t_2) = rayon::join(
+            || {
+                let (x, y) = rayon::join(|| gates.add(&h, wk),
+                                         || gates.add(&big_sigma_1(gates, &e), &gates.ch(&e, &f, &g)));
+                gates.add(&x, &y)
+            },
+            || gates.add(&big_sigma_0(gates, &a), &gates.maj(&a, &b, &c)),
+        );
+        let (new_a, new_e) = rayon::join(|| gates.prepare(gates.add(&t_1, &t_2)),
+                                         || gates.prepare(gates.add(&d, &t_1)));
+        state = vec![new_a, a, b, c, new_e, e, f, g];
+    }
+
+    state.par_iter()
+         .zip(hash.par_iter())
+         .map(|(w, h)| gates.add(w, h))
+         .collect()
+}
+
+
+/// SHA-256 of a padded message, given as blocks of 16 words.
+pub fn sha256<G : Sha256Gates>(gates : &G, message : &[G::Word]) -> Vec<G::Word>{
+    assert_eq!(message.len() % 16, 0, "The message should be padded");
+    let initial_hash : Vec<G::Word> = INITIAL_HASH.iter().map(|w| gates.prepare(gates.constant(*w))).collect();
+    message.chunks(16)
+           .fold(initial_hash, |hash, block| compress(gates, &hash, block))
+}
+
+
+/// The padding is done in the clear, as the length of the message is public : a 1 bit, zeros and
+/// the length in bits, the whole being cut in big endian words.
+pub fn pad(message : &[u8]) -> Vec<u32>{
+    let mut bytes = message.to_vec();
+    bytes.push(0x80);
+    while bytes.len() % 64 != 56{
+        bytes.push(0);
+    }
+    bytes.extend((8 * message.len() as u64).to_be_bytes());
+    bytes.chunks(4).map(|word| u32::from_be_bytes(word.try_into().unwrap())).collect()
+}
+
+
+
+pub struct Sha256TestVector{
+    pub message : &'static str,
+    pub digest : [u32;8],
+}
+
+
+// FIPS 180-2 examples, the last one being two blocks long
+pub const SHA256_TEST_VECTORS : [Sha256TestVector;3] = [
+    Sha256TestVector {
+        message : "abc",
+        digest : [0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61, 0xf20015ad],
+    },
+    Sha256TestVector {
+        message : "",
+        digest : [0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b, 0x7852b855],
+    },
+    Sha256TestVector {
+        message : "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        digest : [0x248d6a61, 0xd20638b8, 0xe5c02693, 0x0c3e6039, 0xa33ce459, 0x64ff2167, 0xf6ecedd4, 0x19db06c1],
+    },
+];
+
+
+
+/// Hashes the encrypted words of the padded message of the first test vector.
+pub fn demo_sha256(){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let vector = &SHA256_TEST_VECTORS[0];
+    let message : Vec<OddWord> = pad(vector.message.as_bytes()).iter()
+                                                               .map(|w| OddWord::from_radix(client_key.encrypt_radix(*w as u64)))
+                                                               .collect();
+
+    let gates = OddArxGates::new(server_key);
+
+    let start = Instant::now();
+    let digest = sha256(&gates, &message);
+    println!("SHA-256 of {} block(s) : {:?}", message.len() / 16, start.elapsed());
+
+    let output : String = digest.iter()
+                                .map(|w| format!("{:08x}", client_key.decrypt_radix(&gates.radix(w))))
+                                .collect();
+    println!("{}", output);
+    println!("Expected:");
+    println!("{}", vector.digest.iter().map(|w| format!("{:08x}", w)).collect::<String>());
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_sha256_test_vectors(){
+        for vector in &SHA256_TEST_VECTORS{
+            assert_eq!(sha256(&Clear, &pad(vector.message.as_bytes())), vector.digest, "{:?}", vector.message);
+        }
+    }
+    #[test]
+    fn test_odd_sha256_gates(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+        let gates = OddArxGates::new(server_key);
+
+        // the words held as parity bits reach the gadgets through the switch to Z7, the other one
+        // through the radix decomposition
+        let words : [u32;3] = [0x510e527f, 0x9b05688c, 0x1f83d9ab];
+        let from_bits = |w : u32| OddWord::from_bits((0..32).map(|i| client_key.encrypt_arithmetic(((w >> i) & 1) as u64, &Encoding::parity_encoding())).collect());
+        let [x, y, z] = [from_bits(words[0]), OddWord::from_radix(client_key.encrypt_radix(words[1] as u64)), from_bits(words[2])];
+        let decrypt = |word : &OddWord| gates.bits(word)
+                                             .iter()
+                                             .rev()
+                                             .fold(0, |acc, b| 2 * acc + client_key.decrypt(b) as u32);
+
+        assert_eq!(decrypt(&gates.ch(&x, &y, &z)), Clear.ch(&words[0], &words[1], &words[2]));
+        assert_eq!(decrypt(&gates.maj(&z, &x, &y)), Clear.maj(&words[2], &words[0], &words[1]));
+        assert_eq!(decrypt(&gates.shift_right(&y, 3)), words[1] >> 3);
+        assert_eq!(decrypt(&small_sigma_1(&gates, &x)), small_sigma_1(&Clear, &words[0]));
+    }
+}
@@ -0,0 +1,40 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tfhe::odd::prelude::*;
+
+use crate::arx::odd::{OddArxGates, OddWord, GADGET_MODULUS};
+
+use super::Sha256Gates;
+
+
+impl OddArxGates<'_>{
+    // One gadget per bit, the inputs of the i-th one being the i-th bits of the words
+    fn bitwise_gadget(&self, gadget : &Gadget, words : [&OddWord;3]) -> OddWord{
+        assert_eq!(gadget.modulus(), GADGET_MODULUS);
+        let [x, y, z] = words.map(|word| self.gadget_bits(word));
+        OddWord::from_bits(x.par_iter()
+                            .zip(y.par_iter())
+                            .zip(z.par_iter())
+                            .map(|((x, y), z)| self.server_key.apply_gadget(gadget,
+                                                                            &[x.to_owned(), y.to_owned(), z.to_owned()],
+                                                                            &Encoding::parity_encoding()))
+                            .collect())
+    }
+}
+
+
+impl Sha256Gates for OddArxGates<'_>{
+    // e selects between f and g
+    fn ch(&self, e : &OddWord, f : &OddWord, g : &OddWord) -> OddWord{
+        self.bitwise_gadget(&Gadget::mux(), [e, f, g])
+    }
+
+    fn maj(&self, a : &OddWord, b : &OddWord, c : &OddWord) -> OddWord{
+        self.bitwise_gadget(&Gadget::new(vec![1, 1, 1], GADGET_MODULUS, &|x : &[bool]| x.iter().filter(|b| **b).count() >= 2),
+                            [a, b, c])
+    }
+
+    // a and e are read by the sigma functions of their round and by the next three gadgets
+    fn prepare(&self, word : OddWord) -> OddWord{
+        self.with_all_bits(&word)
+    }
+}
@@ -0,0 +1,296 @@
+use std::time::Instant;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::ParallelSlice;
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use crate::aes::casts::{decomposer, recomposer};
+use crate::aes::linear_circuit::{CircuitDefinition, LinearCircuit};
+
+pub mod present;
+pub mod skinny;
+
+
+/// The layers a round of an SPN is made of, in any order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpnLayer{
+    SBox,
+    AddRoundKey,
+    Permutation,
+    LinearLayer,
+}
+
+
+pub struct TestVector{
+    pub plaintext : u128,
+    pub key : u128,
+    pub ciphertext : u128,
+}
+
+
+/// Description of a substitution-permutation network on a block of bits, the bit 0 being the most
+/// significant bit of the block as for the AES state.
+///
+/// The state is encrypted bit by bit with the parity encoding, as the AES state before the
+/// recomposition, so the key additions, the permutation and the linear layer are free of
+/// bootstrapping.
+pub struct SpnCipher{
+    pub name : &'static str,
+    pub block_bits : usize,
+    pub key_bits : usize,
+    /// The S-box table on cells of `sbox_bits` consecutive bits, 4 or 8.
+    pub sbox : Vec<u64>,
+    pub sbox_bits : usize,
+    /// The bit i of the output of the permutation is the bit `permutation[i]` of its input.
+    pub permutation : Vec<usize>,
+    /// An SLP run by [`LinearCircuit`] on each chunk of `input_count` consecutive bits.
+    pub linear_layer : Option<CircuitDefinition>,
+    pub round : Vec<SpnLayer>,
+    pub round_count : usize,
+    /// Whether a last key addition follows the rounds.
+    pub final_key_addition : bool,
+    /// Expands the key into one mask per key addition, the round constants being folded in.
+    pub key_schedule : fn(&[bool]) -> Vec<Vec<bool>>,
+    pub test_vectors : &'static [TestVector],
+}
+
+
+impl SpnCipher{
+    pub fn from_name(name : &str) -> Option<Self>{
+        match name{
+            "present-80" => Some(SpnCipher::present_80()),
+            "skinny-64-64" => Some(SpnCipher::skinny_64_64()),
+            _ => None,
+        }
+    }
+
+
+    fn round_keys(&self, key : &[bool]) -> Vec<Vec<bool>>{
+        assert_eq!(key.len(), self.key_bits);
+        let round_keys = (self.key_schedule)(key);
+        let key_additions = self.round_count * self.round.iter().filter(|layer| **layer == SpnLayer::AddRoundKey).count()
+                            + self.final_key_addition as usize;
+        assert_eq!(round_keys.len(), key_additions);
+        assert!(round_keys.iter().all(|round_key| round_key.len() == self.block_bits));
+        round_keys
+    }
+
+
+    #[cfg(test)]
+    pub fn encrypt_clear(&self, block : &[bool], key : &[bool]) -> Vec<bool>{
+        assert_eq!(block.len(), self.block_bits);
+        let round_keys = self.round_keys(key);
+        let mut round_keys = round_keys.iter();
+
+        let add_round_key = |state : &Vec<bool>, round_key : &Vec<bool>| state.iter().zip(round_key).map(|(b, k)| b ^ k).collect();
+
+        let mut state = block.to_vec();
+        for _ in 0..self.round_count{
+            for layer in &self.round{
+                state = match layer{
+                    SpnLayer::SBox => state.chunks(self.sbox_bits)
+                                            .flat_map(|cell| to_bits(self.sbox[from_bits(cell) as usize] as u128, self.sbox_bits))
+                                            .collect(),
+                    SpnLayer::AddRoundKey => add_round_key(&state, round_keys.next().unwrap()),
+                    SpnLayer::Permutation => self.permutation.iter().map(|i| state[*i]).collect(),
+                    SpnLayer::LinearLayer => {
+                        let circuit = self.linear_layer.as_ref().unwrap();
+                        state.chunks(circuit.input_count()).flat_map(|chunk| circuit.evaluate_clear(chunk)).collect()
+                    }
+                };
+            }
+        }
+        if self.final_key_addition{
+            state = add_round_key(&state, round_keys.next().unwrap());
+        }
+        state
+    }
+
+
+    /// Evaluates the cipher on a state of parity encoded bits, with a clear key as for the AES.
+    pub fn run(&self, state : &[Ciphertext], server_key : &ServerKey, key : &[bool]) -> Vec<Ciphertext>{
+        assert_eq!(state.len(), self.block_bits);
+        let round_keys = self.round_keys(key);
+        let mut round_keys = round_keys.iter();
+
+        let mut state = state.to_vec();
+        for _ in 0..self.round_count{
+            for layer in &self.round{
+                state = match layer{
+                    SpnLayer::SBox => self.sbox_layer(&state, server_key),
+                    SpnLayer::AddRoundKey => add_round_key(&state, round_keys.next().unwrap(), server_key),
+                    SpnLayer::Permutation => self.permutation.iter().map(|i| state[*i].to_owned()).collect(),
+                    SpnLayer::LinearLayer => self.linear_layer(&state, server_key),
+                };
+            }
+        }
+        if self.final_key_addition{
+            state = add_round_key(&state, round_keys.next().unwrap(), server_key);
+        }
+        state
+    }
+
+
+    // A 4-bit cell is recomposed in Z16 and its output bits come out of a single multi-value
+    // bootstrapping, an 8-bit cell goes through the full tree bootstrapping on two nibbles as
+    // the AES S-box
+    fn sbox_layer(&self, state : &[Ciphertext], server_key : &ServerKey) -> Vec<Ciphertext>{
+        let encoding_arithmetic = Encoding::new_canonical(16, (0..16).collect(), 17);
+        let nibbles : Vec<Ciphertext> = state.par_chunks(4)
+                                            .map(|bits| recomposer(&bits.to_vec(), &encoding_arithmetic, server_key))
+                                            .collect();
+        match self.sbox_bits{
+            4 => {
+                let functions : Vec<Box<dyn Fn(u64) -> u64>> = (0..4).rev()
+                    .map(|i| {
+                        let sbox = self.sbox.clone();
+                        Box::new(move |x : u64| (sbox[x as usize] >> i) & 1) as Box<dyn Fn(u64) -> u64>
+                    })
+                    .collect();
+                server_key.mvb_batch(&nibbles, &vec![Encoding::parity_encoding();4], &functions).concat()
+            }
+            8 => {
+                let bytes : Vec<Vec<Ciphertext>> = nibbles.chunks(2).map(|byte| byte.to_vec()).collect();
+                server_key.full_tree_bootstrapping_batch(&bytes,
+                                                        &vec![encoding_arithmetic.clone();2],
+                                                        256,
+                                                        &|x| self.sbox[x as usize])
+                          .concat()
+                          .par_iter()
+                          .map(|nibble| decomposer(nibble, &Encoding::parity_encoding(), server_key))
+                          .collect::<Vec<Vec<Ciphertext>>>()
+                          .concat()
+            }
+            _ => panic!("S-boxes on {} bits are not supported", self.sbox_bits),
+        }
+    }
+
+
+    fn linear_layer(&self, state : &[Ciphertext], server_key : &ServerKey) -> Vec<Ciphertext>{
+        let circuit_def = self.linear_layer.as_ref().expect("The cipher has no linear layer");
+        state.par_chunks(circuit_def.input_count())
+             .map(|chunk| {
+                let mut circuit = LinearCircuit::new(&chunk.to_vec());
+                circuit.execute_definition(server_key, circuit_def);
+                circuit.y
+             })
+             .collect::<Vec<Vec<Ciphertext>>>()
+             .concat()
+    }
+}
+
+
+
+fn add_round_key(state : &[Ciphertext], round_key : &[bool], server_key : &ServerKey) -> Vec<Ciphertext>{
+    state.par_iter()
+         .zip(round_key.par_iter())
+         .map(|(c, k)| if *k {server_key.simple_plaintext_sum(c, 1, 2)} else {c.to_owned()})
+         .collect()
+}
+
+
+
+// most significant bit first
+pub(crate) fn to_bits(x : u128, bit_count : usize) -> Vec<bool>{
+    (0..bit_count).rev().map(|i| (x >> i) & 1 == 1).collect()
+}
+
+pub(crate) fn from_bits(bits : &[bool]) -> u128{
+    bits.iter().fold(0, |acc, b| 2 * acc + *b as u128)
+}
+
+
+
+/// Runs the cipher on its first test vector.
+pub fn demo_spn(cipher : &SpnCipher){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let vector = &cipher.test_vectors[0];
+    let parity_encoding = Encoding::parity_encoding();
+    let state : Vec<Ciphertext> = to_bits(vector.plaintext, cipher.block_bits).iter()
+                                                            .map(|b| client_key.encrypt_arithmetic(*b as u64, &parity_encoding))
+                                                            .collect();
+
+    let start = Instant::now();
+    let result = cipher.run(&state, server_key, &to_bits(vector.key, cipher.key_bits));
+    println!("{} : {:?}", cipher.name, start.elapsed());
+
+    let result_bits : Vec<bool> = result.iter().map(|c| client_key.decrypt(c) == 1).collect();
+    let width = cipher.block_bits / 4;
+    println!("{:0width$x}", from_bits(&result_bits), width = width);
+    println!("Expected:");
+    println!("{:0width$x}", vector.ciphertext, width = width);
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::aes::clear::clear_sub_bytes;
+
+    #[test]
+    fn test_spn_test_vectors(){
+        for cipher in [SpnCipher::present_80(), SpnCipher::skinny_64_64()]{
+            for vector in cipher.test_vectors{
+                let result = cipher.encrypt_clear(&to_bits(vector.plaintext, cipher.block_bits), &to_bits(vector.key, cipher.key_bits));
+                assert_eq!(from_bits(&result), vector.ciphertext, "{}", cipher.name);
+            }
+        }
+    }
+
+    fn check_run(cipher : &SpnCipher, plaintext : u128, key : u128, expected : u128){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        let state : Vec<Ciphertext> = to_bits(plaintext, cipher.block_bits).iter()
+                                                .map(|b| client_key.encrypt_arithmetic(*b as u64, &Encoding::parity_encoding()))
+                                                .collect();
+        let result = cipher.run(&state, server_key, &to_bits(key, cipher.key_bits));
+
+        let result_bits : Vec<bool> = result.iter().map(|c| client_key.decrypt(c) == 1).collect();
+        assert_eq!(from_bits(&result_bits), expected, "{}", cipher.name);
+    }
+
+    #[test]
+    fn test_spn_run(){
+        let cipher = SpnCipher::present_80();
+        let vector = &cipher.test_vectors[0];
+        check_run(&cipher, vector.plaintext, vector.key, vector.ciphertext);
+    }
+
+    // SKINNY is the one going through the LinearLayer
+    #[test]
+    fn test_spn_run_linear_layer(){
+        let cipher = SpnCipher::skinny_64_64();
+        let vector = &cipher.test_vectors[0];
+        check_run(&cipher, vector.plaintext, vector.key, vector.ciphertext);
+    }
+
+    // No shipped cipher has 8-bit cells, a single round with the AES S-box goes through the
+    // full tree bootstrapping
+    #[test]
+    fn test_spn_run_8_bit_sbox(){
+        let cipher = SpnCipher{
+            name : "AES S-box",
+            block_bits : 16,
+            key_bits : 16,
+            sbox : (0..256).map(clear_sub_bytes).collect(),
+            sbox_bits : 8,
+            permutation : (0..16).collect(),
+            linear_layer : None,
+            round : vec![SpnLayer::AddRoundKey, SpnLayer::SBox],
+            round_count : 1,
+            final_key_addition : true,
+            key_schedule : |key| vec![key.to_vec(); 2],
+            test_vectors : &[],
+        };
+        let (plaintext, key) = (0x00ff, 0x53c4);
+        let expected = from_bits(&cipher.encrypt_clear(&to_bits(plaintext, 16), &to_bits(key, 16)));
+        // sbox(0x53) = 0xed and sbox(0x3b) = 0xe2, then the key is added again
+        assert_eq!(expected, 0xede2 ^ 0x53c4);
+        check_run(&cipher, plaintext, key, expected);
+    }
+}
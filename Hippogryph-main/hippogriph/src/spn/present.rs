@@ -0,0 +1,56 @@
+use super::{from_bits, to_bits, SpnCipher, SpnLayer, TestVector};
+
+
+const PRESENT_SBOX : [u64;16] = [0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2];
+
+
+// From the appendix of the PRESENT paper (CHES 2007)
+const PRESENT_80_TEST_VECTORS : [TestVector;4] = [
+    TestVector { plaintext : 0x0000000000000000, key : 0x00000000000000000000, ciphertext : 0x5579c1387b228445 },
+    TestVector { plaintext : 0x0000000000000000, key : 0xffffffffffffffffffff, ciphertext : 0xe72c46c0f5945049 },
+    TestVector { plaintext : 0xffffffffffffffff, key : 0x00000000000000000000, ciphertext : 0xa112ffc72f68417b },
+    TestVector { plaintext : 0xffffffffffffffff, key : 0xffffffffffffffffffff, ciphertext : 0x3333dcd3213210d2 },
+];
+
+
+// The round key is the 64 leftmost bits of the key register, which is then rotated by 61 to the
+// left, goes through the S-box on its 4 leftmost bits, and gets the round counter on bits 19 to 15
+fn present_80_key_schedule(key : &[bool]) -> Vec<Vec<bool>>{
+    let mut register = from_bits(key);
+    (1..=32u128).map(|i| {
+        let round_key = to_bits(register >> 16, 64);
+        register = ((register << 61) | (register >> 19)) & ((1 << 80) - 1);
+        register = ((PRESENT_SBOX[(register >> 76) as usize] as u128) << 76) | (register & ((1 << 76) - 1));
+        register ^= i << 15;
+        round_key
+    }).collect()
+}
+
+
+impl SpnCipher{
+    /// PRESENT with an 80-bit key : 31 rounds of key addition, S-box and bit permutation, and a
+    /// last key addition.
+    pub fn present_80() -> Self{
+        // the bit i, counted from the least significant one, moves to 16 * i mod 63
+        let mut permutation = vec![0;64];
+        (0..64).for_each(|i| {
+            let destination = if i == 63 {63} else {16 * i % 63};
+            permutation[63 - destination] = 63 - i;
+        });
+
+        SpnCipher{
+            name : "PRESENT-80",
+            block_bits : 64,
+            key_bits : 80,
+            sbox : PRESENT_SBOX.to_vec(),
+            sbox_bits : 4,
+            permutation,
+            linear_layer : None,
+            round : vec![SpnLayer::AddRoundKey, SpnLayer::SBox, SpnLayer::Permutation],
+            round_count : 31,
+            final_key_addition : true,
+            key_schedule : present_80_key_schedule,
+            test_vectors : &PRESENT_80_TEST_VECTORS,
+        }
+    }
+}
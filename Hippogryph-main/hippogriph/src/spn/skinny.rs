@@ -0,0 +1,77 @@
+use crate::aes::linear_circuit::CircuitDefinition;
+
+use super::{from_bits, to_bits, SpnCipher, SpnLayer, TestVector};
+
+
+const SKINNY_64_SBOX : [u64;16] = [0xc, 0x6, 0x9, 0x0, 0x1, 0xa, 0x2, 0xb, 0x3, 0x8, 0x5, 0xd, 0x4, 0xe, 0x7, 0xf];
+
+// The cell i of the output of ShiftRows is the cell SHIFT_ROWS[i] of its input
+const SHIFT_ROWS : [usize;16] = [0, 1, 2, 3, 7, 4, 5, 6, 10, 11, 8, 9, 13, 14, 15, 12];
+
+const TWEAKEY_PERMUTATION : [usize;16] = [9, 15, 8, 13, 10, 14, 12, 11, 0, 1, 2, 3, 4, 5, 6, 7];
+
+
+// From the appendix of the SKINNY paper (CRYPTO 2016)
+const SKINNY_64_64_TEST_VECTORS : [TestVector;1] = [
+    TestVector { plaintext : 0x06034f957724d19d, key : 0xf5269826fc681238, ciphertext : 0xbb39dfb2429b8ac7 },
+];
+
+
+// The round tweakey is added to the first two rows and the round constants to the first column,
+// both right after the S-boxes, so they are folded in a single mask
+fn skinny_64_64_key_schedule(key : &[bool]) -> Vec<Vec<bool>>{
+    let mut tweakey : Vec<u64> = key.chunks(4).map(|cell| from_bits(cell) as u64).collect();
+    let mut rc = 0u64;
+    (0..32).map(|_| {
+        rc = ((rc << 1) & 0x3f) | (((rc >> 5) ^ (rc >> 4) ^ 1) & 1);
+
+        let mut round_key = [0u64;16];
+        round_key[..8].copy_from_slice(&tweakey[..8]);
+        round_key[0] ^= rc & 0xf;
+        round_key[4] ^= rc >> 4;
+        round_key[8] ^= 0x2;
+
+        tweakey = TWEAKEY_PERMUTATION.iter().map(|i| tweakey[*i]).collect();
+        round_key.iter().flat_map(|cell| to_bits(*cell as u128, 4)).collect()
+    }).collect()
+}
+
+
+// MixColumns on the whole state : on every bit of every column,
+// (a0, a1, a2, a3) -> (a0 ^ a2 ^ a3, a0, a1 ^ a2, a0 ^ a2)
+fn skinny_64_mixcolumns_slp() -> String{
+    let mut slp = String::from("64 0 0 0 64 0\n");
+    for col in 0..4{
+        for bit in 0..4{
+            let idx = |row : usize| (row * 4 + col) * 4 + bit;
+            slp.push_str(&format!("y{} = x{} + x{}\n", idx(3), idx(0), idx(2)));
+            slp.push_str(&format!("y{} = y{} + x{}\n", idx(0), idx(3), idx(3)));
+            slp.push_str(&format!("y{} = x{}\n", idx(1), idx(0)));
+            slp.push_str(&format!("y{} = x{} + x{}\n", idx(2), idx(1), idx(2)));
+        }
+    }
+    slp
+}
+
+
+impl SpnCipher{
+    /// SKINNY-64-64 : 32 rounds of S-box, constant and tweakey addition, ShiftRows and MixColumns.
+    pub fn skinny_64_64() -> Self{
+        let permutation = SHIFT_ROWS.iter().flat_map(|cell| (0..4).map(move |bit| cell * 4 + bit)).collect();
+
+        SpnCipher{
+            name : "SKINNY-64-64",
+            block_bits : 64,
+            key_bits : 64,
+            sbox : SKINNY_64_SBOX.to_vec(),
+            sbox_bits : 4,
+            permutation,
+            linear_layer : Some(CircuitDefinition::parse(&skinny_64_mixcolumns_slp())),
+            round : vec![SpnLayer::SBox, SpnLayer::AddRoundKey, SpnLayer::Permutation, SpnLayer::LinearLayer],
+            round_count : 32,
+            final_key_addition : false,
+            key_schedule : skinny_64_64_key_schedule,
+            test_vectors : &SKINNY_64_64_TEST_VECTORS,
+        }
+    }
+}
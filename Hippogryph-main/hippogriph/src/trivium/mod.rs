@@ -0,0 +1,324 @@
+use std::time::Instant;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use tfhe::odd::{keycache::KEY_CACHE, prelude::*};
+
+use crate::aes::clear::Clear;
+
+use self::odd::OddTriviumGates;
+
+pub mod odd;
+
+
+/// The updates of Trivium and Kreyvium only read bits that are at least 66 steps old, so this
+/// many steps are evaluated in parallel.
+const STEPS_PER_BATCH : usize = 64;
+
+const WARMUP_STEPS : usize = 4 * 288;
+
+
+/// The two gates of the Trivium family, both evaluated with one bootstrapping on the odd side.
+pub trait TriviumGates : Sync{
+    type Bit : Clone + Send + Sync;
+    type Output : Send;
+
+    /// The new bit of a register : the XOR of `xors` and of the AND of `and`.
+    fn update(&self, xors : &[&Self::Bit], and : [&Self::Bit;2]) -> Self::Bit;
+
+    /// A keystream bit : the XOR of `xors`.
+    fn output(&self, xors : &[&Self::Bit]) -> Self::Output;
+
+    fn constant(&self, value : bool) -> Self::Bit;
+}
+
+
+impl TriviumGates for Clear{
+    type Bit = bool;
+    type Output = bool;
+
+    fn update(&self, xors : &[&bool], and : [&bool;2]) -> bool{
+        xors.iter().fold(and[0] & and[1], |acc, b| acc ^ **b)
+    }
+
+    fn output(&self, xors : &[&bool]) -> bool{
+        xors.iter().fold(false, |acc, b| acc ^ **b)
+    }
+
+    fn constant(&self, value : bool) -> bool{
+        value
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriviumVariant{
+    Trivium,
+    /// Trivium with a 128-bit key and IV, both also cycled in registers of their own.
+    Kreyvium,
+}
+
+impl TriviumVariant{
+    pub fn from_name(name : &str) -> Option<Self>{
+        match name{
+            "trivium" => Some(TriviumVariant::Trivium),
+            "kreyvium" => Some(TriviumVariant::Kreyvium),
+            _ => None,
+        }
+    }
+
+    pub fn key_bits(&self) -> usize{
+        match self{
+            TriviumVariant::Trivium => 80,
+            TriviumVariant::Kreyvium => 128,
+        }
+    }
+
+    pub fn iv_bits(&self) -> usize{
+        self.key_bits()
+    }
+}
+
+
+// The new bits of the registers a, b and c after a step, and its keystream bit if asked for
+type StepOutput<G> = (<G as TriviumGates>::Bit, <G as TriviumGates>::Bit, <G as TriviumGates>::Bit, Option<<G as TriviumGates>::Output>);
+
+
+/// The state of a Trivium or Kreyvium instance, the gates deciding whether it is clear or
+/// encrypted.
+///
+/// Each register is stored with its first bit last, so that the bit s_k of the register at the
+/// step j of a batch is at `len - k + j` until the batch is over.
+pub struct TriviumStream<'a, G : TriviumGates>{
+    gates : &'a G,
+    variant : TriviumVariant,
+    a : Vec<G::Bit>,
+    b : Vec<G::Bit>,
+    c : Vec<G::Bit>,
+    // Kreyvium only, K1 to K128 and IV1 to IV128
+    key : Vec<G::Bit>,
+    iv : Vec<G::Bit>,
+    step : usize,
+}
+
+
+impl<'a, G : TriviumGates> TriviumStream<'a, G>{
+    /// Loads the key and the IV, the bits K1 and IV1 first, and runs the 1152 warm-up steps.
+    pub fn new(gates : &'a G, variant : TriviumVariant, key : Vec<G::Bit>, iv : Vec<G::Bit>) -> Self{
+        assert_eq!(key.len(), variant.key_bits());
+        assert_eq!(iv.len(), variant.iv_bits());
+        let zeros = |n : usize| vec![gates.constant(false);n];
+        let ones = |n : usize| vec![gates.constant(true);n];
+
+        let (mut a, mut b, mut c) = match variant{
+            TriviumVariant::Trivium => (
+                [key.clone(), zeros(13)].concat(),
+                [iv.clone(), zeros(4)].concat(),
+                [zeros(108), ones(3)].concat(),
+            ),
+            TriviumVariant::Kreyvium => (
+                key[..93].to_vec(),
+                iv[..84].to_vec(),
+                [iv[84..].to_vec(), ones(66), zeros(1)].concat(),
+            ),
+        };
+        a.reverse();
+        b.reverse();
+        c.reverse();
+
+        let mut stream = TriviumStream { gates, variant, a, b, c, key, iv, step : 0 };
+        for _ in 0..WARMUP_STEPS / STEPS_PER_BATCH{
+            stream.run_batch(STEPS_PER_BATCH, false);
+        }
+        stream
+    }
+
+
+    /// The next `n` bits of the keystream.
+    pub fn next_keystream(&mut self, n : usize) -> Vec<G::Output>{
+        let mut keystream = Vec::with_capacity(n);
+        while keystream.len() < n{
+            let steps = STEPS_PER_BATCH.min(n - keystream.len());
+            keystream.extend(self.run_batch(steps, true).into_iter().map(Option::unwrap));
+        }
+        keystream
+    }
+
+
+    fn run_batch(&mut self, steps : usize, with_output : bool) -> Vec<Option<G::Output>>{
+        assert!(steps <= STEPS_PER_BATCH);
+        let (a, b, c) = (&self.a, &self.b, &self.c);
+        // s_k, s_{93 + k} and s_{177 + k}
+        let sa = |k : usize, j : usize| &a[a.len() + j - k];
+        let sb = |k : usize, j : usize| &b[b.len() + j - k];
+        let sc = |k : usize, j : usize| &c[c.len() + j - k];
+
+        let results : Vec<StepOutput<G>> = (0..steps).into_par_iter().map(|j| {
+            let mut xors_1 = vec![sa(66, j), sa(93, j), sb(78, j)];
+            let xors_2 = vec![sb(69, j), sb(84, j), sc(87, j)];
+            let mut xors_3 = vec![sc(66, j), sc(111, j), sa(69, j)];
+            let mut xors_z = vec![sa(66, j), sa(93, j), sb(69, j), sb(84, j), sc(66, j), sc(111, j)];
+
+            if self.variant == TriviumVariant::Kreyvium{
+                // K*_0 and IV*_0 go through the key and the IV from the last bit
+                let index = 127 - (self.step + j) % 128;
+                xors_1.push(&self.iv[index]);
+                xors_3.push(&self.key[index]);
+                xors_z.push(&self.key[index]);
+            }
+
+            let (t_1, (t_2, t_3)) = rayon::join(
+                || self.gates.update(&xors_1, [sa(91, j), sa(92, j)]),
+                || rayon::join(
+                    || self.gates.update(&xors_2, [sb(82, j), sb(83, j)]),
+                    || self.gates.update(&xors_3, [sc(109, j), sc(110, j)]),
+                ),
+            );
+            let z = with_output.then(|| self.gates.output(&xors_z));
+            (t_1, t_2, t_3, z)
+        }).collect();
+
+        let mut output = Vec::with_capacity(steps);
+        for (t_1, t_2, t_3, z) in results{
+            self.a.push(t_3);
+            self.b.push(t_1);
+            self.c.push(t_2);
+            output.push(z);
+        }
+        self.a.drain(..self.a.len() - 93);
+        self.b.drain(..self.b.len() - 84);
+        self.c.drain(..self.c.len() - 111);
+        self.step += steps;
+        output
+    }
+}
+
+
+
+pub struct TriviumTestVector{
+    pub key : &'static str,
+    pub iv : &'static str,
+    pub keystream : &'static str,
+}
+
+
+// eSTREAM set 1 vector 0 and set 6 vector 0
+pub const TRIVIUM_TEST_VECTORS : [TriviumTestVector;2] = [
+    TriviumTestVector {
+        key : "80000000000000000000",
+        iv : "00000000000000000000",
+        keystream : "38EB86FF730D7A9CAF8DF13A4420540DBB7B651464C87501552041C249F29A64D2FBF515610921EBE06C8F92CECF7F8098FF20CCCC6A62B97BE8EF7454FC80F9",
+    },
+    TriviumTestVector {
+        key : "0053A6F94C9FF24598EB",
+        iv : "0D74DB42A91077DE45AC",
+        keystream : "F4CD954A717F26A7D6930830C4E7CF0819F80E03F25F342C64ADC66ABA7F8A8E6EAA49F23632AE3CD41A7BD290A0132F81C6D4043B6E397D7388F3A03B5FE358",
+    },
+];
+
+
+pub const KREYVIUM_TEST_VECTORS : [TriviumTestVector;1] = [
+    TriviumTestVector {
+        key : "00000000000000000000000000000000",
+        iv : "00000000000000000000000000000000",
+        keystream : "26DCF1F4BC0F1922",
+    },
+];
+
+
+impl TriviumTestVector{
+    pub fn for_variant(variant : TriviumVariant) -> &'static [TriviumTestVector]{
+        match variant{
+            TriviumVariant::Trivium => &TRIVIUM_TEST_VECTORS,
+            TriviumVariant::Kreyvium => &KREYVIUM_TEST_VECTORS,
+        }
+    }
+
+    pub fn key_bits(&self) -> Vec<bool>{
+        hex_to_bits(self.key)
+    }
+
+    pub fn iv_bits(&self) -> Vec<bool>{
+        hex_to_bits(self.iv)
+    }
+}
+
+
+// As in the eSTREAM reference code, the bytes are read as a little endian integer whose most
+// significant bit is K1
+fn hex_to_bits(hex : &str) -> Vec<bool>{
+    let bytes : Vec<u8> = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+    bytes.iter().rev().flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)).collect()
+}
+
+// and the keystream is packed least significant bit first
+fn bits_to_hex(bits : &[bool]) -> String{
+    bits.chunks(8)
+        .map(|byte| format!("{:02X}", byte.iter().rev().fold(0u8, |acc, b| 2 * acc + *b as u8)))
+        .collect()
+}
+
+
+
+/// Generates 64 bits of keystream from an encrypted key and IV on the first test vector.
+pub fn demo_trivium(variant : TriviumVariant){
+    let parameters = PARAMETERS_40;
+
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+    let vector = &TriviumTestVector::for_variant(variant)[0];
+    let encoding = OddTriviumGates::input_encoding(variant);
+    let encrypt = |bits : Vec<bool>| bits.iter().map(|b| client_key.encrypt_arithmetic(*b as u64, &encoding)).collect();
+
+    let gates = OddTriviumGates::new(server_key, variant);
+
+    let start = Instant::now();
+    let mut stream = TriviumStream::new(&gates, variant, encrypt(vector.key_bits()), encrypt(vector.iv_bits()));
+    println!("{:?} warm-up : {:?}", variant, start.elapsed());
+
+    let start = Instant::now();
+    let keystream = stream.next_keystream(64);
+    println!("{:?} 64 keystream bits : {:?}", variant, start.elapsed());
+
+    let keystream_bits : Vec<bool> = keystream.iter().map(|c| client_key.decrypt(c) == 1).collect();
+    println!("{}", bits_to_hex(&keystream_bits));
+    println!("Expected:");
+    println!("{}", &vector.keystream[..16]);
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_trivium_kreyvium_test_vectors(){
+        for variant in [TriviumVariant::Trivium, TriviumVariant::Kreyvium]{
+            for vector in TriviumTestVector::for_variant(variant){
+                let mut stream = TriviumStream::new(&Clear, variant, vector.key_bits(), vector.iv_bits());
+                // in two calls, so that the batches are not aligned
+                let n = vector.keystream.len() * 4;
+                let mut keystream = stream.next_keystream(n / 2 + 3);
+                keystream.extend(stream.next_keystream(n / 2 - 3));
+                assert_eq!(bits_to_hex(&keystream), vector.keystream, "{:?}", variant);
+            }
+        }
+    }
+    // The warm-up is 1152 steps of gadgets, so only the first keystream bits are checked
+    #[test]
+    fn test_odd_trivium_gates(){
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (client_key, server_key) = (keys.client_key(), keys.server_key());
+
+        for variant in [TriviumVariant::Trivium, TriviumVariant::Kreyvium]{
+            let vector = &TriviumTestVector::for_variant(variant)[0];
+            let encoding = OddTriviumGates::input_encoding(variant);
+            let encrypt = |bits : Vec<bool>| bits.iter().map(|b| client_key.encrypt_arithmetic(*b as u64, &encoding)).collect();
+
+            let gates = OddTriviumGates::new(server_key, variant);
+            let mut stream = TriviumStream::new(&gates, variant, encrypt(vector.key_bits()), encrypt(vector.iv_bits()));
+            let keystream : Vec<bool> = stream.next_keystream(16).iter().map(|c| client_key.decrypt(c) == 1).collect();
+            assert_eq!(bits_to_hex(&keystream), vector.keystream[..4], "{:?}", variant);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use tfhe::odd::prelude::*;
+
+use super::{TriviumGates, TriviumVariant};
+
+
+/// The Trivium gates as single bootstrapping gadgets on odd.
+///
+/// The bits of the state are encoded with `new_canonical_binary(1, p)`, each new bit being the
+/// output of a gadget : the AND is folded with the XORs by giving its inputs a weight w, with
+/// p = 9 and w = 4 for the Trivium updates (up to three XORs) and p = 11 and w = 5 for the
+/// Kreyvium ones (up to four XORs). The keystream bits come out with the parity encoding, so that
+/// the message bits are then XORed for free.
+pub struct OddTriviumGates<'a>{
+    server_key : &'a ServerKey,
+    state_encoding : Encoding,
+    // by number of XORed inputs, from 3
+    updates : Vec<Gadget>,
+    output : Gadget,
+}
+
+
+impl<'a> OddTriviumGates<'a>{
+    fn modulus_and_weight(variant : TriviumVariant) -> (u64, u64){
+        match variant{
+            TriviumVariant::Trivium => (9, 4),
+            TriviumVariant::Kreyvium => (11, 5),
+        }
+    }
+
+    /// The encoding of the key and IV bits given by the client.
+    pub fn input_encoding(variant : TriviumVariant) -> Encoding{
+        Encoding::new_canonical_binary(1, Self::modulus_and_weight(variant).0)
+    }
+
+    pub fn new(server_key : &'a ServerKey, variant : TriviumVariant) -> Self{
+        let (modulus, weight) = Self::modulus_and_weight(variant);
+        let xor_and = |xor_count : usize| Gadget::new([vec![1;xor_count], vec![weight;2]].concat(),
+                                                      modulus,
+                                                      &move |x : &[bool]| x[..xor_count].iter().fold(x[xor_count] & x[xor_count + 1], |acc, b| acc ^ b));
+        let output_xor_count = match variant{
+            TriviumVariant::Trivium => 6,
+            TriviumVariant::Kreyvium => 7,
+        };
+
+        OddTriviumGates{
+            server_key,
+            state_encoding : Self::input_encoding(variant),
+            updates : match variant{
+                TriviumVariant::Trivium => vec![xor_and(3)],
+                TriviumVariant::Kreyvium => vec![xor_and(3), xor_and(4)],
+            },
+            output : Gadget::new(vec![1;output_xor_count], modulus, &|x : &[bool]| x.iter().fold(false, |acc, b| acc ^ b)),
+        }
+    }
+}
+
+
+impl TriviumGates for OddTriviumGates<'_>{
+    type Bit = Ciphertext;
+    type Output = Ciphertext;
+
+    fn update(&self, xors : &[&Ciphertext], and : [&Ciphertext;2]) -> Ciphertext{
+        let inputs : Vec<Ciphertext> = xors.iter().chain(and.iter()).map(|c| (*c).clone()).collect();
+        self.server_key.apply_gadget(&self.updates[xors.len() - 3], &inputs, &self.state_encoding)
+    }
+
+    fn output(&self, xors : &[&Ciphertext]) -> Ciphertext{
+        let inputs : Vec<Ciphertext> = xors.iter().map(|c| (*c).clone()).collect();
+        self.server_key.apply_gadget(&self.output, &inputs, &Encoding::parity_encoding())
+    }
+
+    // the gadgets do not take trivial ciphertexts, so the constants are noiseless encryptions
+    fn constant(&self, value : bool) -> Ciphertext{
+        self.server_key.trivial_encrypt_arithmetic(value as u64, &self.state_encoding)
+    }
+}
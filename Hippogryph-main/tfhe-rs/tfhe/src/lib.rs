@@ -123,6 +123,9 @@ pub mod keycache;
 
 pub mod safe_serialization;
 
+#[cfg(feature = "zk-pok")]
+pub mod zk;
+
 pub mod conformance;
 
 pub mod named;
@@ -3,6 +3,7 @@
 //! This module implements the ciphertext structure containing an encryption of a Boolean message.
 
 use crate::core_crypto::entities::*;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 
@@ -15,7 +16,7 @@ pub enum Ciphertext {
 type ZoElem = u64;
 type ZpElem = u64;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Encoding {
     origin_modulus: u64,         // o in the paper
     parts: Vec<HashSet<ZpElem>>, //element of index i \in \Zo returns the elems of \Zp associated with i in the encoding.
@@ -149,16 +150,23 @@ impl Encoding {
 
     pub fn new(origin_modulus: u64, parts: Vec<HashSet<ZpElem>>, modulus_p: u64) -> Self {
         assert!(parts.iter().all(|part| part.iter().all(|x| *x < modulus_p)));
+        match Self::try_new(origin_modulus, parts, modulus_p) {
+            Some(new_encoding) => new_encoding,
+            None => panic!("This Arithmetic Encoding is not correct !"),
+        }
+    }
+
+    // Same as new, but returns None instead of panicking when the encoding is not valid
+    pub fn try_new(origin_modulus: u64, parts: Vec<HashSet<ZpElem>>, modulus_p: u64) -> Option<Self> {
+        if origin_modulus != parts.len() as u64 || parts.iter().any(|part| part.iter().any(|x| *x >= modulus_p)) {
+            return None;
+        }
         let new_encoding = Self {
             origin_modulus,
             parts,
             modulus_p,
         };
-        if new_encoding.is_valid() {
-            new_encoding
-        } else {
-            panic!("This Arithmetic Encoding is not correct !");
-        }
+        new_encoding.is_valid().then_some(new_encoding)
     }
 
     pub fn new_canonical(
@@ -206,13 +214,19 @@ impl Encoding {
                 }
             };
         }
-        let parts = (0..self.origin_modulus)
+        // f may send Zo beyond itself (e.g. a sum of bits), the output then needs one part per image
+        let output_origin_modulus = parts_hashmap
+            .keys()
+            .map(|y| y + 1)
+            .max()
+            .map_or(self.origin_modulus, |bound| bound.max(self.origin_modulus));
+        let parts = (0..output_origin_modulus)
             .map(|i| match parts_hashmap.get(&i) {
                 Some(part) => part.to_owned(),
                 None => HashSet::new(),
             })
             .collect();
-        Self::new(self.origin_modulus, parts, self.modulus_p)
+        Self::new(output_origin_modulus, parts, self.modulus_p)
     }
 
     pub fn multiply_encoding_by_constant(&self, constant: ZpElem) -> Self {
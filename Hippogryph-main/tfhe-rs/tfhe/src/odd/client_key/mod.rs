@@ -6,6 +6,9 @@
 use crate::odd::{engine::WithThreadLocalEngine, prelude::*};
 use crate::odd::parameters::CustomOddParameters;
 use crate::core_crypto::entities::*;
+use crate::core_crypto::commons::math::random::Seed;
+use crate::core_crypto::prelude::{DynamicDistribution, EncryptionKeyChoice};
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
 use super::{ciphertext::Encoding, engine::OddEngine};
@@ -17,7 +20,7 @@ use super::{ciphertext::Encoding, engine::OddEngine};
 /// * `glwe_secret_key` - a GLWE secret key, used to generate the bootstrapping keys and key
 /// switching keys.
 /// * `parameters` - the cryptographic parameter set.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ClientKey {
     pub(crate) lwe_secret_key: LweSecretKeyOwned<u64>,
     pub(crate) glwe_secret_key: GlweSecretKeyOwned<u64>,
@@ -51,7 +54,15 @@ impl ClientKey {
         OddEngine::with_thread_local_mut(|engine| engine.encrypt_arithmetic(message, encoding, self))
     }
 
-    
+    /// Encrypt a message with an engine seeded by `seed`: the same seed always gives the same
+    /// ciphertext.
+    ///
+    /// WARNING: two messages encrypted with the same seed share the same mask, this must only
+    /// be used for known-answer tests and reproducible fixtures.
+    pub fn encrypt_arithmetic_with_seed(&self, message: u64, encoding : &Encoding, seed: Seed) -> Ciphertext {
+        assert!(encoding.is_canonical());
+        OddEngine::new_from_seed(seed).encrypt_arithmetic(message, encoding, self)
+    }
 
     /// Decrypt a ciphertext encrypting a Boolean message using the client key.
     ///
@@ -76,6 +87,20 @@ impl ClientKey {
         OddEngine::with_thread_local_mut(|engine| engine.decrypt(ct, self))
     }
 
+    /// The LWE key the ciphertexts are encrypted under, with its noise distribution.
+    pub fn encryption_key_and_noise(&self) -> (LweSecretKeyView<'_, u64>, DynamicDistribution<u64>) {
+        match self.parameters.encryption_key_choice {
+            EncryptionKeyChoice::Big => (
+                self.glwe_secret_key.as_lwe_secret_key(),
+                self.parameters.glwe_noise_distribution,
+            ),
+            EncryptionKeyChoice::Small => (
+                self.lwe_secret_key.as_view(),
+                self.parameters.lwe_noise_distribution,
+            ),
+        }
+    }
+
 
     /// Allocate and generate a client key.
     ///
@@ -94,5 +119,13 @@ impl ClientKey {
     pub fn new(parameter_set: &CustomOddParameters) -> ClientKey {
         OddEngine::with_thread_local_mut(|engine| engine.create_client_key(*parameter_set))
     }
+
+    /// Deterministically generate a client key from a seed.
+    ///
+    /// WARNING: Using a deterministic seed renders the key generation insecure, it is only meant
+    /// for test fixtures, known-answer tests and benchmarks.
+    pub fn new_with_seed(parameter_set: &CustomOddParameters, seed: Seed) -> ClientKey {
+        OddEngine::new_from_seed(seed).create_client_key(*parameter_set)
+    }
 }
 
@@ -0,0 +1,201 @@
+//! Optional debug oracle, only compiled with the `odd-debug-oracle` feature.
+//!
+//! The server side of `odd` never takes a client key. To inspect intermediate values while
+//! developing a circuit, a [`DebugOracle`] can be registered at runtime: the homomorphic
+//! operators then hand it their intermediate ciphertexts and accumulators, and the oracle is free
+//! to decrypt and log them. When no oracle is registered these hooks do nothing, and without the
+//! feature they are not compiled at all.
+
+use std::sync::{Arc, RwLock};
+
+use crate::core_crypto::algorithms::*;
+use crate::core_crypto::entities::*;
+use crate::core_crypto::prelude::{ContiguousEntityContainer, PlaintextCount};
+use crate::odd::client_key::ClientKey;
+use crate::odd::engine::OddEngine;
+use crate::odd::prelude::*;
+
+/// Something able to look inside intermediate ciphertexts.
+pub trait DebugOracle: Send + Sync {
+    /// Decrypt a ciphertext into its element of Zo.
+    fn decrypt(&self, ct: &Ciphertext) -> u64;
+
+    /// Return the noise of a ciphertext, scaled on the torus as a signed 64 bits integer.
+    fn noise(&self, ct: &Ciphertext) -> i64;
+
+    /// Called by the homomorphic operators on their intermediate LWE ciphertexts.
+    fn log_ciphertexts(&self, label: &str, cts: &[Ciphertext]) {
+        let values: Vec<u64> = cts.iter().map(|ct| self.decrypt(ct)).collect();
+        let max_noise = cts
+            .iter()
+            .filter(|ct| matches!(ct, Ciphertext::EncodingEncrypted(..)))
+            .map(|ct| self.noise(ct).unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        println!(
+            "DEBUG {} : {:?} (max noise : 2^{:.2})",
+            label,
+            values,
+            (max_noise as f64).log2()
+        );
+    }
+
+    /// Called by the homomorphic operators on their intermediate GLWE ciphertexts
+    /// (accumulators, common factors, ...).
+    fn log_glwe(&self, label: &str, glwe: &GlweCiphertextOwned<u64>) {
+        let _ = (label, glwe);
+    }
+}
+
+static DEBUG_ORACLE: RwLock<Option<Arc<dyn DebugOracle>>> = RwLock::new(None);
+
+/// Register the oracle that will receive the intermediate values of all the following
+/// homomorphic operations, replacing the previous one if any.
+pub fn register_debug_oracle(oracle: Arc<dyn DebugOracle>) {
+    *DEBUG_ORACLE.write().unwrap() = Some(oracle);
+}
+
+/// Remove the registered oracle, if any.
+pub fn unregister_debug_oracle() {
+    *DEBUG_ORACLE.write().unwrap() = None;
+}
+
+/// Return the registered oracle, if any.
+pub fn debug_oracle() -> Option<Arc<dyn DebugOracle>> {
+    DEBUG_ORACLE.read().unwrap().clone()
+}
+
+/// Hand intermediate ciphertexts to the registered oracle, if any.
+pub fn log_ciphertexts(label: &str, cts: &[Ciphertext]) {
+    if let Some(oracle) = debug_oracle() {
+        oracle.log_ciphertexts(label, cts);
+    }
+}
+
+/// Hand an intermediate GLWE ciphertext to the registered oracle, if any.
+pub(crate) fn log_glwe(label: &str, glwe: &GlweCiphertextOwned<u64>) {
+    if let Some(oracle) = debug_oracle() {
+        oracle.log_glwe(label, glwe);
+    }
+}
+
+/// Oracle holding the client key: it decrypts everything it is given and prints it.
+///
+/// Note that it does not go through the thread-local [`OddEngine`], since it is called from
+/// inside the engine methods.
+pub struct ClientKeyOracle {
+    client_key: ClientKey,
+}
+
+impl ClientKeyOracle {
+    pub fn new(client_key: ClientKey) -> Self {
+        Self { client_key }
+    }
+
+    // Intermediate ciphertexts may live under the small key (after a keyswitch) or under the big
+    // one (after a bootstrap), the right one is picked from their dimension.
+    fn secret_key_for(&self, lwe: &LweCiphertextOwned<u64>) -> LweSecretKey<&[u64]> {
+        if lwe.lwe_size().to_lwe_dimension() == self.client_key.lwe_secret_key.lwe_dimension() {
+            LweSecretKey::from_container(self.client_key.lwe_secret_key.as_ref())
+        } else {
+            self.client_key.glwe_secret_key.as_lwe_secret_key()
+        }
+    }
+}
+
+impl DebugOracle for ClientKeyOracle {
+    fn decrypt(&self, ct: &Ciphertext) -> u64 {
+        match ct {
+            Ciphertext::Trivial(m) => *m,
+            Ciphertext::EncodingEncrypted(lwe, encoding) => {
+                OddEngine::decrypt_arithmetic(&self.secret_key_for(lwe), lwe, encoding)
+            }
+        }
+    }
+
+    fn noise(&self, ct: &Ciphertext) -> i64 {
+        match ct {
+            Ciphertext::Trivial(_) => panic!("No error level with trivial ciphertext"),
+            Ciphertext::EncodingEncrypted(lwe, encoding) => {
+                OddEngine::noise_arithmetic(&self.secret_key_for(lwe), lwe, encoding)
+            }
+        }
+    }
+
+    fn log_glwe(&self, label: &str, glwe: &GlweCiphertextOwned<u64>) {
+        let mut plaintext_list =
+            PlaintextList::new(0u64, PlaintextCount(glwe.polynomial_size().0));
+        decrypt_glwe_ciphertext(&self.client_key.glwe_secret_key, glwe, &mut plaintext_list);
+        println!("DEBUG {label} :");
+        plaintext_list
+            .iter()
+            .for_each(|plaintext| println!("{:064b} = {}", plaintext.0, plaintext.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odd::keycache::KEY_CACHE;
+    use std::sync::Mutex;
+
+    // Decrypts like ClientKeyOracle, but keeps what it is given instead of printing it
+    struct RecordingOracle {
+        inner: ClientKeyOracle,
+        logs: Mutex<Vec<(String, Vec<u64>)>>,
+    }
+
+    impl DebugOracle for RecordingOracle {
+        fn decrypt(&self, ct: &Ciphertext) -> u64 {
+            self.inner.decrypt(ct)
+        }
+
+        fn noise(&self, ct: &Ciphertext) -> i64 {
+            self.inner.noise(ct)
+        }
+
+        fn log_ciphertexts(&self, label: &str, cts: &[Ciphertext]) {
+            let values = cts.iter().map(|ct| self.decrypt(ct)).collect();
+            self.logs.lock().unwrap().push((label.to_owned(), values));
+        }
+    }
+
+    #[test]
+    fn test_debug_oracle_logs_tree_output() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+
+        let oracle = Arc::new(RecordingOracle {
+            inner: ClientKeyOracle::new(cks.clone()),
+            logs: Mutex::new(Vec::new()),
+        });
+        register_debug_oracle(oracle.clone());
+
+        let encoding = Encoding::new_canonical(3, vec![0, 1, 2], 7);
+        let encoding_out = Encoding::new_canonical(2, vec![0, 1], 3);
+        let inputs = vec![
+            cks.encrypt_arithmetic(2, &encoding),
+            cks.encrypt_arithmetic(1, &encoding),
+        ];
+        // (2 + 3 * 1) % 4 = 1, decomposed in base 2 over the two output digits
+        let digits = sks.apply_multivariate_lut(
+            &inputs,
+            &|x| (x[0] + 3 * x[1]) % 4,
+            &[encoding_out.clone(), encoding_out],
+        );
+        unregister_debug_oracle();
+
+        let decrypted: Vec<u64> = digits.iter().map(|ct| cks.decrypt(ct)).collect();
+        assert_eq!(decrypted, vec![1, 0]);
+
+        // other tests may log concurrently, only the outputs of this tree are looked for
+        let logs = oracle.logs.lock().unwrap();
+        let outputs: Vec<&Vec<u64>> = logs
+            .iter()
+            .filter(|(label, _)| label == "MULTIVARIATE OUTPUT")
+            .map(|(_, values)| values)
+            .collect();
+        assert!(outputs.contains(&&vec![1]));
+        assert!(outputs.contains(&&vec![0]));
+    }
+}
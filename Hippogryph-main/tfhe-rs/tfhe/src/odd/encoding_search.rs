@@ -0,0 +1,317 @@
+//! Automatic search of the encodings evaluating a function with a single bootstrapping.
+//!
+//! Given the truth table of a function of several small inputs, the solver looks for a modulus
+//! p, coefficients `c_i` and a constant `k` such that the function only depends on
+//! `k + sum c_i * x_i` in Zp, where each input `x_i` is encrypted with the encoding `x -> x`. The
+//! bootstrapping then maps this sum to the output, through an encoding of Zp whose parts are
+//! indexed by the values of the function.
+//!
+//! The moduli are tried in increasing order, so the first solution found has the smallest p. All
+//! the coefficients and constants are tried for each p. An encoding `{a, b}` of a bit is the
+//! affine function `a + (b - a) * x`, and its validity only depends on `b - a`: the search thus
+//! covers every encoding of the boolean inputs, and a failure is a proof that no solution exists.
+//! For larger inputs, only the encodings `x -> c_i * x` are considered, so a failure only means
+//! that none of them works.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct EncodingSolution {
+    /// The modulus p of the linear combination.
+    pub modulus: u64,
+    /// The encodings `x -> x` in Zp, in which the inputs are expected.
+    pub input_encodings: Vec<Encoding>,
+    /// The coefficient applied to each input.
+    pub coefficients: Vec<u64>,
+    /// The constant added to the linear combination.
+    pub constant: u64,
+    /// The encodings of the inputs once multiplied by their coefficient.
+    pub weighted_input_encodings: Vec<Encoding>,
+    /// The encoding of the linear combination (constant included) : the part of index `y` holds
+    /// the elements of Zp reached when the function is equal to `y`. This is the intermediate
+    /// encoding of the bootstrapping.
+    pub output_encoding: Encoding,
+}
+
+/// The reason why [`search_encodings`] found no solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchFailure {
+    /// All the inputs are bits, and none of their encodings evaluates the function with a
+    /// single bootstrapping.
+    NoSolution,
+    /// Some input is not a bit, and none of its encodings `x -> c * x` works. Other encodings of
+    /// this input were not tried, so a solution may still exist.
+    NotFound,
+}
+
+impl EncodingSolution {
+    /// Evaluate the solution in the clear, as the bootstrapping would do.
+    pub fn evaluate(&self, inputs: &[u64]) -> u64 {
+        let sum = inputs
+            .iter()
+            .zip(&self.coefficients)
+            .fold(self.constant, |acc, (x, c)| (acc + x * c) % self.modulus);
+        self.output_encoding
+            .inverse_encoding(sum)
+            .expect("The sum is outside of the output encoding")
+    }
+}
+
+/// Search the encodings evaluating the function given by `truth_table`, with p at most
+/// `max_modulus`.
+///
+/// The truth table is indexed by the mixed radix value of the inputs, over `input_moduli`, the
+/// last input being the least significant one. The origin modulus of the output is the largest
+/// value of the table plus one. When nothing is found with `p <= max_modulus`, the error tells
+/// whether the search was exhaustive, see [`SearchFailure`].
+pub fn search_encodings(truth_table: &[u64], input_moduli: &[u64], max_modulus: u64) -> Result<EncodingSolution, SearchFailure> {
+    assert!(!input_moduli.is_empty(), "No encoding search without inputs");
+    assert_eq!(
+        truth_table.len() as u64,
+        input_moduli.iter().product::<u64>(),
+        "The truth table should have one entry per value of the inputs"
+    );
+
+    let output_modulus = truth_table.iter().max().unwrap() + 1;
+    let assignments: Vec<Vec<u64>> = input_moduli
+        .iter()
+        .map(|o| 0..*o)
+        .multi_cartesian_product()
+        .collect();
+    let arity = input_moduli.len();
+    let max_input_modulus = *input_moduli.iter().max().unwrap();
+
+    for p in max_input_modulus.max(2)..=max_modulus {
+        // p = 2 only works for the special bootstrapping of one bit to one bit
+        if p == 2 && output_modulus > 2 {
+            continue;
+        }
+
+        let input_encodings: Option<Vec<Encoding>> = input_moduli
+            .iter()
+            .map(|o| Encoding::try_new(*o, (0..*o).map(|x| HashSet::from([x])).collect(), p))
+            .collect();
+        let Some(input_encodings) = input_encodings else {
+            continue;
+        };
+
+        // with an odd p every partition of Zp can be bootstrapped, so the constant does not matter
+        let constants = if p % 2 == 1 { 0..1 } else { 0..p };
+
+        for constant in constants {
+            for coefficients in (0..arity).map(|_| 0..p).multi_cartesian_product() {
+                if let Some(solution) = try_solution(truth_table, &assignments, output_modulus, p, &input_encodings, &coefficients, constant) {
+                    return Ok(solution);
+                }
+            }
+        }
+    }
+    if input_moduli.iter().all(|o| *o == 2) {
+        Err(SearchFailure::NoSolution)
+    } else {
+        Err(SearchFailure::NotFound)
+    }
+}
+
+/// Same as [`search_encodings`], from a function of the inputs instead of its truth table.
+pub fn search_encodings_for_fn(
+    f: &dyn Fn(&[u64]) -> u64,
+    input_moduli: &[u64],
+    max_modulus: u64,
+) -> Result<EncodingSolution, SearchFailure> {
+    let truth_table: Vec<u64> = input_moduli
+        .iter()
+        .map(|o| 0..*o)
+        .multi_cartesian_product()
+        .map(|x| f(&x))
+        .collect();
+    search_encodings(&truth_table, input_moduli, max_modulus)
+}
+
+fn try_solution(
+    truth_table: &[u64],
+    assignments: &[Vec<u64>],
+    output_modulus: u64,
+    p: u64,
+    input_encodings: &[Encoding],
+    coefficients: &[u64],
+    constant: u64,
+) -> Option<EncodingSolution> {
+    // value of the function for each element of Zp, if reached
+    let mut image: Vec<Option<u64>> = vec![None; p as usize];
+    for (x, y) in assignments.iter().zip(truth_table) {
+        let sum = x
+            .iter()
+            .zip(coefficients)
+            .fold(constant, |acc, (xi, c)| (acc + xi * c) % p);
+        if *image[sum as usize].get_or_insert(*y) != *y {
+            return None;
+        }
+    }
+
+    let mut parts = vec![HashSet::new(); output_modulus as usize];
+    image
+        .iter()
+        .enumerate()
+        .filter_map(|(sum, y)| y.map(|y| (sum as u64, y)))
+        .for_each(|(sum, y)| {
+            parts[y as usize].insert(sum);
+        });
+    // checks the negacyclicity for an even p
+    let output_encoding = Encoding::try_new(output_modulus, parts, p)?;
+
+    let weighted_input_encodings: Option<Vec<Encoding>> = input_encodings
+        .iter()
+        .zip(coefficients)
+        .map(|(encoding, c)| {
+            let parts = (0..encoding.get_origin_modulus())
+                .map(|x| HashSet::from([x * c % p]))
+                .collect();
+            Encoding::try_new(encoding.get_origin_modulus(), parts, p)
+        })
+        .collect();
+
+    Some(EncodingSolution {
+        modulus: p,
+        input_encodings: input_encodings.to_vec(),
+        coefficients: coefficients.to_vec(),
+        constant,
+        weighted_input_encodings: weighted_input_encodings?,
+        output_encoding,
+    })
+}
+
+impl ServerKey {
+    /// Evaluate the function found by [`search_encodings`] with a single bootstrapping.
+    ///
+    /// The inputs must be encrypted with `solution.input_encodings`, the output is encrypted with
+    /// the canonical `encoding_out`.
+    pub fn apply_encoding_solution(&self, solution: &EncodingSolution, inputs: &[Ciphertext], encoding_out: &Encoding) -> Ciphertext {
+        assert_eq!(inputs.len(), solution.coefficients.len(), "Wrong number of inputs for this solution");
+        let p = solution.modulus;
+
+        let mut weighted_inputs: Vec<Ciphertext> = inputs
+            .iter()
+            .zip(&solution.input_encodings)
+            .zip(&solution.coefficients)
+            .map(|((input, input_encoding), c)| match input {
+                Ciphertext::EncodingEncrypted(_, encoding) => {
+                    assert_eq!(encoding, input_encoding, "The inputs should be encrypted with the input encodings of the solution");
+                    self.encoding_switching_mul_constant(input, *c)
+                }
+                Ciphertext::Trivial(_) => panic!("No encoding solution with trivial ciphertexts"),
+            })
+            .collect();
+        if solution.constant != 0 {
+            weighted_inputs[0] = self.simple_plaintext_sum(&weighted_inputs[0], solution.constant, p);
+        }
+
+        OddEngine::with_thread_local_mut(|engine| {
+            engine.exec_gadget_with_extraction(
+                &solution.weighted_input_encodings,
+                &solution.output_encoding,
+                encoding_out,
+                &weighted_inputs,
+                self,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odd::keycache::KEY_CACHE;
+
+    fn check_solution(solution: &EncodingSolution, f: &dyn Fn(&[u64]) -> u64, input_moduli: &[u64]) {
+        for x in input_moduli.iter().map(|o| 0..*o).multi_cartesian_product() {
+            assert_eq!(solution.evaluate(&x), f(&x), "inputs : {x:?}");
+        }
+    }
+
+    #[test]
+    fn test_search_boolean_gates() {
+        let gates: Vec<(&dyn Fn(&[u64]) -> u64, usize, u64)> = vec![
+            (&|x| x[0] & x[1], 2, 3),
+            (&|x| x[0] | x[1], 2, 3),
+            (&|x| (x[0] + x[1] + x[2] >= 2) as u64, 3, 5),
+            (&|x| if x[0] == 1 { x[1] } else { x[2] }, 3, 7),
+        ];
+        for (f, arity, expected_modulus) in gates {
+            let input_moduli = vec![2; arity];
+            let solution = search_encodings_for_fn(f, &input_moduli, 17).unwrap();
+            assert_eq!(solution.modulus, expected_modulus);
+            check_solution(&solution, f, &input_moduli);
+        }
+    }
+
+    #[test]
+    fn test_search_xor() {
+        // the parity of a sum of bits is linear in Z2
+        let f = |x: &[u64]| x.iter().sum::<u64>() % 2;
+        let solution = search_encodings_for_fn(&f, &[2, 2, 2], 17).unwrap();
+        assert_eq!(solution.modulus, 2);
+        check_solution(&solution, &f, &[2, 2, 2]);
+    }
+
+    #[test]
+    fn test_search_recomposition() {
+        // the recomposition of a nibble from its bits, as done by hand for the AES
+        let f = |x: &[u64]| 8 * x[0] + 4 * x[1] + 2 * x[2] + x[3];
+        let solution = search_encodings_for_fn(&f, &[2, 2, 2, 2], 17).unwrap();
+        assert!(solution.modulus <= 17);
+        check_solution(&solution, &f, &[2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_search_small_moduli() {
+        let f = |x: &[u64]| (x[0] + 2 * x[1]) % 3;
+        let solution = search_encodings_for_fn(&f, &[3, 3], 17).unwrap();
+        check_solution(&solution, &f, &[3, 3]);
+
+        let f = |x: &[u64]| (x[0] * x[1] > 2) as u64;
+        let solution = search_encodings_for_fn(&f, &[3, 4], 31).unwrap();
+        check_solution(&solution, &f, &[3, 4]);
+    }
+
+    #[test]
+    fn test_search_without_solution() {
+        // the majority needs 5 elements
+        let f = |x: &[u64]| (x[0] + x[1] + x[2] >= 2) as u64;
+        assert_eq!(search_encodings_for_fn(&f, &[2, 2, 2], 4).unwrap_err(), SearchFailure::NoSolution);
+
+        // an AND of 4 bits needs p > 4 for odd moduli
+        let f = |x: &[u64]| x.iter().product();
+        assert_eq!(search_encodings_for_fn(&f, &[2, 2, 2, 2], 3).unwrap_err(), SearchFailure::NoSolution);
+
+        // with an input in Z3, only a part of its encodings is tried
+        let f = |x: &[u64]| (x[0] * x[1] > 2) as u64;
+        assert_eq!(search_encodings_for_fn(&f, &[3, 4], 4).unwrap_err(), SearchFailure::NotFound);
+    }
+
+    #[test]
+    fn test_apply_encoding_solution() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+
+        let f = |x: &[u64]| (x[0] + x[1] + x[2] >= 2) as u64 + 2 * (x[0] ^ x[1] ^ x[2]);
+        let input_moduli = [2, 2, 2];
+        let solution = search_encodings_for_fn(&f, &input_moduli, 17).unwrap();
+        let encoding_out = Encoding::new_canonical(4, vec![0, 1, 2, 3], 5);
+
+        for x in input_moduli.iter().map(|o| 0..*o).multi_cartesian_product() {
+            let inputs: Vec<Ciphertext> = x
+                .iter()
+                .zip(&solution.input_encodings)
+                .map(|(xi, encoding)| cks.encrypt_arithmetic(*xi, encoding))
+                .collect();
+            let result = sks.apply_encoding_solution(&solution, &inputs, &encoding_out);
+            assert_eq!(cks.decrypt(&result), f(&x), "inputs : {x:?}");
+        }
+    }
+}
@@ -1,10 +1,14 @@
 #![allow(non_snake_case)] 
 
 
-use crate::core_crypto::prelude::{CiphertextModulus, Container, ContiguousEntityContainer, ContiguousEntityContainerMut, Fft, MonomialDegree, PBSOrder, PlaintextCount};
+use crate::core_crypto::prelude::{CiphertextModulus, ContiguousEntityContainer, DynamicDistribution, ContiguousEntityContainerMut, Fft, MonomialDegree, PBSOrder};
 use crate::odd::engine::polynomial_algorithms::polynomial_wrapping_monic_monomial_mul_assign;
 use crate::odd::engine::slice_algorithms::slice_wrapping_add_assign;
 use crate::odd::prelude::*;
+#[cfg(feature = "odd-debug-oracle")]
+use crate::odd::debug_oracle;
+#[cfg(feature = "pbs-stats")]
+use crate::odd::server_key::pbs_stats::{self, Operation};
 use crate::core_crypto::algorithms::*;
 use crate::core_crypto::commons::computation_buffers::ComputationBuffers;
 use crate::core_crypto::commons::generators::{DeterministicSeeder, EncryptionRandomGenerator};
@@ -12,6 +16,7 @@ use crate::core_crypto::commons::math::random::{DefaultRandomGenerator, Seeder};
 use crate::core_crypto::entities::*;
 
 use self::polynomial_algorithms::polynomial_karatsuba_wrapping_mul;
+use serde::{Deserialize, Serialize};
 
 
 /////Accumulator used in the BlindRotate part of the bootstrapping
@@ -138,20 +143,9 @@ impl Memory {
 
 
 
-    /// Return a tuple with buffers that matches the server key.
-    ///
-    /// - The first element is the accumulator for bootstrap step.
-    /// - The second element is a lwe buffer where the result of the of the bootstrap should be
-    ///   written
-    fn as_buffers(
-        &mut self,
-        server_key: &ServerKey,
-        enc_in : &Encoding,
-        enc_out : &Encoding
-    ) -> BuffersRef<'_>{
-        let (mut accumulator, buffer_lwe_after_ks, buffer_lwe_after_pbs) = self.allocate_ciphertexts_for_bootstrapping(server_key);
-
-        ////accumulator filling
+    /// Fill the lookup table of a bootstrapping from `enc_in` to `enc_out`, whatever the sizes
+    /// of the bootstrapping key.
+    pub(crate) fn fill_accumulator(accumulator : &mut GlweCiphertext<&mut [u64]>, enc_in : &Encoding, enc_out : &Encoding){
         let p = enc_in.get_modulus();
         let new_p = enc_out.get_modulus() as u64;
         accumulator.get_mut_mask().as_mut().fill(0u64);
@@ -200,6 +194,25 @@ impl Memory {
             buffer_value = ((1 << 64) / new_p as u128) as u64 * new_1 as u64;
             accumulator.get_mut_body().as_mut()[N_poly / 2..].fill(buffer_value as u64);   //filling of the second half window
         }
+    }
+
+
+
+
+    /// Return a tuple with buffers that matches the server key.
+    ///
+    /// - The first element is the accumulator for bootstrap step.
+    /// - The second element is a lwe buffer where the result of the of the bootstrap should be
+    ///   written
+    fn as_buffers(
+        &mut self,
+        server_key: &ServerKey,
+        enc_in : &Encoding,
+        enc_out : &Encoding
+    ) -> BuffersRef<'_>{
+        let (mut accumulator, buffer_lwe_after_ks, buffer_lwe_after_pbs) = self.allocate_ciphertexts_for_bootstrapping(server_key);
+
+        Self::fill_accumulator(&mut accumulator, enc_in, enc_out);
 
         BuffersRef {
             lookup_table: accumulator,
@@ -249,7 +262,7 @@ impl Memory {
 /// In more details, it contains:
 /// * `bootstrapping_key` - a public key, used to perform the bootstrapping operation.
 /// * `key_switching_key` - a public key, used to perform the key-switching operation.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ServerKey {
     pub(crate) bootstrapping_key: FourierLweBootstrapKeyOwned,
     pub(crate) key_switching_key: LweKeyswitchKeyOwned<u64>,
@@ -302,14 +315,22 @@ impl Bootstrapper {
         }
     }
 
-    pub(crate) fn new_server_key(&mut self, cks: &ClientKey) -> ServerKey {
+    // Bootstrapping key from `input_key` to `output_key`, converted to the Fourier domain
+    pub(crate) fn new_fourier_bootstrapping_key(
+        &mut self,
+        input_key: &LweSecretKeyView<'_, u64>,
+        output_key: &GlweSecretKeyOwned<u64>,
+        base_log: DecompositionBaseLog,
+        level: DecompositionLevelCount,
+        noise: DynamicDistribution<u64>,
+    ) -> FourierLweBootstrapKeyOwned {
         let standard_bootstrapping_key: LweBootstrapKeyOwned<u64> =
             par_allocate_and_generate_new_lwe_bootstrap_key(
-                &cks.lwe_secret_key,
-                &cks.glwe_secret_key,
-                cks.parameters.pbs_base_log,
-                cks.parameters.pbs_level,
-                cks.parameters.glwe_noise_distribution,
+                input_key,
+                output_key,
+                base_log,
+                level,
+                noise,
                 CiphertextModulus::new_native(),
                 &mut self.encryption_generator,
             );
@@ -337,6 +358,18 @@ impl Bootstrapper {
             &mut fourier_bsk,
         );
 
+        fourier_bsk
+    }
+
+    pub(crate) fn new_server_key(&mut self, cks: &ClientKey) -> ServerKey {
+        let fourier_bsk = self.new_fourier_bootstrapping_key(
+            &cks.lwe_secret_key.as_view(),
+            &cks.glwe_secret_key,
+            cks.parameters.pbs_base_log,
+            cks.parameters.pbs_level,
+            cks.parameters.glwe_noise_distribution,
+        );
+
         // Convert the GLWE secret key into an LWE secret key:
         let big_lwe_secret_key = cks.glwe_secret_key.clone().into_lwe_secret_key();
 
@@ -373,11 +406,14 @@ impl Bootstrapper {
 
 
 
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn bootstrap(
         &mut self,
         input: &LweCiphertextOwned<u64>,
         accumulator : &GlweCiphertext<Vec<u64>>,
         server_key: &ServerKey) -> LweCiphertextOwned<u64> {
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Pbs, 1, std::panic::Location::caller());
         // let BuffersRef {
         //     lookup_table: accumulator,
         //     mut buffer_lwe_after_pbs,
@@ -423,7 +459,6 @@ impl Bootstrapper {
             &accumulator, 
             fourier_bsk, fft, stack);
 
-        // Self::decrypt_glwe_with_builtin_function(&client_key_debug, &output_glwe);
         
         extract_lwe_sample_from_glwe_ciphertext(&output_glwe, &mut buffer_lwe_after_pbs, MonomialDegree(0));
 
@@ -435,12 +470,15 @@ impl Bootstrapper {
 
 
     //perform the BlindRotation of v0
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub fn bootstrap_common_factor(
         &mut self,
         input: &LweCiphertextOwned<u64>,
         enc_out : &Encoding,
         server_key: &ServerKey
         ) -> GlweCiphertextOwned<u64> {
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Pbs, 1, std::panic::Location::caller());
         let BuffersRef {
             lookup_table:  accumulator,
             ..
@@ -476,8 +514,6 @@ impl Bootstrapper {
         );
 
 
-        // Self::decrypt_glwe_with_builtin_function(&client_key_debug, &output);
-        // println!("-----------------------");
 
         GlweCiphertext::from_container(
             output.as_ref().to_owned(),
@@ -523,29 +559,7 @@ impl Bootstrapper {
     } 
 
 
-    // Debug
-    // fn decrypt_glwe_with_sample_extraction<OutputCont>(client_key_debug: &ClientKey, glwe_ciphertext : &GlweCiphertext<OutputCont>)
-    // where         OutputCont: Container<Element = u64>,
-    // {
-    //     for i in 0..client_key_debug.parameters.polynomial_size.0{
-    //         let lwe_size = LweSize(client_key_debug.parameters.glwe_dimension.0 * client_key_debug.parameters.polynomial_size.0 + 1);
-    //         let mut output_lwe = LweCiphertext::new(0u64, lwe_size, CiphertextModulus::new_native());
-    //         extract_lwe_sample_from_glwe_ciphertext(&glwe_ciphertext, &mut output_lwe, MonomialDegree(i)); 
-    //         println!("{:032b}", decrypt_lwe_ciphertext(&client_key_debug.glwe_secret_key.as_lwe_secret_key(), &output_lwe).0);
-    //     }
-    // }
-
-
-    // pub fn decrypt_glwe_with_builtin_function<OutputCont>(client_key_debug : &ClientKey, glwe_ciphertext : &GlweCiphertext<OutputCont>) where
-    //     OutputCont: Container<Element = u64>,
-    // {
-    //     let mut plaintext_list = PlaintextList::new(0u64, PlaintextCount(glwe_ciphertext.polynomial_size().0));
-    //     decrypt_glwe_ciphertext(&client_key_debug.glwe_secret_key, &glwe_ciphertext, &mut plaintext_list);
-    //     plaintext_list.iter().for_each(|plaintext|println!("{:032b} = {} / {}", plaintext.0, (*plaintext.0 as f64 / (1u64 << 32) as f64 * 5.0).round(), plaintext.0) );
-    // }
-
-
-
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn mvb_bootstrap(
         &mut self,
         ciphertext : LweCiphertextOwned<u64>,
@@ -554,6 +568,8 @@ impl Bootstrapper {
         lut_fis : &Vec<Vec<u64>>,
         server_key: &ServerKey
     ) -> Vec<LweCiphertext<Vec<u64>>>{
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Mvb, 1, std::panic::Location::caller());
         // let start_mvb = Instant::now();
 
         // let start_bootstrapping_common_factor = Instant::now();
@@ -561,8 +577,8 @@ impl Bootstrapper {
 
         let v0 = self.bootstrap_common_factor(&ciphertext, &encs_out[0], server_key);
 
-        // Self::decrypt_glwe_with_builtin_function(&client_key_debug, &v0);
-        // println!("----------------------------------------");
+        #[cfg(feature = "odd-debug-oracle")]
+        debug_oracle::log_glwe("MVB COMMON_FACTOR", &v0);
         // let stop_bootstrapping_common_factor = start_bootstrapping_common_factor.elapsed();
         // println!("Durée BR common factor: {:?}: {:?}", stop_bootstrapping_common_factor.as_millis(), SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
 
@@ -599,16 +615,7 @@ impl Bootstrapper {
 
 
 
-    pub fn _decrypt_glwe_with_builtin_function<OutputCont>(client_key_debug : &ClientKey, glwe_ciphertext : &GlweCiphertext<OutputCont>) where
-    OutputCont: Container<Element = u64>,
-        {
-            let mut plaintext_list = PlaintextList::new(0u64, PlaintextCount(glwe_ciphertext.polynomial_size().0));
-            decrypt_glwe_ciphertext(&client_key_debug.glwe_secret_key, &glwe_ciphertext, &mut plaintext_list);
-            plaintext_list.iter().for_each(|plaintext|println!("{:032b} = {} / {}", plaintext.0, (*plaintext.0 as f64 / (1u64 << 32) as f64 * 5.0).round(), plaintext.0) );
-        }
-
-
-
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn mvb_bootstrap_with_common_factor_given(
         &mut self,
         common_factor : &GlweCiphertext<Vec<u64>>,
@@ -616,8 +623,9 @@ impl Bootstrapper {
         encs_out : &Vec<Encoding>,
         lut_fis : &Vec<Vec<u64>>,
         server_key: &ServerKey,
-        client_key_debug : &ClientKey
     ) -> Vec<LweCiphertext<Vec<u64>>>{
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Mvb, 1, std::panic::Location::caller());
         // let start_mvb = Instant::now();
 
         // let start_bootstrapping_common_factor = Instant::now();
@@ -625,8 +633,8 @@ impl Bootstrapper {
 
         let v0: &GlweCiphertext<Vec<u64>> = common_factor;
 
-        // Self::decrypt_glwe_with_builtin_function(&client_key_debug, &v0_bis);
-        // println!("----------------------------------------");
+        #[cfg(feature = "odd-debug-oracle")]
+        debug_oracle::log_glwe("TREE COMMON_FACTOR", v0);
         // let stop_bootstrapping_common_factor = start_bootstrapping_common_factor.elapsed();
         // println!("Durée BR common factor: {:?}: {:?}", stop_bootstrapping_common_factor.as_millis(), SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
 
@@ -646,9 +654,9 @@ impl Bootstrapper {
         for vi in vis{
             let mut accu_i = GlweCiphertext::new(0u64, v0.glwe_size(), v0.polynomial_size(), v0.ciphertext_modulus());
             accu_i.as_mut_polynomial_list().iter_mut().zip(v0.as_polynomial_list().iter()).for_each(|(mut output, v0_poly_j)| polynomial_karatsuba_wrapping_mul(&mut output, &v0_poly_j, &vi));
-            
-            // Self::decrypt_glwe_with_builtin_function(&client_key_debug, &accu_i);
-            // println!("------------------------------------------");
+
+            #[cfg(feature = "odd-debug-oracle")]
+            debug_oracle::log_glwe("TREE MVB_ACCUMULATOR", &accu_i);
 
             //Sample Extraction
             let mut output_lwe_i = LweCiphertext::new(0u64, accu_i.glwe_size().to_glwe_dimension().to_equivalent_lwe_dimension(accu_i.polynomial_size()).to_lwe_size(), accu_i.ciphertext_modulus());
@@ -665,6 +673,7 @@ impl Bootstrapper {
     }
 
 
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn pack_into_new_accumulator(
         &self, 
         lwe_ciphertexts : Vec<LweCiphertextOwned<u64>>,
@@ -694,6 +703,9 @@ impl Bootstrapper {
 
 
         assert!(p % 2 == 1, "Pas sûr que ça marche avec une output paire");
+        // one packing keyswitch per slot, plus one for the wrap-around of the first slot
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::PackingKeyswitch, p + 1, std::panic::Location::caller());
         let N = server_key.bootstrapping_key.polynomial_size().0;
         let size_slice = N / p as usize;
 
@@ -751,6 +763,7 @@ impl Bootstrapper {
     }
 
 
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn bootstrap_keyswitch(
         &mut self,
         mut ciphertext: LweCiphertextOwned<u64>,
@@ -780,6 +793,10 @@ impl Bootstrapper {
         );
         let stack = self.computation_buffers.stack();
 
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Pbs, 1, std::panic::Location::caller());
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Keyswitch, 1, std::panic::Location::caller());
         // Compute a bootstrap
         programmable_bootstrap_lwe_ciphertext_mem_optimized(
             &ciphertext,
@@ -803,6 +820,7 @@ impl Bootstrapper {
 
 
 
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn keyswitch_bootstrap(
             &mut self,
             mut ciphertext: LweCiphertextOwned<u64>,
@@ -832,6 +850,10 @@ impl Bootstrapper {
         );
         let stack = self.computation_buffers.stack();
 
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Keyswitch, 1, std::panic::Location::caller());
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Pbs, 1, std::panic::Location::caller());
         // let start_keyswitch = Instant::now();
         // Keyswitch from large LWE key to the small one
         keyswitch_lwe_ciphertext(
@@ -860,6 +882,7 @@ impl Bootstrapper {
     }
 
     
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn apply_bootstrapping_pattern(
         &mut self,
         ct: LweCiphertextOwned<u64>,
@@ -876,7 +899,10 @@ impl Bootstrapper {
 
 
 impl ServerKey {
+    #[cfg_attr(feature = "pbs-stats", track_caller)]
     pub(crate) fn keyswitch(&self, input: &LweCiphertextOwned<u64>) -> LweCiphertextOwned<u64> {
+        #[cfg(feature = "pbs-stats")]
+        pbs_stats::record(Operation::Keyswitch, 1, std::panic::Location::caller());
         // Allocate the output of the KS
         let mut output = LweCiphertext::new(
             0u64,
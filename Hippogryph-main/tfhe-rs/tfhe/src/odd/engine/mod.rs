@@ -8,23 +8,26 @@ use tfhe_csprng::generators::DefaultRandomGenerator;
 use crate::core_crypto::algorithms::*;
 use crate::core_crypto::entities::*;
 use crate::core_crypto::prelude::CiphertextModulus;
-use crate::core_crypto::prelude::Container;
-use crate::core_crypto::prelude::ContiguousEntityContainer;
+use crate::core_crypto::prelude::DynamicDistribution;
 use crate::core_crypto::prelude::EncryptionKeyChoice;
-use crate::core_crypto::prelude::LweSize;
-use crate::core_crypto::prelude::MonomialDegree;
+#[cfg(feature = "zk-pok")]
+use crate::core_crypto::prelude::LweCiphertextCount;
 use crate::core_crypto::prelude::PBSOrder;
-use crate::core_crypto::prelude::PlaintextCount;
 use crate::odd::prelude::*;
 use std::cell::RefCell;
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
 pub mod bootstrapping;
+
+#[cfg(test)]
+mod tests;
 use crate::core_crypto::commons::generators::{
     DeterministicSeeder, EncryptionRandomGenerator, SecretRandomGenerator,
 };
-use crate::core_crypto::commons::math::random::Seeder;
+use crate::core_crypto::commons::math::random::{Seed, Seeder};
+#[cfg(feature = "zk-pok")]
+use crate::core_crypto::commons::math::random::RandomGenerator;
 use crate::odd::engine::bootstrapping::{Bootstrapper, ServerKey};
+#[cfg(feature = "odd-debug-oracle")]
+use crate::odd::debug_oracle;
 //use crate::core_crypto::commons::parameters::*;
 use crate::core_crypto::seeders::new_seeder;
 
@@ -56,6 +59,9 @@ pub struct OddEngine {
     /// encryption.
     encryption_generator: EncryptionRandomGenerator<DefaultRandomGenerator>,
     bootstrapper: Bootstrapper,
+    /// A CSPRNG for the public randomness of the zero-knowledge proofs.
+    #[cfg(feature = "zk-pok")]
+    pub(crate) random_generator: RandomGenerator<DefaultRandomGenerator>,
 }
 
 impl WithThreadLocalEngine for OddEngine {
@@ -95,10 +101,148 @@ impl OddEngine {
         server_key
     }
 
+    // The key switching keys toward or from the shortint and boolean keys, the output noise is
+    // the one of the destination key
+    pub(crate) fn new_key_switching_key(
+        &mut self,
+        input_key: &LweSecretKeyView<'_, u64>,
+        output_key: &LweSecretKeyView<'_, u64>,
+        output_noise: DynamicDistribution<u64>,
+        params: OddKeySwitchingParameters,
+    ) -> LweKeyswitchKeyOwned<u64> {
+        allocate_and_generate_new_lwe_keyswitch_key(
+            input_key,
+            output_key,
+            params.ks_base_log,
+            params.ks_level,
+            output_noise,
+            CiphertextModulus::new_native(),
+            &mut self.encryption_generator,
+        )
+    }
+
+    pub(crate) fn new_compression_private_key(
+        &mut self,
+        params: CompressionParameters,
+    ) -> GlweSecretKeyOwned<u64> {
+        allocate_and_generate_new_binary_glwe_secret_key(
+            params.packing_ks_glwe_dimension,
+            params.packing_ks_polynomial_size,
+            &mut self.secret_generator,
+        )
+    }
+
+    // The packing keyswitch key from the big key to the compression key, and the blind rotation
+    // key from the compression key back to the big key
+    pub(crate) fn new_compression_decompression_keys(
+        &mut self,
+        cks: &ClientKey,
+        compression_key: &GlweSecretKeyOwned<u64>,
+        params: CompressionParameters,
+    ) -> (LwePackingKeyswitchKeyOwned<u64>, FourierLweBootstrapKeyOwned) {
+        let packing_key_switching_key = allocate_and_generate_new_lwe_packing_keyswitch_key(
+            &cks.glwe_secret_key.as_lwe_secret_key(),
+            compression_key,
+            params.packing_ks_base_log,
+            params.packing_ks_level,
+            params.packing_ks_key_noise_distribution,
+            CiphertextModulus::new_native(),
+            &mut self.encryption_generator,
+        );
+
+        let blind_rotate_key = self.bootstrapper.new_fourier_bootstrapping_key(
+            &compression_key.as_lwe_secret_key(),
+            &cks.glwe_secret_key,
+            params.br_base_log,
+            params.br_level,
+            cks.parameters.glwe_noise_distribution,
+        );
+
+        (packing_key_switching_key, blind_rotate_key)
+    }
+
+    #[cfg(feature = "zk-pok")]
+    pub(crate) fn new_compact_public_key(
+        &mut self,
+        cks: &ClientKey,
+        noise_distribution: DynamicDistribution<u64>,
+    ) -> LweCompactPublicKeyOwned<u64> {
+        let (encryption_key, _) = cks.encryption_key_and_noise();
+        allocate_and_generate_new_lwe_compact_public_key(
+            &encryption_key,
+            noise_distribution,
+            CiphertextModulus::new_native(),
+            &mut self.encryption_generator,
+        )
+    }
+
+    // Encrypts the elements of Zp with the compact public key, delta being 2^64 / p, and proves
+    // that they lie in the range committed in the crs
+    #[cfg(feature = "zk-pok")]
+    pub(crate) fn encrypt_and_prove_compact(
+        &mut self,
+        public_key: &LweCompactPublicKeyOwned<u64>,
+        zp_elements: &[u64],
+        delta: u64,
+        noise_distribution: DynamicDistribution<u64>,
+        crs: &crate::zk::CompactPkeCrs,
+        metadata: &[u8],
+        load: crate::zk::ZkComputeLoad,
+    ) -> crate::Result<(LweCompactCiphertextListOwned<u64>, crate::zk::CompactPkeProof)> {
+        let mut ct_list = LweCompactCiphertextListOwned::new(
+            0u64,
+            public_key.lwe_dimension().to_lwe_size(),
+            LweCiphertextCount(zp_elements.len()),
+            CiphertextModulus::new_native(),
+        );
+
+        let proof = encrypt_and_prove_lwe_compact_ciphertext_list_with_compact_public_key(
+            public_key,
+            &mut ct_list,
+            &zp_elements,
+            delta,
+            noise_distribution,
+            noise_distribution,
+            &mut self.secret_generator,
+            &mut self.encryption_generator,
+            &mut self.random_generator,
+            crs,
+            metadata,
+            load,
+        )?;
+
+        Ok((ct_list, proof))
+    }
+
     pub fn trivial_encrypt(&mut self, message: u64) -> Ciphertext {
         Ciphertext::Trivial(message)
     }
 
+    // Noiseless encryption with a zero mask : unlike Ciphertext::Trivial, it carries an encoding and
+    // can go through the bootstrappings like any other ciphertext
+    pub fn trivial_encrypt_arithmetic(
+        &mut self,
+        message: u64,
+        encoding: &Encoding,
+        server_key: &ServerKey,
+    ) -> Ciphertext {
+        assert!(message < encoding.get_origin_modulus());
+        let size = match server_key.pbs_order {
+            PBSOrder::KeyswitchBootstrap => server_key
+                .key_switching_key
+                .input_key_lwe_dimension()
+                .to_lwe_size(),
+            PBSOrder::BootstrapKeyswitch => server_key
+                .bootstrapping_key
+                .input_lwe_dimension()
+                .to_lwe_size(),
+        };
+
+        let mut ct = LweCiphertext::new(0u64, size, CiphertextModulus::new_native());
+        *ct.get_mut_body().data = self.encode_message_into_plaintext(message, encoding).0;
+        Ciphertext::EncodingEncrypted(ct, encoding.clone())
+    }
+
     fn encryption_from_plaintext(
         &mut self,
         cks: &ClientKey,
@@ -163,7 +307,7 @@ impl OddEngine {
         }
     }
 
-    fn decrypt_arithmetic(
+    pub(crate) fn decrypt_arithmetic(
         lwe_sk: &LweSecretKey<&[u64]>,
         ciphertext: &LweCiphertext<Vec<u64>>,
         encoding: &Encoding,
@@ -202,58 +346,42 @@ impl OddEngine {
                         LweSecretKey::from_container(cks.lwe_secret_key.as_ref())
                     }
                 };
-                // decryption
-                let decrypted = decrypt_lwe_ciphertext(&lwe_sk, ciphertext);
+                Self::noise_arithmetic(&lwe_sk, ciphertext, encoding)
+            }
+        }
+    }
 
-                // cast as a u64
-                let decrypted_u64 = decrypted.0 as u64;
-                //println!("Debug : decrypted : {:#034b}", decrypted_u64);
+    pub(crate) fn noise_arithmetic(
+        lwe_sk: &LweSecretKey<&[u64]>,
+        ciphertext: &LweCiphertext<Vec<u64>>,
+        encoding: &Encoding,
+    ) -> i64 {
+        // decryption
+        let decrypted = decrypt_lwe_ciphertext(&lwe_sk, ciphertext);
 
-                let divisor: u128 = 1 << 64;
-                let divisor_float = divisor as f64;
-                let slice: f64 = encoding.get_modulus() as f64 / divisor_float;
-                // println!("Debug : decrypted : {}, on Zp : {}", decrypted_u64, decrypted_u64 as f64 / divisor_float * encoding.get_modulus() as f64);
+        // cast as a u64
+        let decrypted_u64 = decrypted.0 as u64;
+        //println!("Debug : decrypted : {:#034b}", decrypted_u64);
 
-                let floating_result = decrypted_u64 as f64 * slice;
+        let divisor: u128 = 1 << 64;
+        let divisor_float = divisor as f64;
+        let slice: f64 = encoding.get_modulus() as f64 / divisor_float;
+        // println!("Debug : decrypted : {}, on Zp : {}", decrypted_u64, decrypted_u64 as f64 / divisor_float * encoding.get_modulus() as f64);
 
-                let closest_integer = floating_result.round() as u64 % encoding.get_modulus();
+        let floating_result = decrypted_u64 as f64 * slice;
 
-                // println!("Closest integer : {}", closest_integer);
+        let closest_integer = floating_result.round() as u64 % encoding.get_modulus();
 
-                let mut noise = closest_integer as f64 - floating_result;
-                if noise.abs() > encoding.get_modulus() as f64 / 2.0 {
-                    noise = encoding.get_modulus() as f64 - noise.abs()
-                }
+        // println!("Closest integer : {}", closest_integer);
 
-                //remettre le bruit dans Zq
-                let noise_int = (noise * (1u128 << 64) as f64).round() as i64;
-                noise_int
-            }
+        let mut noise = closest_integer as f64 - floating_result;
+        if noise.abs() > encoding.get_modulus() as f64 / 2.0 {
+            noise = encoding.get_modulus() as f64 - noise.abs()
         }
-    }
 
-    pub fn test_mvb(&mut self, ct: &GlweCiphertext<Vec<u64>>, client_key: &ClientKey) {
-        for i in 0..client_key.parameters.polynomial_size.0 {
-            let mut output_lwe = LweCiphertext::new(
-                0,
-                LweSize(
-                    client_key.parameters.glwe_dimension.0
-                        * client_key.parameters.polynomial_size.0
-                        + 1,
-                ),
-                CiphertextModulus::new_native(),
-            );
-            extract_lwe_sample_from_glwe_ciphertext(ct, &mut output_lwe, MonomialDegree(i));
-            let decrypted = decrypt_lwe_ciphertext(
-                &client_key.glwe_secret_key.as_lwe_secret_key(),
-                &output_lwe,
-            );
-            println!(
-                "{} -> {}",
-                decrypted.0,
-                decrypted.0 as f64 / (1u64 << 32) as f64 * 17f64
-            );
-        }
+        //remettre le bruit dans Zq
+        let noise_int = (noise * (1u128 << 64) as f64).round() as i64;
+        noise_int
     }
 }
 
@@ -324,6 +452,58 @@ impl OddEngine {
         }
     }
 
+    // The blind rotation of the random input under the small key gives an element of
+    // {1, ..., 2o} in Z_{2o+1} : the first half of the torus to {1, ..., o} and the other half to
+    // their negatives. A second bootstrapping reduces it modulo o into the output encoding.
+    pub(crate) fn generate_oblivious_pseudo_random(
+        &mut self,
+        seed: Seed,
+        output_encoding: &Encoding,
+        server_key: &ServerKey,
+    ) -> Ciphertext {
+        let o = output_encoding.get_origin_modulus();
+        let intermediate_encoding = Encoding::new_trivial(2 * o + 1);
+
+        let fourier_bsk = &server_key.bootstrapping_key;
+        let polynomial_size = fourier_bsk.polynomial_size();
+
+        let seeded = crate::odd::oprf::create_random_from_seed_modulus_switched(
+            seed,
+            fourier_bsk.input_lwe_dimension().to_lwe_size(),
+            polynomial_size.to_blind_rotation_input_modulus_log(),
+            CiphertextModulus::new_native(),
+        );
+
+        let delta = ((1u128 << 64) / (2 * o + 1) as u128) as u64;
+        let mut accumulator = GlweCiphertext::new(
+            0u64,
+            fourier_bsk.glwe_size(),
+            polynomial_size,
+            CiphertextModulus::new_native(),
+        );
+        accumulator
+            .get_mut_body()
+            .as_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(j, coefficient)| {
+                *coefficient = (1 + j as u64 * o / polynomial_size.0 as u64) * delta
+            });
+
+        let bootstrapper = &mut self.bootstrapper;
+        let mut intermediate = bootstrapper.bootstrap(&seeded, &accumulator, server_key);
+        if server_key.pbs_order == PBSOrder::BootstrapKeyswitch {
+            intermediate = server_key.keyswitch(&intermediate);
+        }
+
+        bootstrapper.apply_bootstrapping_pattern(
+            intermediate,
+            &intermediate_encoding.apply_lut_to_encoding(&|v| (v + o - 1) % o),
+            output_encoding,
+            server_key,
+        )
+    }
+
     pub fn mvb(
         &mut self,
         input: &Ciphertext,
@@ -372,29 +552,6 @@ impl OddEngine {
         }
     }
 
-    pub fn decrypt_glwe_with_builtin_function<OutputCont>(
-        client_key_debug: &ClientKey,
-        glwe_ciphertext: &GlweCiphertext<OutputCont>,
-    ) where
-        OutputCont: Container<Element = u64>,
-    {
-        let mut plaintext_list =
-            PlaintextList::new(0u64, PlaintextCount(glwe_ciphertext.polynomial_size().0));
-        decrypt_glwe_ciphertext(
-            &client_key_debug.glwe_secret_key,
-            &glwe_ciphertext,
-            &mut plaintext_list,
-        );
-        plaintext_list.iter().for_each(|plaintext| {
-            println!(
-                "{:032b} = {} / {}",
-                plaintext.0,
-                (*plaintext.0 as f64 / (1u64 << 32) as f64 * 5.0).round(),
-                plaintext.0
-            )
-        });
-    }
-
     pub fn compute_common_factor(
         &mut self,
         ciphertext: &Ciphertext,
@@ -421,8 +578,6 @@ impl OddEngine {
         t: u64,
         lut_fi: Vec<u64>,
         server_key: &ServerKey,
-        client_key_debug: &ClientKey,
-        log: bool,
     ) -> Ciphertext {
         let c_0 = inputs[1].clone();
         match c_0 {
@@ -449,26 +604,23 @@ impl OddEngine {
                                 &vec![encoding_out.clone(); (t / o_0).try_into().unwrap()],
                                 &first_functions,
                                 &server_key,
-                                &client_key_debug,
-                            );
-                        if log {
-                            println!(
-                                "TIMING POST_MVB_TREE ? {:?}",
-                                SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
                             );
-                        }
+                        #[cfg(feature = "odd-debug-oracle")]
+                        debug_oracle::log_ciphertexts(
+                            "TREE FIRST_LEVEL",
+                            &first_ciphertexts
+                                .iter()
+                                .map(|c| Ciphertext::EncodingEncrypted(c.clone(), encoding_out.clone()))
+                                .collect::<Vec<_>>(),
+                        );
                         
                         let next_accumulator = bootstrapper.pack_into_new_accumulator(
                             first_ciphertexts,
                             server_key,
                             encoding_in_0.get_modulus(),
                         );
-                        if log {
-                            println!(
-                                "TIMING POST_PACKING_TREE ? {:?}",
-                                SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
-                            );
-                        }
+                        #[cfg(feature = "odd-debug-oracle")]
+                        debug_oracle::log_glwe("TREE PACKED_ACCUMULATOR", &next_accumulator);
 
 
                         //for now, only depth-2 trees
@@ -478,26 +630,17 @@ impl OddEngine {
                             Ciphertext::EncodingEncrypted(lwe_c_1, _) => {
                                 //we assume that they both hve the same input encoding
                                 let lwe_c_1_after_ks = server_key.keyswitch(&lwe_c_1);
-                                if log {
-                                    println!(
-                                        "TIMING POST_SECOND_KEYSWITCH_TREE ? {:?}",
-                                        SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
-                                    );
-                                }
 
                                 let final_lwe = bootstrapper.bootstrap(
                                     &lwe_c_1_after_ks,
                                     &next_accumulator,
                                     server_key,
                                 );
-                                if log {
-                                    println!(
-                                        "TIMING POST_SIMPLE_BOOTSTRAPPING_IN_TREE ? {:?}",
-                                        SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
-                                    );
-                                }
-
-                                Ciphertext::EncodingEncrypted(final_lwe, encoding_out.clone())
+
+                                let result = Ciphertext::EncodingEncrypted(final_lwe, encoding_out.clone());
+                                #[cfg(feature = "odd-debug-oracle")]
+                                debug_oracle::log_ciphertexts("TREE OUTPUT", std::slice::from_ref(&result));
+                                result
                             }
                             _ => panic!(),
                         }
@@ -510,6 +653,147 @@ impl OddEngine {
         }
     }
 
+    // Tree bootstrapping over any number of inputs, for a single output digit.
+    // `lut` is indexed by the mixed radix value of the inputs, the last input being the least significant one:
+    // the MVB on the last input (sharing the given common factor) evaluates one function per value of the other inputs,
+    // then each previous input selects, by a bootstrapping on a packed accumulator, among the ciphertexts of the level below.
+    pub fn multivariate_tree_bootstrapping(
+        &mut self,
+        common_factor: &GlweCiphertextOwned<u64>,
+        inputs: &[Ciphertext],
+        encoding_out: &Encoding,
+        lut: &[u64],
+        server_key: &ServerKey,
+    ) -> Ciphertext {
+        let (last_input, first_inputs) = inputs.split_last().expect("No tree bootstrapping without inputs");
+        let encoding_last = match last_input {
+            Ciphertext::EncodingEncrypted(_, encoding) => encoding,
+            Ciphertext::Trivial(_) => panic!("No tree bootstrapping with trivial ciphertexts (yet)"),
+        };
+
+        match server_key.pbs_order {
+            PBSOrder::BootstrapKeyswitch => {
+                panic!("Tree bootstrapping is only available with the big encryption key")
+            }
+            PBSOrder::KeyswitchBootstrap => {
+                let bootstrapper = &mut self.bootstrapper;
+
+                let first_functions: Vec<Vec<u64>> = lut
+                    .chunks(encoding_last.get_origin_modulus() as usize)
+                    .map(|chunk| chunk.to_vec())
+                    .collect();
+
+                // No keyswitch here, it has been done while bootstrapping the common factor
+                let mut level = bootstrapper.mvb_bootstrap_with_common_factor_given(
+                    common_factor,
+                    encoding_last,
+                    &vec![encoding_out.clone(); first_functions.len()],
+                    &first_functions,
+                    server_key,
+                );
+                #[cfg(feature = "odd-debug-oracle")]
+                debug_oracle::log_ciphertexts(
+                    "MULTIVARIATE FIRST_LEVEL",
+                    &level
+                        .iter()
+                        .map(|c| Ciphertext::EncodingEncrypted(c.clone(), encoding_out.clone()))
+                        .collect::<Vec<_>>(),
+                );
+
+                for input in first_inputs.iter().rev() {
+                    let (lwe_input, encoding_input) = match input {
+                        Ciphertext::EncodingEncrypted(lwe, encoding) => (lwe, encoding),
+                        Ciphertext::Trivial(_) => panic!("No tree bootstrapping with trivial ciphertexts (yet)"),
+                    };
+                    let p = encoding_input.get_modulus();
+                    let lwe_input_after_ks = server_key.keyswitch(lwe_input);
+
+                    level = level
+                        .chunks(encoding_input.get_origin_modulus() as usize)
+                        .map(|group| {
+                            // each element of Zp gets the ciphertext of the element of Zo it encodes
+                            let slots: Vec<LweCiphertextOwned<u64>> = (0..p)
+                                .map(|x| match encoding_input.inverse_encoding(x) {
+                                    Some(i) => group[i as usize].clone(),
+                                    None => LweCiphertext::new(0u64, group[0].lwe_size(), group[0].ciphertext_modulus()),
+                                })
+                                .collect();
+                            let accumulator = bootstrapper.pack_into_new_accumulator(slots, server_key, p);
+                            bootstrapper.bootstrap(&lwe_input_after_ks, &accumulator, server_key)
+                        })
+                        .collect();
+                }
+                assert_eq!(level.len(), 1);
+
+                let result = Ciphertext::EncodingEncrypted(level.pop().unwrap(), encoding_out.clone());
+                #[cfg(feature = "odd-debug-oracle")]
+                debug_oracle::log_ciphertexts("MULTIVARIATE OUTPUT", std::slice::from_ref(&result));
+                result
+            }
+        }
+    }
+
+    pub fn apply_encrypted_lut(
+        &mut self,
+        index: &Ciphertext,
+        table: &[Ciphertext],
+        server_key: &ServerKey,
+    ) -> Ciphertext {
+        match index {
+            Ciphertext::EncodingEncrypted(lwe_index, encoding_index) => {
+                assert_eq!(
+                    table.len() as u64,
+                    encoding_index.get_origin_modulus(),
+                    "The table should have one entry per element of Zo"
+                );
+                let p = encoding_index.get_modulus();
+                assert!(p % 2 == 1, "Encrypted lookup tables need an odd modulus for the index");
+
+                let encoding_out = match &table[0] {
+                    Ciphertext::EncodingEncrypted(_, encoding) => encoding.clone(),
+                    Ciphertext::Trivial(_) => panic!("No encrypted lut with trivial entries"),
+                };
+                let entries: Vec<&LweCiphertextOwned<u64>> = table
+                    .iter()
+                    .map(|entry| match entry {
+                        Ciphertext::EncodingEncrypted(lwe, encoding) => {
+                            assert_eq!(*encoding, encoding_out, "All the entries of the table should share the same encoding");
+                            lwe
+                        }
+                        Ciphertext::Trivial(_) => panic!("No encrypted lut with trivial entries"),
+                    })
+                    .collect();
+
+                match server_key.pbs_order {
+                    PBSOrder::BootstrapKeyswitch => {
+                        panic!("Encrypted lookup tables are only available with the big encryption key")
+                    }
+                    PBSOrder::KeyswitchBootstrap => {
+                        // the accumulator is indexed by the elements of Zp : each one receives the entry of the element of Zo it encodes,
+                        // and the elements of Zp outside of the encoding get an encryption of zero
+                        let slots: Vec<LweCiphertextOwned<u64>> = (0..p)
+                            .map(|x| match encoding_index.inverse_encoding(x) {
+                                Some(i) => entries[i as usize].clone(),
+                                None => LweCiphertext::new(0u64, entries[0].lwe_size(), entries[0].ciphertext_modulus()),
+                            })
+                            .collect();
+
+                        let bootstrapper = &mut self.bootstrapper;
+                        let accumulator = bootstrapper.pack_into_new_accumulator(slots, server_key, p);
+                        #[cfg(feature = "odd-debug-oracle")]
+                        debug_oracle::log_glwe("ENCRYPTED_LUT ACCUMULATOR", &accumulator);
+
+                        let lwe_index_after_ks = server_key.keyswitch(lwe_index);
+                        let result_lwe = bootstrapper.bootstrap(&lwe_index_after_ks, &accumulator, server_key);
+
+                        Ciphertext::EncodingEncrypted(result_lwe, encoding_out)
+                    }
+                }
+            }
+            Ciphertext::Trivial(_) => panic!("No encrypted lut with a trivial index"),
+        }
+    }
+
     pub fn encoding_switching_mul_constant(
         &mut self,
         input: &Ciphertext,
@@ -678,6 +962,17 @@ impl Default for OddEngine {
 }
 
 impl OddEngine {
+    /// Replace the thread_local OddEngine
+    ///
+    /// `new_engine` will replace the already_existing
+    /// `thread_local` engine, e.g. with an engine created by [`OddEngine::new_from_seed`] so
+    /// that all the following key generations and encryptions of this thread are reproducible.
+    pub fn replace_thread_local(new_engine: Self) {
+        Self::with_thread_local_mut(|local_engine| {
+            let _ = std::mem::replace(local_engine, new_engine);
+        });
+    }
+
     pub fn new() -> Self {
         let mut root_seeder = new_seeder();
 
@@ -697,6 +992,17 @@ impl OddEngine {
                 &mut deterministic_seeder,
             ),
             bootstrapper: Bootstrapper::new(&mut deterministic_seeder),
+            #[cfg(feature = "zk-pok")]
+            random_generator: RandomGenerator::new(deterministic_seeder.seed()),
         }
     }
+
+    /// Create an engine whose random generators are all derived from `seed`.
+    ///
+    /// WARNING: Using a deterministic seed renders the random generation insecure, it is only
+    /// meant for test fixtures, known-answer tests and benchmarks.
+    pub fn new_from_seed(seed: Seed) -> Self {
+        let mut seeder = DeterministicSeeder::<DefaultRandomGenerator>::new(seed);
+        Self::new_from_seeder(&mut seeder)
+    }
 }
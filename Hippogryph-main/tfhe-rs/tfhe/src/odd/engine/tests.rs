@@ -0,0 +1,96 @@
+use crate::core_crypto::prelude::DynamicDistribution;
+use crate::odd::engine::OddEngine;
+use crate::odd::prelude::*;
+
+// The keys are not used for evaluation here, so the parameters do not need to be secure
+const TEST_PARAMETERS: CustomOddParameters = CustomOddParameters {
+    lwe_dimension: LweDimension(64),
+    glwe_dimension: GlweDimension(1),
+    polynomial_size: PolynomialSize(256),
+    lwe_noise_distribution: DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.0e-8)),
+    glwe_noise_distribution: DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.0e-15)),
+    pbs_base_log: DecompositionBaseLog(23),
+    pbs_level: DecompositionLevelCount(2),
+    ks_base_log: DecompositionBaseLog(4),
+    ks_level: DecompositionLevelCount(3),
+    encryption_key_choice: EncryptionKeyChoice::Big,
+};
+
+fn lwe_data(ct: &Ciphertext) -> Vec<u64> {
+    match ct {
+        Ciphertext::EncodingEncrypted(lwe, _) => lwe.as_ref().to_vec(),
+        Ciphertext::Trivial(_) => panic!("Expected an encrypted ciphertext"),
+    }
+}
+
+#[test]
+fn test_seeded_client_key_generation() {
+    let cks_1 = ClientKey::new_with_seed(&TEST_PARAMETERS, Seed(0));
+    let cks_2 = ClientKey::new_with_seed(&TEST_PARAMETERS, Seed(0));
+    let cks_3 = ClientKey::new_with_seed(&TEST_PARAMETERS, Seed(1));
+
+    assert_eq!(cks_1, cks_2);
+    assert_ne!(cks_1, cks_3);
+}
+
+#[test]
+fn test_seeded_server_key_generation() {
+    let serialized = |sks: &ServerKey| bincode::serialize(sks).unwrap();
+
+    let (cks_1, sks_1) = gen_keys_with_seed(&TEST_PARAMETERS, Seed(0));
+    let (cks_2, sks_2) = gen_keys_with_seed(&TEST_PARAMETERS, Seed(0));
+    let (_, sks_3) = gen_keys_with_seed(&TEST_PARAMETERS, Seed(1));
+
+    assert_eq!(cks_1, cks_2);
+    assert_eq!(serialized(&sks_1), serialized(&sks_2));
+    assert_ne!(serialized(&sks_1), serialized(&sks_3));
+
+    // Only the client key and the seed are needed to get the same server key back
+    let sks_regenerated = gen_server_key_with_seed(&cks_1, Seed(0));
+    assert_eq!(serialized(&sks_1), serialized(&sks_regenerated));
+}
+
+#[test]
+fn test_seeded_encryption() {
+    let cks = ClientKey::new_with_seed(&TEST_PARAMETERS, Seed(0));
+    let encoding = Encoding::new_canonical(16, (0..16).collect(), 17);
+
+    for m in 0..16 {
+        let ct_1 = cks.encrypt_arithmetic_with_seed(m, &encoding, Seed(42));
+        let ct_2 = cks.encrypt_arithmetic_with_seed(m, &encoding, Seed(42));
+        assert_eq!(lwe_data(&ct_1), lwe_data(&ct_2));
+        assert_eq!(cks.decrypt(&ct_1), m);
+    }
+}
+
+#[test]
+fn test_replacing_thread_local_engine() {
+    let encoding = Encoding::parity_encoding();
+
+    // We change the engine in the main thread, then generate a client key and encrypt a bit
+    OddEngine::replace_thread_local(OddEngine::new_from_seed(Seed(0)));
+    let cks = ClientKey::new(&TEST_PARAMETERS);
+    let main_thread_data = lwe_data(&cks.encrypt_arithmetic(1, &encoding));
+
+    // This thread keeps its randomly seeded engine, so the ciphertext has to differ
+    let second_thread_data = std::thread::spawn(move || {
+        let cks = ClientKey::new(&TEST_PARAMETERS);
+        lwe_data(&cks.encrypt_arithmetic(1, &Encoding::parity_encoding()))
+    })
+    .join()
+    .unwrap();
+    assert_ne!(second_thread_data, main_thread_data);
+
+    // This thread uses an engine with the same seed, so the ciphertext has to be the same
+    let third_thread_data = std::thread::spawn(move || {
+        OddEngine::replace_thread_local(OddEngine::new_from_seed(Seed(0)));
+        let cks = ClientKey::new(&TEST_PARAMETERS);
+        lwe_data(&cks.encrypt_arithmetic(1, &Encoding::parity_encoding()))
+    })
+    .join()
+    .unwrap();
+    assert_eq!(third_thread_data, main_thread_data);
+
+    // Put back a randomly seeded engine for the other tests running on this thread
+    OddEngine::replace_thread_local(OddEngine::new());
+}
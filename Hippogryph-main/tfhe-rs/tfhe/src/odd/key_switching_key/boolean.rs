@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core_crypto::prelude::{
+    keyswitch_lwe_ciphertext, lwe_ciphertext_cleartext_mul_assign,
+    lwe_ciphertext_plaintext_add_assign, Cleartext, CiphertextModulus, DynamicDistribution,
+    LweCiphertext, LweKeyswitchKeyOwned, LweSecretKey, LweSecretKeyOwned, Plaintext,
+};
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+
+use super::assert_binary;
+
+// The boolean keys are binary, they are the same keys on 64 bits
+fn lift_secret_key(key: &[u32]) -> LweSecretKeyOwned<u64> {
+    LweSecretKey::from_container(key.iter().map(|x| *x as u64).collect())
+}
+
+// The gaussian standard deviations are given on the torus, only the bounds of the t-uniform
+// distributions depend on the integer size
+fn lift_noise_distribution(noise: DynamicDistribution<u32>) -> DynamicDistribution<u64> {
+    match noise {
+        DynamicDistribution::Gaussian(gaussian) => DynamicDistribution::Gaussian(gaussian),
+        DynamicDistribution::TUniform(t_uniform) => DynamicDistribution::new_t_uniform(t_uniform.bound_log2() + 32),
+    }
+}
+
+/// Cast odd bits into boolean ciphertexts.
+///
+/// The bits go through one odd bootstrapping to `{-1/8, 1/8}`, are switched to the boolean key
+/// lifted on 64 bits, then rounded to 32 bits. The key holds the odd server key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OddToBooleanKeySwitchingKey {
+    pub(crate) key_switching_key: LweKeyswitchKeyOwned<u64>,
+    pub(crate) src_server_key: ServerKey,
+}
+
+impl OddToBooleanKeySwitchingKey {
+    pub fn new(
+        input_key_pair: (&ClientKey, &ServerKey),
+        output_key: &crate::boolean::client_key::ClientKey,
+        params: OddKeySwitchingParameters,
+    ) -> Self {
+        let (input_lwe_key, _) = input_key_pair.0.encryption_key_and_noise();
+        let (output_lwe_key, output_noise) = output_key.encryption_key_and_noise();
+        let output_lwe_key = lift_secret_key(output_lwe_key.as_ref());
+        let key_switching_key = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_key_switching_key(
+                &input_lwe_key,
+                &output_lwe_key.as_view(),
+                lift_noise_distribution(output_noise),
+                params,
+            )
+        });
+
+        Self {
+            key_switching_key,
+            src_server_key: input_key_pair.1.clone(),
+        }
+    }
+
+    pub fn cast(&self, ct: &Ciphertext) -> crate::boolean::ciphertext::Ciphertext {
+        assert_binary(ct);
+        let input = match ct {
+            Ciphertext::EncodingEncrypted(_, _) => {
+                // true is 1/8 and false is -1/8
+                match self.src_server_key.encoding_switching_lut(ct, &Encoding::new_canonical(2, vec![7, 1], 8)) {
+                    Ciphertext::EncodingEncrypted(input, _) => input,
+                    Ciphertext::Trivial(_) => unreachable!(),
+                }
+            }
+            Ciphertext::Trivial(b) => return crate::boolean::ciphertext::Ciphertext::Trivial(*b == 1),
+        };
+
+        let mut output = LweCiphertext::new(
+            0u64,
+            self.key_switching_key.output_lwe_size(),
+            input.ciphertext_modulus(),
+        );
+        keyswitch_lwe_ciphertext(&self.key_switching_key, &input, &mut output);
+
+        // rounding to the 32 most significant bits
+        let output = LweCiphertext::from_container(
+            output
+                .as_ref()
+                .iter()
+                .map(|x| (x.wrapping_add(1 << 31) >> 32) as u32)
+                .collect::<Vec<u32>>(),
+            CiphertextModulus::new_native(),
+        );
+        crate::boolean::ciphertext::Ciphertext::Encrypted(output)
+    }
+}
+
+/// Cast boolean ciphertexts into odd bits in the parity encoding.
+///
+/// No bootstrapping is needed : the ciphertexts are lifted on 64 bits, doubled and shifted by
+/// 1/4, which sends true to 1/2 and false to 0, then switched to the odd key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BooleanToOddKeySwitchingKey {
+    pub(crate) key_switching_key: LweKeyswitchKeyOwned<u64>,
+}
+
+impl BooleanToOddKeySwitchingKey {
+    pub fn new(
+        input_key: &crate::boolean::client_key::ClientKey,
+        output_key: &ClientKey,
+        params: OddKeySwitchingParameters,
+    ) -> Self {
+        let (input_lwe_key, _) = input_key.encryption_key_and_noise();
+        let input_lwe_key = lift_secret_key(input_lwe_key.as_ref());
+        let (output_lwe_key, output_noise) = output_key.encryption_key_and_noise();
+        let key_switching_key = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_key_switching_key(&input_lwe_key.as_view(), &output_lwe_key, output_noise, params)
+        });
+
+        Self { key_switching_key }
+    }
+
+    pub fn cast(&self, ct: &crate::boolean::ciphertext::Ciphertext) -> Ciphertext {
+        let input = match ct {
+            crate::boolean::ciphertext::Ciphertext::Encrypted(input) => input,
+            crate::boolean::ciphertext::Ciphertext::Trivial(b) => return Ciphertext::Trivial(*b as u64),
+        };
+
+        let mut lifted = LweCiphertext::from_container(
+            input.as_ref().iter().map(|x| (*x as u64) << 32).collect::<Vec<u64>>(),
+            CiphertextModulus::new_native(),
+        );
+        lwe_ciphertext_cleartext_mul_assign(&mut lifted, Cleartext(2));
+        lwe_ciphertext_plaintext_add_assign(&mut lifted, Plaintext(1 << 62));
+
+        let mut output = LweCiphertext::new(
+            0u64,
+            self.key_switching_key.output_lwe_size(),
+            CiphertextModulus::new_native(),
+        );
+        keyswitch_lwe_ciphertext(&self.key_switching_key, &lifted, &mut output);
+        Ciphertext::EncodingEncrypted(output, Encoding::parity_encoding())
+    }
+}
@@ -0,0 +1,33 @@
+//! Key switching keys between odd ciphertexts and the shortint and boolean ones.
+//!
+//! Both the LWE key and the encoding change on the way:
+//! - a shortint ciphertext holds its message with a padding bit, `m * 2^63 / (message_modulus *
+//!   carry_modulus)`, which odd sees as the canonical encoding `x -> x` of Z_{message_modulus *
+//!   carry_modulus} into Z_{2 * message_modulus * carry_modulus};
+//! - a boolean ciphertext holds `+1/8` for true and `-1/8` for false on 32 bits, which becomes the
+//!   parity encoding once lifted to 64 bits, doubled and shifted.
+//!
+//! In the other direction, an odd bit is first bootstrapped to an encoding `{-w, w}` symmetric
+//! around zero, since this is the only kind of output allowed from the parity encoding, then
+//! shifted to the plaintext of the destination scheme before the key switch.
+
+#[cfg(feature = "boolean")]
+mod boolean;
+#[cfg(feature = "shortint")]
+mod shortint;
+#[cfg(test)]
+mod test;
+
+#[cfg(feature = "boolean")]
+pub use boolean::{BooleanToOddKeySwitchingKey, OddToBooleanKeySwitchingKey};
+#[cfg(feature = "shortint")]
+pub use shortint::{OddToShortintKeySwitchingKey, ShortintToOddKeySwitchingKey};
+
+use crate::odd::prelude::*;
+
+// Panics unless the ciphertext encrypts a bit
+fn assert_binary(ct: &Ciphertext) {
+    if let Ciphertext::EncodingEncrypted(_, encoding) = ct {
+        assert_eq!(encoding.get_origin_modulus(), 2, "Only the odd ciphertexts of a bit can be cast");
+    }
+}
@@ -0,0 +1,158 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core_crypto::prelude::{
+    keyswitch_lwe_ciphertext, lwe_ciphertext_add_assign, LweCiphertext, LweCiphertextOwned,
+    LweKeyswitchKeyOwned, PBSOrder,
+};
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+use crate::shortint::ciphertext::{Degree, NoiseLevel};
+use crate::shortint::parameters::{CarryModulus, MessageModulus};
+
+use super::assert_binary;
+
+/// Cast odd bits into shortint ciphertexts, one bit or a group of bits per message.
+///
+/// The bits go through one odd bootstrapping to the shortint encoding, so the key holds the odd
+/// server key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OddToShortintKeySwitchingKey {
+    pub(crate) key_switching_key: LweKeyswitchKeyOwned<u64>,
+    pub(crate) src_server_key: ServerKey,
+    pub(crate) message_modulus: MessageModulus,
+    pub(crate) carry_modulus: CarryModulus,
+    pub(crate) dest_pbs_order: PBSOrder,
+}
+
+impl OddToShortintKeySwitchingKey {
+    pub fn new(
+        input_key_pair: (&ClientKey, &ServerKey),
+        output_key: &crate::shortint::ClientKey,
+        params: OddKeySwitchingParameters,
+    ) -> Self {
+        let (input_lwe_key, _) = input_key_pair.0.encryption_key_and_noise();
+        let (output_lwe_key, output_noise) = output_key.encryption_key_and_noise();
+        let key_switching_key = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_key_switching_key(&input_lwe_key, &output_lwe_key, output_noise, params)
+        });
+
+        Self {
+            key_switching_key,
+            src_server_key: input_key_pair.1.clone(),
+            message_modulus: output_key.parameters.message_modulus(),
+            carry_modulus: output_key.parameters.carry_modulus(),
+            dest_pbs_order: output_key.parameters.encryption_key_choice().into(),
+        }
+    }
+
+    pub fn cast(&self, ct: &Ciphertext) -> crate::shortint::Ciphertext {
+        self.pack_bits(std::slice::from_ref(ct)).pop().unwrap()
+    }
+
+    /// Pack groups of odd bits into shortint messages of `log2(message_modulus)` bits, the
+    /// bits of each group being given least significant first.
+    ///
+    /// Every bit costs one odd bootstrapping, straight to its weight in the shortint message,
+    /// then each group is summed and switched to the shortint key with a single keyswitch. The
+    /// last group may be shorter.
+    pub fn pack_bits(&self, bits: &[Ciphertext]) -> Vec<crate::shortint::Ciphertext> {
+        let bits_per_message = self.message_modulus.0.ilog2() as usize;
+        assert!(bits_per_message > 0, "The shortint messages cannot hold a single bit");
+
+        let weighted_bits: Vec<LweCiphertextOwned<u64>> = bits
+            .par_iter()
+            .enumerate()
+            .map(|(i, bit)| self.weighted_bit(bit, 1 << (i % bits_per_message)))
+            .collect();
+
+        weighted_bits
+            .par_chunks(bits_per_message)
+            .map(|group| {
+                let mut sum = group[0].clone();
+                group[1..].iter().for_each(|bit| lwe_ciphertext_add_assign(&mut sum, bit));
+
+                let mut output = LweCiphertext::new(
+                    0u64,
+                    self.key_switching_key.output_lwe_size(),
+                    sum.ciphertext_modulus(),
+                );
+                keyswitch_lwe_ciphertext(&self.key_switching_key, &sum, &mut output);
+
+                crate::shortint::Ciphertext::new(
+                    output,
+                    Degree::new((1 << group.len()) - 1),
+                    NoiseLevel::NOMINAL,
+                    self.message_modulus,
+                    self.carry_modulus,
+                    self.dest_pbs_order,
+                )
+            })
+            .collect()
+    }
+
+    // Bootstrap a bit to `{0, weight}` on the shortint scale, still under the odd key
+    fn weighted_bit(&self, bit: &Ciphertext, weight: u64) -> LweCiphertextOwned<u64> {
+        assert_binary(bit);
+        // shortint scale : 2^63 / (message_modulus * carry_modulus) = 2 * 2^64 / p
+        let p = 4 * self.message_modulus.0 * self.carry_modulus.0;
+
+        let shifted = match bit {
+            Ciphertext::EncodingEncrypted(_, _) => {
+                let symmetric = self.src_server_key.encoding_switching_lut(bit, &Encoding::new_canonical(2, vec![p - weight, weight], p));
+                self.src_server_key.simple_plaintext_sum(&symmetric, weight, p)
+            }
+            Ciphertext::Trivial(b) => self.src_server_key.trivial_encrypt_arithmetic(*b, &Encoding::new_canonical(2, vec![0, 2 * weight], p)),
+        };
+        match shifted {
+            Ciphertext::EncodingEncrypted(ct, _) => ct,
+            Ciphertext::Trivial(_) => unreachable!(),
+        }
+    }
+}
+
+/// Cast shortint ciphertexts into odd ones, encrypted with the canonical encoding of
+/// Z_{message_modulus * carry_modulus} into Z_{2 * message_modulus * carry_modulus}.
+///
+/// No bootstrapping is needed. The modulus of the result is even, so it is meant for the linear
+/// operations of the odd server key : the odd bootstrappings only read the even moduli of
+/// negacyclic encodings.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShortintToOddKeySwitchingKey {
+    pub(crate) key_switching_key: LweKeyswitchKeyOwned<u64>,
+    pub(crate) full_modulus: u64,
+}
+
+impl ShortintToOddKeySwitchingKey {
+    pub fn new(
+        input_key: &crate::shortint::ClientKey,
+        output_key: &ClientKey,
+        params: OddKeySwitchingParameters,
+    ) -> Self {
+        let (input_lwe_key, _) = input_key.encryption_key_and_noise();
+        let (output_lwe_key, output_noise) = output_key.encryption_key_and_noise();
+        let key_switching_key = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_key_switching_key(&input_lwe_key, &output_lwe_key, output_noise, params)
+        });
+
+        Self {
+            key_switching_key,
+            full_modulus: input_key.parameters.message_modulus().0 * input_key.parameters.carry_modulus().0,
+        }
+    }
+
+    /// The encoding of the cast ciphertexts.
+    pub fn encoding(&self) -> Encoding {
+        Encoding::new_canonical(self.full_modulus, (0..self.full_modulus).collect(), 2 * self.full_modulus)
+    }
+
+    pub fn cast(&self, ct: &crate::shortint::Ciphertext) -> Ciphertext {
+        let mut output = LweCiphertext::new(
+            0u64,
+            self.key_switching_key.output_lwe_size(),
+            ct.ct.ciphertext_modulus(),
+        );
+        keyswitch_lwe_ciphertext(&self.key_switching_key, &ct.ct, &mut output);
+        Ciphertext::EncodingEncrypted(output, self.encoding())
+    }
+}
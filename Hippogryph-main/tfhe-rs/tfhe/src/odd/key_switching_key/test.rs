@@ -0,0 +1,99 @@
+use crate::odd::keycache::KEY_CACHE;
+use crate::odd::prelude::*;
+
+#[cfg(feature = "boolean")]
+#[test]
+fn test_odd_boolean_key_switching() {
+    use crate::boolean::parameters::DEFAULT_PARAMETERS;
+
+    use super::{BooleanToOddKeySwitchingKey, OddToBooleanKeySwitchingKey};
+
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let boolean_keys = crate::boolean::keycache::KEY_CACHE.get_from_param(DEFAULT_PARAMETERS);
+    let boolean_cks = boolean_keys.client_key();
+
+    let to_boolean = OddToBooleanKeySwitchingKey::new((cks, sks), boolean_cks, PARAMETERS_KEYSWITCH_40_TO_BOOLEAN);
+    let to_odd = BooleanToOddKeySwitchingKey::new(boolean_cks, cks, PARAMETERS_KEYSWITCH_40);
+
+    for encoding in [Encoding::parity_encoding(), Encoding::new_canonical_binary(1, 3)] {
+        for b in [0, 1] {
+            let ct = to_boolean.cast(&cks.encrypt_arithmetic(b, &encoding));
+            assert_eq!(boolean_cks.decrypt(&ct), b == 1);
+
+            let back = to_odd.cast(&ct);
+            assert_eq!(cks.decrypt(&back), b);
+        }
+    }
+}
+
+#[cfg(feature = "shortint")]
+#[test]
+fn test_odd_shortint_key_switching() {
+    use crate::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
+
+    use super::{OddToShortintKeySwitchingKey, ShortintToOddKeySwitchingKey};
+
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let shortint_keys = crate::shortint::keycache::KEY_CACHE.get_from_param(PARAM_MESSAGE_2_CARRY_2_KS_PBS);
+    let (shortint_cks, shortint_sks) = (shortint_keys.client_key(), shortint_keys.server_key());
+
+    let to_shortint = OddToShortintKeySwitchingKey::new((cks, sks), shortint_cks, PARAMETERS_KEYSWITCH_40);
+    let to_odd = ShortintToOddKeySwitchingKey::new(shortint_cks, cks, PARAMETERS_KEYSWITCH_40);
+
+    for encoding in [Encoding::parity_encoding(), Encoding::new_canonical_binary(1, 3)] {
+        for b in [0, 1] {
+            let ct = to_shortint.cast(&cks.encrypt_arithmetic(b, &encoding));
+            assert_eq!(shortint_cks.decrypt(&ct), b);
+
+            // the cast ciphertexts are usable by the shortint server key
+            let sum = shortint_sks.unchecked_add(&ct, &ct);
+            assert_eq!(shortint_cks.decrypt(&sum), 2 * b);
+        }
+    }
+
+    for m in 0..4 {
+        let ct = to_odd.cast(&shortint_cks.encrypt(m));
+        assert_eq!(cks.decrypt(&ct), m);
+
+        // and the odd ones by the linear operations of the odd server key
+        let sum = sks.simple_sum(&vec![ct.clone(), ct]);
+        assert_eq!(cks.decrypt(&sum), 2 * m);
+    }
+}
+
+#[cfg(feature = "shortint")]
+#[test]
+fn test_odd_bits_packing_into_shortint() {
+    use rand::Rng;
+
+    use crate::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
+
+    use super::OddToShortintKeySwitchingKey;
+
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let shortint_keys = crate::shortint::keycache::KEY_CACHE.get_from_param(PARAM_MESSAGE_2_CARRY_2_KS_PBS);
+    let (shortint_cks, shortint_sks) = (shortint_keys.client_key(), shortint_keys.server_key());
+    let to_shortint = OddToShortintKeySwitchingKey::new((cks, sks), shortint_cks, PARAMETERS_KEYSWITCH_40);
+    let mut rng = rand::thread_rng();
+
+    // a byte and a lonely bit, in the parity encoding of the AES outputs
+    let clear_bits: Vec<u64> = (0..9).map(|_| rng.gen_range(0..2)).collect();
+    let bits: Vec<Ciphertext> = clear_bits
+        .iter()
+        .map(|b| cks.encrypt_arithmetic(*b, &Encoding::parity_encoding()))
+        .collect();
+
+    let messages = to_shortint.pack_bits(&bits);
+    assert_eq!(messages.len(), 5);
+    for (message, group) in messages.iter().zip(clear_bits.chunks(2)) {
+        let expected = group.iter().rev().fold(0, |acc, b| 2 * acc + b);
+        assert_eq!(shortint_cks.decrypt(message), expected);
+
+        // the messages go through the shortint bootstrappings
+        let squared = shortint_sks.apply_lookup_table(message, &shortint_sks.generate_lookup_table(|x| x * x % 4));
+        assert_eq!(shortint_cks.decrypt(&squared), expected * expected % 4);
+    }
+}
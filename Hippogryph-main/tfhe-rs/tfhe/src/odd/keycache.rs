@@ -0,0 +1,91 @@
+use std::sync::LazyLock;
+
+use crate::keycache::utils::named_params_impl;
+use crate::keycache::*;
+use crate::odd::parameters::*;
+use crate::odd::client_key::ClientKey;
+use crate::odd::server_key::ServerKey;
+
+named_params_impl!(expose PARAMETERS_40, PARAMETERS_128);
+
+impl NamedParam for CustomOddParameters {
+    fn name(&self) -> String {
+        named_params_impl!({*self; Self} == (PARAMETERS_40, PARAMETERS_128));
+        // the custom sets are stored under a hash of their serialization, which does not depend on
+        // the compiler like the std hashers do
+        let bytes = bincode::serialize(self).unwrap();
+        let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("CUSTOM_ODD_PARAMETERS_{hash:016x}")
+    }
+}
+
+impl From<CustomOddParameters> for (ClientKey, ServerKey) {
+    fn from(param: CustomOddParameters) -> Self {
+        let cks = ClientKey::new(&param);
+        let sks = ServerKey::new(&cks);
+        (cks, sks)
+    }
+}
+
+pub struct Keycache {
+    inner: ImplKeyCache<CustomOddParameters, (ClientKey, ServerKey), FileStorage>,
+}
+
+impl Default for Keycache {
+    fn default() -> Self {
+        Self {
+            inner: ImplKeyCache::new(FileStorage::new("../keys/odd/client_server".to_string())),
+        }
+    }
+}
+
+pub struct SharedKey {
+    inner: GenericSharedKey<(ClientKey, ServerKey)>,
+}
+
+impl SharedKey {
+    pub fn client_key(&self) -> &ClientKey {
+        &self.inner.0
+    }
+    pub fn server_key(&self) -> &ServerKey {
+        &self.inner.1
+    }
+}
+
+impl Keycache {
+    pub fn get_from_param(&self, param: CustomOddParameters) -> SharedKey {
+        SharedKey {
+            inner: self.inner.get(param),
+        }
+    }
+
+    pub fn clear_in_memory_cache(&self) {
+        self.inner.clear_in_memory_cache();
+    }
+}
+
+pub static KEY_CACHE: LazyLock<Keycache> = LazyLock::new(Keycache::default);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_parameters_name() {
+        assert_eq!(PARAMETERS_40.name(), PARAMETERS_40_NAME);
+
+        let custom = CustomOddParameters {
+            pbs_level: DecompositionLevelCount(PARAMETERS_40.pbs_level.0 + 1),
+            ..PARAMETERS_40
+        };
+        let other_custom = CustomOddParameters {
+            ks_level: DecompositionLevelCount(PARAMETERS_40.ks_level.0 + 1),
+            ..PARAMETERS_40
+        };
+        assert!(custom.name().starts_with("CUSTOM_ODD_PARAMETERS_"));
+        assert_eq!(custom.name(), custom.name());
+        assert_ne!(custom.name(), other_custom.name());
+    }
+}
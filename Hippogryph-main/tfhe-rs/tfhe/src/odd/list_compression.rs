@@ -0,0 +1,306 @@
+//! Compression of odd ciphertext lists for storage and transfer.
+//!
+//! As in `shortint::list_compression`, the LWE ciphertexts are packed `lwe_per_glwe` at a time
+//! into GLWE ciphertexts by a packing keyswitch, then modulus switched to a few bits per
+//! coefficient. Each entry keeps its [`Encoding`], so the decompression gives back ciphertexts
+//! of the same elements of Zp : it samples an entry out of its GLWE and bootstraps it with the
+//! identity of Zp to come back to the big key.
+//!
+//! The compressed ciphertexts are stored with fewer bits than the bootstrapping reads, this is
+//! only correct for the moduli whose windows are wider than the modulus switching noise, up to
+//! p = 17 with [`COMP_PARAMETERS_40`].
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core_crypto::prelude::compressed_modulus_switched_glwe_ciphertext::CompressedModulusSwitchedGlweCiphertext;
+use crate::core_crypto::prelude::{
+    extract_lwe_sample_from_glwe_ciphertext, lwe_ciphertext_plaintext_add_assign,
+    par_keyswitch_lwe_ciphertext_list_and_pack_in_glwe_ciphertext,
+    programmable_bootstrap_lwe_ciphertext, CiphertextCount, CiphertextModulus,
+    CiphertextModulusLog, EncryptionKeyChoice, FourierLweBootstrapKeyOwned, GlweCiphertext,
+    GlweSecretKeyOwned, LweCiphertext, LweCiphertextCount, LweCiphertextList,
+    LwePackingKeyswitchKeyOwned, MonomialDegree, Plaintext,
+};
+use crate::odd::engine::bootstrapping::Memory;
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+
+/// The GLWE secret key the ciphertexts are packed under.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompressionPrivateKeys {
+    pub post_packing_ks_key: GlweSecretKeyOwned<u64>,
+    pub params: CompressionParameters,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompressionKey {
+    pub packing_key_switching_key: LwePackingKeyswitchKeyOwned<u64>,
+    pub lwe_per_glwe: LweCiphertextCount,
+    pub storage_log_modulus: CiphertextModulusLog,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DecompressionKey {
+    pub blind_rotate_key: FourierLweBootstrapKeyOwned,
+    pub lwe_per_glwe: LweCiphertextCount,
+}
+
+/// A list of odd ciphertexts packed into modulus switched GLWE ciphertexts, with the encoding of
+/// each entry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompressedCiphertextList {
+    pub(crate) modulus_switched_glwe_ciphertext_list: Vec<CompressedModulusSwitchedGlweCiphertext<u64>>,
+    pub(crate) encodings: Vec<Encoding>,
+    pub(crate) lwe_per_glwe: LweCiphertextCount,
+    pub(crate) count: CiphertextCount,
+}
+
+impl CompressedCiphertextList {
+    pub fn len(&self) -> usize {
+        self.count.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count.0 == 0
+    }
+
+    pub fn encoding(&self, index: usize) -> Option<&Encoding> {
+        self.encodings.get(index)
+    }
+}
+
+impl ClientKey {
+    pub fn new_compression_private_key(&self, params: CompressionParameters) -> CompressionPrivateKeys {
+        assert_eq!(
+            self.parameters.encryption_key_choice,
+            EncryptionKeyChoice::Big,
+            "Compression is only compatible with ciphertext in post PBS dimension"
+        );
+
+        CompressionPrivateKeys {
+            post_packing_ks_key: OddEngine::with_thread_local_mut(|engine| engine.new_compression_private_key(params)),
+            params,
+        }
+    }
+
+    pub fn new_compression_decompression_keys(
+        &self,
+        private_compression_key: &CompressionPrivateKeys,
+    ) -> (CompressionKey, DecompressionKey) {
+        assert_eq!(
+            self.parameters.encryption_key_choice,
+            EncryptionKeyChoice::Big,
+            "Compression is only compatible with ciphertext in post PBS dimension"
+        );
+
+        let params = private_compression_key.params;
+        assert!(
+            params.storage_log_modulus.0 <= self.parameters.polynomial_size.to_blind_rotation_input_modulus_log().0,
+            "Compression parameters say to store more bits than useful"
+        );
+
+        let (packing_key_switching_key, blind_rotate_key) = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_compression_decompression_keys(self, &private_compression_key.post_packing_ks_key, params)
+        });
+
+        (
+            CompressionKey {
+                packing_key_switching_key,
+                lwe_per_glwe: params.lwe_per_glwe,
+                storage_log_modulus: params.storage_log_modulus,
+            },
+            DecompressionKey {
+                blind_rotate_key,
+                lwe_per_glwe: params.lwe_per_glwe,
+            },
+        )
+    }
+}
+
+impl CompressionKey {
+    pub fn compress_ciphertexts_into_list(&self, ciphertexts: &[Ciphertext]) -> CompressedCiphertextList {
+        let lwe_pksk = &self.packing_key_switching_key;
+
+        let polynomial_size = lwe_pksk.output_polynomial_size();
+        let ciphertext_modulus = lwe_pksk.ciphertext_modulus();
+        let glwe_size = lwe_pksk.output_glwe_size();
+        let lwe_size = lwe_pksk.input_key_lwe_dimension().to_lwe_size();
+
+        assert!(
+            self.lwe_per_glwe.0 <= polynomial_size.0,
+            "Cannot pack more than polynomial_size(={}) elements per glwe, {} requested",
+            polynomial_size.0,
+            self.lwe_per_glwe.0,
+        );
+
+        let encodings: Vec<Encoding> = ciphertexts
+            .iter()
+            .map(|ct| match ct {
+                Ciphertext::EncodingEncrypted(lwe, encoding) => {
+                    assert_eq!(
+                        lwe_size,
+                        lwe.lwe_size(),
+                        "All ciphertexts do not have the same lwe size as the packing keyswitch key"
+                    );
+                    encoding.clone()
+                }
+                Ciphertext::Trivial(_) => panic!("Trivial ciphertexts cannot be compressed"),
+            })
+            .collect();
+
+        let glwe_ct_list = ciphertexts
+            .par_chunks(self.lwe_per_glwe.0)
+            .map(|ct_list| {
+                let list: Vec<u64> = ct_list
+                    .iter()
+                    .flat_map(|ct| match ct {
+                        Ciphertext::EncodingEncrypted(lwe, _) => lwe.as_ref().iter().copied(),
+                        Ciphertext::Trivial(_) => unreachable!(),
+                    })
+                    .collect();
+                let list = LweCiphertextList::from_container(list, lwe_size, ciphertext_modulus);
+
+                let mut out = GlweCiphertext::new(0, glwe_size, polynomial_size, ciphertext_modulus);
+                par_keyswitch_lwe_ciphertext_list_and_pack_in_glwe_ciphertext(lwe_pksk, &list, &mut out);
+
+                CompressedModulusSwitchedGlweCiphertext::compress(
+                    &out,
+                    self.storage_log_modulus,
+                    LweCiphertextCount(ct_list.len()),
+                )
+            })
+            .collect();
+
+        CompressedCiphertextList {
+            modulus_switched_glwe_ciphertext_list: glwe_ct_list,
+            encodings,
+            lwe_per_glwe: self.lwe_per_glwe,
+            count: CiphertextCount(ciphertexts.len()),
+        }
+    }
+}
+
+impl DecompressionKey {
+    /// Decompress the entry `index` of the list, with its original encoding.
+    pub fn unpack(&self, packed: &CompressedCiphertextList, index: usize) -> Result<Ciphertext, crate::Error> {
+        if index >= packed.count.0 {
+            return Err(crate::Error::new(format!(
+                "Tried getting index {index} for CompressedCiphertextList \
+                with {} elements, out of bound access.",
+                packed.count.0
+            )));
+        }
+
+        let encoding = &packed.encodings[index];
+        let p = encoding.get_modulus();
+        if p % 2 == 0 && p != 2 {
+            return Err(crate::Error::new(format!(
+                "Tried to unpack a ciphertext with an even modulus p = {p}, \
+                only odd moduli and p = 2 are supported."
+            )));
+        }
+
+        let lwe_per_glwe = packed.lwe_per_glwe.0;
+        let packed_glwe = packed.modulus_switched_glwe_ciphertext_list[index / lwe_per_glwe].extract();
+        let lwe_size = packed_glwe
+            .glwe_size()
+            .to_glwe_dimension()
+            .to_equivalent_lwe_dimension(packed_glwe.polynomial_size())
+            .to_lwe_size();
+
+        let mut intermediate_lwe = LweCiphertext::new(0, lwe_size, packed_glwe.ciphertext_modulus());
+        extract_lwe_sample_from_glwe_ciphertext(
+            &packed_glwe,
+            &mut intermediate_lwe,
+            MonomialDegree(index % lwe_per_glwe),
+        );
+
+        let mut accumulator = GlweCiphertext::new(
+            0,
+            self.blind_rotate_key.glwe_size(),
+            self.blind_rotate_key.polynomial_size(),
+            CiphertextModulus::new_native(),
+        );
+        if p == 2 {
+            // p = 2 only bootstraps to opposite values : 0 goes to -1/4 and 1 to 1/4, which is
+            // shifted back below. No encoding can express it, since 1/4 is also the opposite of
+            // -1/4 in Z4
+            let mut body = accumulator.get_mut_body();
+            let (first_half, second_half) = body.as_mut().split_at_mut(self.blind_rotate_key.polynomial_size().0 / 2);
+            first_half.fill(3 << 62);
+            second_half.fill(1 << 62);
+        } else {
+            // the identity of Zp, whatever the elements used by the encoding
+            let identity = Encoding::new_canonical(p, (0..p).collect(), p);
+            Memory::fill_accumulator(&mut accumulator.as_mut_view(), &identity, &identity);
+        }
+
+        let mut output = LweCiphertext::new(
+            0,
+            self.blind_rotate_key.output_lwe_dimension().to_lwe_size(),
+            CiphertextModulus::new_native(),
+        );
+        programmable_bootstrap_lwe_ciphertext(&intermediate_lwe, &mut output, &accumulator, &self.blind_rotate_key);
+
+        if p == 2 {
+            lwe_ciphertext_plaintext_add_assign(&mut output, Plaintext(1 << 62));
+        }
+
+        Ok(Ciphertext::EncodingEncrypted(output, encoding.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odd::keycache::KEY_CACHE;
+    use rand::Rng;
+
+    #[test]
+    fn test_compression() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let private_compression_key = cks.new_compression_private_key(COMP_PARAMETERS_40);
+        let (compression_key, decompression_key) = cks.new_compression_decompression_keys(&private_compression_key);
+        let mut rng = rand::thread_rng();
+
+        let encodings = [
+            Encoding::parity_encoding(),
+            Encoding::new_canonical_binary(1, 3),
+            Encoding::new_canonical(3, vec![0, 2, 5], 7),
+            Encoding::new_canonical(16, (0..16).collect(), 17),
+        ];
+
+        // more than one GLWE
+        let count = COMP_PARAMETERS_40.lwe_per_glwe.0 + 10;
+        let clear: Vec<(u64, usize)> = (0..count)
+            .map(|_| {
+                let i = rng.gen_range(0..encodings.len());
+                (rng.gen_range(0..encodings[i].get_origin_modulus()), i)
+            })
+            .collect();
+        let mut ciphertexts: Vec<Ciphertext> = clear
+            .iter()
+            .map(|(m, i)| cks.encrypt_arithmetic(*m, &encodings[*i]))
+            .collect();
+        // an encoding shifted by a constant
+        ciphertexts[0] = sks.encoding_switching_sum_constant(&cks.encrypt_arithmetic(1, &encodings[2]), 3, 7);
+
+        let compressed = compression_key.compress_ciphertexts_into_list(&ciphertexts);
+        assert_eq!(compressed.len(), count);
+
+        for (index, ct) in ciphertexts.iter().enumerate() {
+            let unpacked = decompression_key.unpack(&compressed, index).unwrap();
+            match (&unpacked, ct) {
+                (Ciphertext::EncodingEncrypted(_, encoding), Ciphertext::EncodingEncrypted(_, expected)) => {
+                    assert_eq!(encoding, expected)
+                }
+                _ => unreachable!(),
+            }
+            assert_eq!(cks.decrypt(&unpacked), cks.decrypt(ct));
+        }
+
+        assert!(decompression_key.unpack(&compressed, count).is_err());
+    }
+}
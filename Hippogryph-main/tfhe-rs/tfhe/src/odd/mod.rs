@@ -8,20 +8,34 @@
 //! TOWRITE
 
 
+use crate::core_crypto::commons::generators::DeterministicSeeder;
+use crate::core_crypto::commons::math::random::{Seed, Seeder};
 use crate::odd::client_key::ClientKey;
 use crate::odd::server_key::ServerKey;
 #[cfg(test)]
 use rand::Rng;
 
 use self::prelude::CustomOddParameters;
+use tfhe_csprng::generators::DefaultRandomGenerator;
 
 
 pub mod ciphertext;
 pub mod client_key;
+#[cfg(feature = "odd-debug-oracle")]
+pub mod debug_oracle;
+pub mod encoding_search;
 pub mod engine;
+#[cfg(any(test, doctest, feature = "internal-keycache"))]
+pub mod keycache;
+pub mod key_switching_key;
+pub mod list_compression;
+pub mod oprf;
 pub mod parameters;
 pub mod prelude;
+pub mod radix;
 pub mod server_key;
+#[cfg(feature = "zk-pok")]
+pub mod zk;
 
 
 /// tool to generate random integers
@@ -51,3 +65,44 @@ pub fn gen_keys(parameters : &CustomOddParameters) -> (ClientKey, ServerKey) {
     // return
     (cks, sks)
 }
+
+
+/// Generate a couple of client and server keys from a seed.
+///
+/// The same parameters and seed always give the same keys, which makes test fixtures,
+/// known-answer tests and benchmarks reproducible. As in shortint, `seed` only seeds a
+/// deterministic seeder, from which the client key and the server key draw distinct seeds.
+///
+/// WARNING: Using a deterministic seed renders the key generation insecure.
+pub fn gen_keys_with_seed(parameters : &CustomOddParameters, seed : Seed) -> (ClientKey, ServerKey) {
+    let (client_key_seed, server_key_seed) = key_seeds(seed);
+
+    let cks = ClientKey::new_with_seed(parameters, client_key_seed);
+
+    let sks = ServerKey::new_with_seed(&cks, server_key_seed);
+
+    (cks, sks)
+}
+
+
+/// Regenerate the server key of [`gen_keys_with_seed`] from its client key and the same seed.
+///
+/// The deterministic seeder is replayed, so that only the client key and the seed have to be
+/// stored. The client key has to be the one generated from this seed, otherwise the result is
+/// a valid server key for `cks` which differs from the original one.
+///
+/// WARNING: Using a deterministic seed renders the key generation insecure.
+pub fn gen_server_key_with_seed(cks : &ClientKey, seed : Seed) -> ServerKey {
+    let (_, server_key_seed) = key_seeds(seed);
+
+    ServerKey::new_with_seed(cks, server_key_seed)
+}
+
+
+// The client key draws the first seed of the deterministic seeder and the server key the second
+fn key_seeds(seed : Seed) -> (Seed, Seed) {
+    let mut seeder = DeterministicSeeder::<DefaultRandomGenerator>::new(seed);
+    let client_key_seed = seeder.seed();
+    let server_key_seed = seeder.seed();
+    (client_key_seed, server_key_seed)
+}
@@ -0,0 +1,183 @@
+//! Oblivious pseudo-random generation of encrypted elements of Zo.
+//!
+//! As in `shortint::oprf`, the server expands a public seed into the mask of an LWE ciphertext
+//! with a zero body, whose phase is unknown to it, and bootstraps it. The blind rotation is
+//! negacyclic, so the first bootstrapping draws an element of {1, ..., 2o} in Z_{2o+1}, which has
+//! no padding to respect, and a second one reduces it modulo o into any canonical encoding.
+//!
+//! The output is exactly uniform when o divides the polynomial size, otherwise each element of
+//! Zo has a probability within o / N of 1 / o.
+
+use crate::core_crypto::fft_impl::common::modulus_switch;
+use crate::core_crypto::prelude::{CiphertextModulus, CiphertextModulusLog, LweCiphertext, LweSize};
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+
+fn sha3_hash(values: &mut [u64], seed: Seed) {
+    use sha3::digest::{ExtendableOutput, Update, XofReader};
+
+    let mut hasher = sha3::Shake256::default();
+
+    let bytes = seed.0.to_le_bytes();
+
+    hasher.update(bytes.as_slice());
+
+    let mut reader = hasher.finalize_xof();
+
+    for value in values {
+        let mut bytes = [0u8; 8];
+        reader.read(&mut bytes);
+        *value = u64::from_le_bytes(bytes);
+    }
+}
+
+/// The LWE ciphertext with a mask expanded from the seed and a zero body, already on the
+/// modulus of the blind rotation.
+pub(crate) fn create_random_from_seed_modulus_switched(
+    seed: Seed,
+    lwe_size: LweSize,
+    log_modulus: CiphertextModulusLog,
+    ciphertext_modulus: CiphertextModulus<u64>,
+) -> LweCiphertext<Vec<u64>> {
+    let mut ct = LweCiphertext::new(0, lwe_size, ciphertext_modulus);
+
+    sha3_hash(ct.get_mut_mask().as_mut(), seed);
+
+    for i in ct.as_mut() {
+        *i = modulus_switch(*i, log_modulus) << (64 - log_modulus.0);
+    }
+
+    ct
+}
+
+impl ServerKey {
+    /// Generates an encryption of a pseudo-random element of Zo under `encoding`, o being the
+    /// origin modulus of the encoding.
+    ///
+    /// The same seed always gives an encryption of the same element, which stays oblivious to
+    /// the server. Costs two bootstrappings.
+    pub fn generate_oblivious_pseudo_random(&self, seed: Seed, encoding: &Encoding) -> Ciphertext {
+        assert!(encoding.is_canonical(), "The output encoding of the OPRF must be canonical");
+        assert!(
+            2 * encoding.get_origin_modulus() < self.bootstrapping_key.polynomial_size().0 as u64,
+            "The intermediate modulus 2 * {} + 1 does not fit in the accumulator",
+            encoding.get_origin_modulus()
+        );
+
+        OddEngine::with_thread_local_mut(|engine| engine.generate_oblivious_pseudo_random(seed, encoding, self))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::core_crypto::prelude::{decrypt_lwe_ciphertext, LweSecretKey};
+    use crate::odd::keycache::KEY_CACHE;
+    use rayon::prelude::*;
+    use statrs::distribution::ContinuousCDF;
+    use std::collections::HashMap;
+
+    fn square(a: f64) -> f64 {
+        a * a
+    }
+
+    #[test]
+    fn oprf_compare_plain() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let encoding = Encoding::new_canonical(3, vec![0, 2, 4], 7);
+        let o = encoding.get_origin_modulus();
+
+        let polynomial_size = sks.bootstrapping_key.polynomial_size();
+        let log_modulus = polynomial_size.to_blind_rotation_input_modulus_log();
+        let lwe_size = sks.bootstrapping_key.input_lwe_dimension().to_lwe_size();
+        let lwe_sk = LweSecretKey::from_container(cks.lwe_secret_key.as_ref());
+
+        for seed in 0..100 {
+            let seed = Seed(seed);
+            let img = sks.generate_oblivious_pseudo_random(seed, &encoding);
+
+            let ct = create_random_from_seed_modulus_switched(
+                seed,
+                lwe_size,
+                log_modulus,
+                CiphertextModulus::new_native(),
+            );
+            let x = decrypt_lwe_ciphertext(&lwe_sk, &ct)
+                .0
+                .wrapping_add(1 << (64 - log_modulus.0 - 1))
+                >> (64 - log_modulus.0);
+
+            // The negacyclic blind rotation, then the reduction modulo o
+            let n = polynomial_size.0 as u64;
+            let v = if x < n {
+                1 + x * o / n
+            } else {
+                2 * o + 1 - (1 + (x - n) * o / n)
+            };
+
+            assert_eq!(cks.decrypt(&img), (v - 1) % o);
+        }
+    }
+
+    #[test]
+    fn oprf_test_uniformity() {
+        let sample_count: usize = 10_000;
+
+        let p_value_limit: f64 = 0.000_01;
+
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+
+        for encoding in [
+            Encoding::new_canonical(3, vec![0, 2, 4], 7),
+            Encoding::new_canonical(4, vec![0, 1, 2, 3], 9),
+            Encoding::new_canonical(2, vec![7, 1], 8),
+        ] {
+            test_uniformity(sample_count, p_value_limit, encoding.get_origin_modulus(), |seed| {
+                let img = sks.generate_oblivious_pseudo_random(Seed(seed as u128), &encoding);
+
+                cks.decrypt(&img)
+            });
+        }
+    }
+
+    pub fn test_uniformity<F>(sample_count: usize, p_value_limit: f64, distinct_values: u64, f: F)
+    where
+        F: Sync + Fn(usize) -> u64,
+    {
+        let p_value = uniformity_p_value(f, sample_count, distinct_values);
+
+        assert!(
+            p_value_limit < p_value,
+            "p_value (={p_value}) expected to be bigger than {p_value_limit}"
+        );
+    }
+
+    fn uniformity_p_value<F>(f: F, sample_count: usize, distinct_values: u64) -> f64
+    where
+        F: Sync + Fn(usize) -> u64,
+    {
+        let values: Vec<_> = (0..sample_count).into_par_iter().map(&f).collect();
+
+        let mut values_count = HashMap::new();
+
+        for i in &values {
+            assert!(*i < distinct_values, "i {} dv{}", *i, distinct_values);
+
+            *values_count.entry(i).or_insert(0) += 1;
+        }
+
+        let single_expected_count = sample_count as f64 / distinct_values as f64;
+
+        // https://en.wikipedia.org/wiki/Pearson's_chi-squared_test
+        let distance: f64 = (0..distinct_values)
+            .map(|value| *values_count.get(&value).unwrap_or(&0))
+            .map(|count| square(count as f64 - single_expected_count) / single_expected_count)
+            .sum();
+
+        statrs::distribution::ChiSquared::new((distinct_values - 1) as f64)
+            .unwrap()
+            .sf(distance)
+    }
+}
@@ -22,7 +22,7 @@ pub use crate::core_crypto::commons::dispersion::StandardDev;
 pub use crate::core_crypto::commons::parameters::{
     DecompositionBaseLog, DecompositionLevelCount, GlweDimension, LweDimension, PolynomialSize, EncryptionKeyChoice
 };
-use crate::core_crypto::prelude::DynamicDistribution;
+use crate::core_crypto::prelude::{CiphertextModulusLog, DynamicDistribution, LweCiphertextCount};
 use serde::{Deserialize, Serialize};
 
 /// A set of cryptographic parameters for homomorphic Boolean circuit evaluation.
@@ -75,4 +75,120 @@ impl CustomOddParameters {
             encryption_key_choice
         }
     }
-}
\ No newline at end of file
+}
+
+
+/// Parameter set with a polynomial size of 1024, offering 40 bits of security.
+///
+/// Fast enough for tests and demos, not meant for production.
+pub const PARAMETERS_40: CustomOddParameters = CustomOddParameters {
+    lwe_dimension: LweDimension(754),
+    glwe_dimension: GlweDimension(1),
+    polynomial_size: PolynomialSize(1024),
+    lwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.0e-8)),
+    glwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.871712650082723e-15)),
+    pbs_base_log: DecompositionBaseLog(23),
+    pbs_level: DecompositionLevelCount(2),
+    ks_base_log: DecompositionBaseLog(4),
+    ks_level: DecompositionLevelCount(3),
+    encryption_key_choice: EncryptionKeyChoice::Big,
+};
+
+
+/// Parameter set with a polynomial size of 4096, offering 128 bits of security.
+pub const PARAMETERS_128: CustomOddParameters = CustomOddParameters {
+    lwe_dimension: LweDimension(900),
+    glwe_dimension: GlweDimension(1),
+    polynomial_size: PolynomialSize(4096),
+    lwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(6.8e-7)),
+    glwe_noise_distribution:  DynamicDistribution::new_gaussian_from_std_dev(StandardDev(2.168404344971009e-19)),
+    pbs_base_log: DecompositionBaseLog(15),
+    pbs_level: DecompositionLevelCount(2),
+    ks_base_log: DecompositionBaseLog(3),
+    ks_level: DecompositionLevelCount(6),
+    encryption_key_choice: EncryptionKeyChoice::Big,
+};
+
+
+/// A set of cryptographic parameters for the key switchings between odd and the shortint or
+/// boolean ciphertexts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OddKeySwitchingParameters {
+    pub ks_base_log: DecompositionBaseLog,
+    pub ks_level: DecompositionLevelCount,
+}
+
+impl OddKeySwitchingParameters {
+    pub fn new(ks_base_log: DecompositionBaseLog, ks_level: DecompositionLevelCount) -> Self {
+        Self {
+            ks_base_log,
+            ks_level,
+        }
+    }
+}
+
+
+/// Key switching between encryption keys of similar sizes and small noises, such as the big keys
+/// of [`PARAMETERS_40`] and of the shortint default parameters.
+pub const PARAMETERS_KEYSWITCH_40: OddKeySwitchingParameters = OddKeySwitchingParameters {
+    ks_base_log: DecompositionBaseLog(15),
+    ks_level: DecompositionLevelCount(3),
+};
+
+
+/// Key switching from the big key of [`PARAMETERS_40`] to the small key of the boolean default
+/// parameters : its noise is too large for the digits of [`PARAMETERS_KEYSWITCH_40`], so the
+/// decomposition of the boolean key switch is reused.
+pub const PARAMETERS_KEYSWITCH_40_TO_BOOLEAN: OddKeySwitchingParameters = OddKeySwitchingParameters {
+    ks_base_log: DecompositionBaseLog(3),
+    ks_level: DecompositionLevelCount(5),
+};
+
+
+/// A set of cryptographic parameters for the compression of odd ciphertext lists.
+///
+/// The ciphertexts are packed `lwe_per_glwe` at a time into GLWEs under a dedicated key, then
+/// stored on `storage_log_modulus` bits. The decompression blind-rotates them back to the big key.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompressionParameters {
+    pub br_level: DecompositionLevelCount,
+    pub br_base_log: DecompositionBaseLog,
+    pub packing_ks_level: DecompositionLevelCount,
+    pub packing_ks_base_log: DecompositionBaseLog,
+    pub packing_ks_polynomial_size: PolynomialSize,
+    pub packing_ks_glwe_dimension: GlweDimension,
+    pub lwe_per_glwe: LweCiphertextCount,
+    pub storage_log_modulus: CiphertextModulusLog,
+    pub packing_ks_key_noise_distribution: DynamicDistribution<u64>,
+}
+
+
+/// Compression parameters matching [`PARAMETERS_40`].
+pub const COMP_PARAMETERS_40: CompressionParameters = CompressionParameters {
+    br_level: DecompositionLevelCount(2),
+    br_base_log: DecompositionBaseLog(23),
+    packing_ks_level: DecompositionLevelCount(3),
+    packing_ks_base_log: DecompositionBaseLog(8),
+    packing_ks_polynomial_size: PolynomialSize(256),
+    packing_ks_glwe_dimension: GlweDimension(2),
+    lwe_per_glwe: LweCiphertextCount(256),
+    storage_log_modulus: CiphertextModulusLog(11),
+    packing_ks_key_noise_distribution: DynamicDistribution::new_gaussian_from_std_dev(StandardDev(5.0e-8)),
+};
+
+
+/// A set of cryptographic parameters for the compact public key encryption of odd ciphertexts.
+///
+/// The compact public key is built on the encryption key of the client key, so the ciphertexts
+/// it produces can be bootstrapped by the matching server key. The zero-knowledge proofs of
+/// encryption need a bounded noise, hence a TUniform distribution.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OddCompactPublicKeyParameters {
+    pub encryption_noise_distribution: DynamicDistribution<u64>,
+}
+
+
+/// Compact public key parameters matching [`PARAMETERS_40`].
+pub const COMPACT_PK_PARAMETERS_40: OddCompactPublicKeyParameters = OddCompactPublicKeyParameters {
+    encryption_noise_distribution: DynamicDistribution::new_t_uniform(17),
+};
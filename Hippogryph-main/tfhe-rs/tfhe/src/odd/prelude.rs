@@ -5,6 +5,8 @@
 
 pub use super::ciphertext::{Ciphertext, Encoding};
 pub use super::client_key::ClientKey;
-pub use super::gen_keys;
+pub use super::{gen_keys, gen_keys_with_seed, gen_server_key_with_seed};
+pub use crate::core_crypto::commons::math::random::Seed;
 pub use super::parameters::*;
-pub use super::server_key::ServerKey;
+pub use super::server_key::{with_thread_count, Gadget, ServerKey};
+pub use super::radix::OddRadixCiphertext;
@@ -0,0 +1,331 @@
+//! Radix integers made of encrypted nibbles.
+//!
+//! An [`OddRadixCiphertext<DIGITS>`] holds an integer modulo `16^DIGITS` as `DIGITS` nibbles,
+//! least significant first, each one encrypted with [`nibble_encoding`] : the values of Z16 are
+//! encoded in Z17 by themselves, as for the bytes of the AES. Carries travel between the digits
+//! in [`carry_encoding`].
+//!
+//! The additions and multiplications go through [`ServerKey::apply_multivariate_lut`], so that
+//! each output digit and its carry are computed by the same tree bootstrapping.
+
+use rayon::prelude::*;
+
+use crate::odd::prelude::*;
+
+const BASE: u64 = 16;
+const MODULUS: u64 = 17;
+// inverse of 2 in Z17
+const INV_2: u64 = 9;
+
+/// The encoding of a digit : `x -> x` from Z16 to Z17.
+pub fn nibble_encoding() -> Encoding {
+    Encoding::new_canonical(BASE, (0..BASE).collect(), MODULUS)
+}
+
+/// The encoding of a carry : `x -> x` from Z2 to Z17.
+pub fn carry_encoding() -> Encoding {
+    Encoding::new_canonical(2, vec![0, 1], MODULUS)
+}
+
+// the bound of the values spread over the output digits of a multivariate lut
+fn output_bound(output_encodings: &[Encoding]) -> u64 {
+    output_encodings.iter().map(Encoding::get_origin_modulus).product()
+}
+
+#[derive(Clone, Debug)]
+pub struct OddRadixCiphertext<const DIGITS: usize> {
+    digits: [Ciphertext; DIGITS],
+}
+
+impl<const DIGITS: usize> OddRadixCiphertext<DIGITS> {
+    const CHECK_DIGITS: () = assert!(0 < DIGITS && DIGITS <= 16, "Between 1 and 16 digits are supported");
+
+    /// Build a radix ciphertext from its digits, least significant first. The digits must be
+    /// encrypted with [`nibble_encoding`].
+    pub fn from_digits(digits: [Ciphertext; DIGITS]) -> Self {
+        let () = Self::CHECK_DIGITS;
+        assert!(
+            digits.iter().all(|digit| matches!(digit, Ciphertext::EncodingEncrypted(_, encoding) if *encoding == nibble_encoding())),
+            "The digits of a radix ciphertext should be encrypted with the nibble encoding"
+        );
+        Self { digits }
+    }
+
+    /// The digits, least significant first.
+    pub fn digits(&self) -> &[Ciphertext; DIGITS] {
+        &self.digits
+    }
+
+    pub fn into_digits(self) -> [Ciphertext; DIGITS] {
+        self.digits
+    }
+
+    /// The modulus of the integers : `16^DIGITS`, or 0 for 2^64.
+    pub fn modulus() -> u64 {
+        BASE.wrapping_pow(DIGITS as u32)
+    }
+
+    fn from_vec(digits: Vec<Ciphertext>) -> Self {
+        Self::from_digits(digits.try_into().unwrap_or_else(|_| panic!("Expected {} digits", DIGITS)))
+    }
+}
+
+impl ClientKey {
+    /// Encrypt `value` modulo `16^DIGITS`.
+    pub fn encrypt_radix<const DIGITS: usize>(&self, value: u64) -> OddRadixCiphertext<DIGITS> {
+        let encoding = nibble_encoding();
+        OddRadixCiphertext::from_digits(std::array::from_fn(|i| {
+            self.encrypt_arithmetic(value.checked_shr(4 * i as u32).unwrap_or(0) % BASE, &encoding)
+        }))
+    }
+
+    pub fn decrypt_radix<const DIGITS: usize>(&self, ct: &OddRadixCiphertext<DIGITS>) -> u64 {
+        ct.digits()
+            .iter()
+            .enumerate()
+            .map(|(i, digit)| self.decrypt(digit) << (4 * i))
+            .sum()
+    }
+}
+
+impl ServerKey {
+    /// Noiseless encryption of `value` modulo `16^DIGITS`.
+    pub fn trivial_encrypt_radix<const DIGITS: usize>(&self, value: u64) -> OddRadixCiphertext<DIGITS> {
+        let encoding = nibble_encoding();
+        OddRadixCiphertext::from_digits(std::array::from_fn(|i| {
+            self.trivial_encrypt_arithmetic(value.checked_shr(4 * i as u32).unwrap_or(0) % BASE, &encoding)
+        }))
+    }
+
+    /// Addition modulo `16^DIGITS`, the carries are propagated from the least significant digit.
+    ///
+    /// Each digit costs one tree bootstrapping, on two inputs for the first digit and on three
+    /// inputs (the incoming carry and both digits) for the other ones.
+    pub fn radix_add<const DIGITS: usize>(&self, a: &OddRadixCiphertext<DIGITS>, b: &OddRadixCiphertext<DIGITS>) -> OddRadixCiphertext<DIGITS> {
+        let mut digits = Vec::with_capacity(DIGITS);
+        let mut carry: Option<Ciphertext> = None;
+
+        for (i, (a_i, b_i)) in a.digits().iter().zip(b.digits()).enumerate() {
+            // the carry of the last digit is dropped
+            let output_encodings = if i + 1 < DIGITS { vec![nibble_encoding(), carry_encoding()] } else { vec![nibble_encoding()] };
+            // 16 * 2 with the carry, 16 without : f has to stay below the product of the output moduli
+            let bound = output_bound(&output_encodings);
+
+            let outputs = match &carry {
+                None => self.apply_multivariate_lut(&[a_i.clone(), b_i.clone()], &|x| (x[0] + x[1]) % bound, &output_encodings),
+                Some(c) => self.apply_multivariate_lut(&[c.clone(), a_i.clone(), b_i.clone()], &|x| (x[0] + x[1] + x[2]) % bound, &output_encodings),
+            };
+            let mut outputs = outputs.into_iter();
+            digits.push(outputs.next().unwrap());
+            carry = outputs.next();
+        }
+        OddRadixCiphertext::from_vec(digits)
+    }
+
+    /// Multiplication by a clear scalar modulo `16^DIGITS`.
+    ///
+    /// Every digit goes through a single MVB computing its products with all the digits of the
+    /// scalar, then the partial products are summed with [`ServerKey::radix_add`].
+    pub fn radix_scalar_mul<const DIGITS: usize>(&self, a: &OddRadixCiphertext<DIGITS>, scalar: u64) -> OddRadixCiphertext<DIGITS> {
+        let scalar_digits: Vec<u64> = (0..DIGITS).map(|j| scalar.checked_shr(4 * j as u32).unwrap_or(0) % BASE).collect();
+
+        // products[i][k] : the MVB output k of the digit i, see below for the order
+        let products: Vec<Vec<Ciphertext>> = a.digits()
+            .par_iter()
+            .enumerate()
+            .map(|(i, a_i)| {
+                let mut encodings = vec![];
+                let mut functions: Vec<Box<dyn Fn(u64) -> u64>> = vec![];
+                for &s_j in scalar_digits.iter().take(DIGITS - i) {
+                    // low nibble then high nibble of a_i * s_j, the high one only if it stays in range
+                    encodings.push(nibble_encoding());
+                    functions.push(Box::new(move |x| x * s_j % BASE));
+                    encodings.push(nibble_encoding());
+                    functions.push(Box::new(move |x| x * s_j / BASE));
+                }
+                self.mvb(a_i, &encodings, &functions)
+            })
+            .collect();
+
+        // a_i * s_j goes to the digits i + j and i + j + 1 : all the products with the same s_j
+        // make two rows without overlap, one of low nibbles and one of high nibbles
+        let rows: Vec<OddRadixCiphertext<DIGITS>> = (0..DIGITS)
+            .filter(|j| scalar_digits[*j] != 0)
+            .flat_map(|j| {
+                let low = self.partial_product_row((0..DIGITS).map(|k| (k >= j).then(|| products[k - j][2 * j].clone())));
+                let high = self.partial_product_row((0..DIGITS).map(|k| (k > j).then(|| products[k - j - 1][2 * j + 1].clone())));
+                [low, high]
+            })
+            .collect();
+
+        self.sum_rows(rows)
+    }
+
+    /// Multiplication modulo `16^DIGITS`.
+    ///
+    /// Every pair of digits whose product stays in range goes through a bivariate tree
+    /// bootstrapping giving its low and high nibbles, then the partial products are summed with
+    /// [`ServerKey::radix_add`].
+    pub fn radix_mul<const DIGITS: usize>(&self, a: &OddRadixCiphertext<DIGITS>, b: &OddRadixCiphertext<DIGITS>) -> OddRadixCiphertext<DIGITS> {
+        let pairs: Vec<(usize, usize)> = (0..DIGITS).flat_map(|i| (0..DIGITS - i).map(move |j| (i, j))).collect();
+
+        let products: Vec<Vec<Ciphertext>> = pairs
+            .par_iter()
+            .map(|&(i, j)| {
+                // the high nibble is only needed if it stays in range
+                let output_encodings = if i + j + 1 < DIGITS { vec![nibble_encoding(); 2] } else { vec![nibble_encoding()] };
+                let bound = output_bound(&output_encodings);
+                self.apply_multivariate_lut(&[a.digits()[i].clone(), b.digits()[j].clone()], &|x| x[0] * x[1] % bound, &output_encodings)
+            })
+            .collect();
+        let product = |i: usize, j: usize, k: usize| products[pairs.iter().position(|pair| *pair == (i, j)).unwrap()][k].clone();
+
+        // the products of a_i with all the digits of b make two rows without overlap
+        let rows: Vec<OddRadixCiphertext<DIGITS>> = (0..DIGITS)
+            .flat_map(|i| {
+                let low = self.partial_product_row((0..DIGITS).map(|k| (k >= i).then(|| product(i, k - i, 0))));
+                let high = self.partial_product_row((0..DIGITS).map(|k| (k > i).then(|| product(i, k - i - 1, 1))));
+                [low, high]
+            })
+            .collect();
+
+        self.sum_rows(rows)
+    }
+
+    /// Decompose into `4 * DIGITS` bits, least significant first, encrypted with `encoding_out`.
+    ///
+    /// Each digit costs one MVB.
+    pub fn radix_to_bits<const DIGITS: usize>(&self, a: &OddRadixCiphertext<DIGITS>, encoding_out: &Encoding) -> Vec<Ciphertext> {
+        let functions: Vec<Box<dyn Fn(u64) -> u64>> = (0..4)
+            .map(|k| Box::new(move |x: u64| (x >> k) & 1) as Box<dyn Fn(u64) -> u64>)
+            .collect();
+        self.mvb_batch(a.digits(), &vec![encoding_out.clone(); 4], &functions)
+            .concat()
+    }
+
+    /// Recompose from `4 * DIGITS` bits, least significant first, in any binary encoding.
+    ///
+    /// Each bit costs one bootstrapping, to an encoding `{-w/2, w/2}` of its weight w in Z17,
+    /// then the digits are plain sums.
+    pub fn radix_from_bits<const DIGITS: usize>(&self, bits: &[Ciphertext]) -> OddRadixCiphertext<DIGITS> {
+        assert_eq!(bits.len(), 4 * DIGITS, "Expected 4 bits per digit");
+
+        let weighted_bits: Vec<Ciphertext> = bits
+            .par_iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                // a bit b in {-h, h} becomes h + (2b - 1)h = 2hb, with 2h equal to its weight
+                let h = (1 << (i % 4)) * INV_2 % MODULUS;
+                let switched = self.encoding_switching_lut(bit, &Encoding::new_canonical(2, vec![MODULUS - h, h], MODULUS));
+                self.encoding_switching_sum_constant(&switched, h, MODULUS)
+            })
+            .collect();
+
+        let digits = weighted_bits
+            .chunks(4)
+            .map(|chunk| match self.simple_sum(&chunk.to_vec()) {
+                Ciphertext::EncodingEncrypted(ct, _) => Ciphertext::EncodingEncrypted(ct, nibble_encoding()),
+                Ciphertext::Trivial(_) => unreachable!(),
+            })
+            .collect();
+        OddRadixCiphertext::from_vec(digits)
+    }
+
+    // Missing digits of a partial product are noiseless zeros
+    fn partial_product_row<const DIGITS: usize>(&self, digits: impl Iterator<Item = Option<Ciphertext>>) -> OddRadixCiphertext<DIGITS> {
+        let encoding = nibble_encoding();
+        OddRadixCiphertext::from_vec(
+            digits
+                .map(|digit| digit.unwrap_or_else(|| self.trivial_encrypt_arithmetic(0, &encoding)))
+                .collect(),
+        )
+    }
+
+    fn sum_rows<const DIGITS: usize>(&self, rows: Vec<OddRadixCiphertext<DIGITS>>) -> OddRadixCiphertext<DIGITS> {
+        rows.into_iter()
+            .reduce(|acc, row| self.radix_add(&acc, &row))
+            .unwrap_or_else(|| self.trivial_encrypt_radix(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odd::keycache::KEY_CACHE;
+    use rand::Rng;
+
+    const NB_TESTS: usize = 2;
+
+    #[test]
+    fn test_radix_encrypt_decrypt() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let cks = keys.client_key();
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..NB_TESTS {
+            let value = rng.gen::<u64>();
+            assert_eq!(cks.decrypt_radix(&cks.encrypt_radix::<16>(value)), value);
+            assert_eq!(cks.decrypt_radix(&cks.encrypt_radix::<3>(value)), value % (1 << 12));
+        }
+    }
+
+    #[test]
+    fn test_radix_add() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..NB_TESTS {
+            let (a, b) = (rng.gen_range(0..1 << 12), rng.gen_range(0..1 << 12));
+            let ct = sks.radix_add(&cks.encrypt_radix::<3>(a), &cks.encrypt_radix::<3>(b));
+            assert_eq!(cks.decrypt_radix(&ct), (a + b) % (1 << 12));
+        }
+
+        // a carry going through all the digits
+        let ct = sks.radix_add(&cks.encrypt_radix::<3>(0xfff), &cks.encrypt_radix::<3>(1));
+        assert_eq!(cks.decrypt_radix(&ct), 0);
+    }
+
+    #[test]
+    fn test_radix_scalar_mul() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..NB_TESTS {
+            let (a, scalar) = (rng.gen_range(0..1 << 8), rng.gen_range(0..1 << 8));
+            let ct = sks.radix_scalar_mul(&cks.encrypt_radix::<2>(a), scalar);
+            assert_eq!(cks.decrypt_radix(&ct), a * scalar % (1 << 8));
+        }
+    }
+
+    #[test]
+    fn test_radix_mul() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..NB_TESTS {
+            let (a, b) = (rng.gen_range(0..1 << 8), rng.gen_range(0..1 << 8));
+            let ct = sks.radix_mul(&cks.encrypt_radix::<2>(a), &cks.encrypt_radix::<2>(b));
+            assert_eq!(cks.decrypt_radix(&ct), a * b % (1 << 8));
+        }
+    }
+
+    #[test]
+    fn test_radix_bits() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..NB_TESTS {
+            let a = rng.gen_range(0..1 << 8);
+            let bits = sks.radix_to_bits(&cks.encrypt_radix::<2>(a), &Encoding::parity_encoding());
+            let clear_bits: Vec<u64> = bits.iter().map(|bit| cks.decrypt(bit)).collect();
+            assert_eq!(clear_bits, (0..8).map(|i| (a >> i) & 1).collect::<Vec<u64>>());
+
+            let ct = sks.radix_from_bits::<2>(&bits);
+            assert_eq!(cks.decrypt_radix(&ct), a);
+        }
+    }
+}
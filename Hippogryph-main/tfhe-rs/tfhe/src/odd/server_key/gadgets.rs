@@ -0,0 +1,188 @@
+//! Boolean gates evaluated with a single bootstrapping.
+//!
+//! A gadget computes a linear combination of its boolean inputs over Zp, then one bootstrapping
+//! maps the result to the output encoding. The inputs of a gadget must be encoded with
+//! `Encoding::new_canonical_binary(1, p)`, where p is the modulus of the gadget: the coefficients
+//! are applied for free by [`ServerKey::encoding_switching_mul_constant`]. The output encoding is
+//! chosen by the caller, so that gadgets can be chained without extra bootstrapping.
+
+use std::collections::HashSet;
+
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct Gadget {
+    coefficients: Vec<u64>,
+    // partition of Zp between the sums leading to false and the sums leading to true
+    encoding_inter: Encoding,
+}
+
+impl Gadget {
+    /// Build the gadget computing `f` from the sum of the inputs weighted by `coefficients` in Zp.
+    ///
+    /// Panics if `f` is constant, or if two inputs with different images under `f` lead to the
+    /// same sum.
+    pub fn new(coefficients: Vec<u64>, modulus: u64, f: &dyn Fn(&[bool]) -> bool) -> Self {
+        assert!(modulus % 2 == 1, "Gadgets need an odd modulus");
+        let arity = coefficients.len();
+        let mut parts = vec![HashSet::new(), HashSet::new()];
+
+        for assignment in 0..1u64 << arity {
+            let x: Vec<bool> = (0..arity).map(|i| (assignment >> i) & 1 == 1).collect();
+            let sum = coefficients
+                .iter()
+                .zip(&x)
+                .filter(|(_, xi)| **xi)
+                .map(|(c, _)| c)
+                .sum::<u64>()
+                % modulus;
+            parts[f(&x) as usize].insert(sum);
+        }
+        assert!(
+            !parts[0].is_empty() && !parts[1].is_empty(),
+            "A gadget cannot compute a constant function, use a trivial encryption instead"
+        );
+        assert!(
+            parts[0].is_disjoint(&parts[1]),
+            "These coefficients do not allow to compute the function in Z{}",
+            modulus
+        );
+
+        Self {
+            coefficients,
+            encoding_inter: Encoding::new(2, parts, modulus),
+        }
+    }
+
+    /// `a AND b`, in Z3.
+    pub fn and() -> Self {
+        Self::threshold(2, 2)
+    }
+
+    /// `a OR b`, in Z3.
+    pub fn or() -> Self {
+        Self::threshold(2, 1)
+    }
+
+    /// Majority of three bits, in Z5.
+    pub fn maj() -> Self {
+        Self::threshold(3, 2)
+    }
+
+    /// `a XOR b XOR c`, in Z5.
+    pub fn xor3() -> Self {
+        Self::new(vec![1, 1, 1], 5, &|x| x[0] ^ x[1] ^ x[2])
+    }
+
+    /// `if s {a} else {b}` on the inputs `[s, a, b]`, in Z7.
+    pub fn mux() -> Self {
+        Self::new(vec![1, 2, 3], 7, &|x| if x[0] { x[1] } else { x[2] })
+    }
+
+    /// True when at least `k` of the `n` inputs are true, in the smallest odd Zp with p > n.
+    pub fn threshold(n: usize, k: usize) -> Self {
+        assert!(n > 0 && 0 < k && k <= n, "Invalid threshold {} out of {}", k, n);
+        let modulus = (n as u64 + 1) | 1;
+        Self::new(vec![1; n], modulus, &|x| x.iter().filter(|xi| **xi).count() >= k)
+    }
+
+    /// AND of `n` bits.
+    pub fn and_n(n: usize) -> Self {
+        Self::threshold(n, n)
+    }
+
+    /// OR of `n` bits.
+    pub fn or_n(n: usize) -> Self {
+        Self::threshold(n, 1)
+    }
+
+    pub fn arity(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.encoding_inter.get_modulus()
+    }
+
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+
+    /// The encoding expected for every input of the gadget.
+    pub fn input_encoding(&self) -> Encoding {
+        Encoding::new_canonical_binary(1, self.modulus())
+    }
+}
+
+impl ServerKey {
+    /// Evaluate a gadget with a single bootstrapping.
+    ///
+    /// The inputs must be encoded with `gadget.input_encoding()`, the output is encoded with
+    /// `encoding_out`.
+    pub fn apply_gadget(&self, gadget: &Gadget, inputs: &[Ciphertext], encoding_out: &Encoding) -> Ciphertext {
+        assert_eq!(inputs.len(), gadget.arity(), "Wrong number of inputs for this gadget");
+        let input_encoding = gadget.input_encoding();
+
+        let weighted_inputs: Vec<Ciphertext> = inputs
+            .iter()
+            .zip(gadget.coefficients())
+            .map(|(input, c)| match input {
+                Ciphertext::EncodingEncrypted(_, encoding) => {
+                    assert_eq!(*encoding, input_encoding, "The inputs of a gadget in Z{} should be encoded with new_canonical_binary(1, {})", gadget.modulus(), gadget.modulus());
+                    self.encoding_switching_mul_constant(input, *c)
+                }
+                Ciphertext::Trivial(_) => panic!("No gadget with trivial ciphertexts"),
+            })
+            .collect();
+        let encodings_in: Vec<Encoding> = weighted_inputs
+            .iter()
+            .map(|input| match input {
+                Ciphertext::EncodingEncrypted(_, encoding) => encoding.clone(),
+                Ciphertext::Trivial(_) => unreachable!(),
+            })
+            .collect();
+
+        OddEngine::with_thread_local_mut(|engine| {
+            engine.exec_gadget_with_extraction(&encodings_in, &gadget.encoding_inter, encoding_out, &weighted_inputs, self)
+        })
+    }
+
+    /// Inputs encoded with `new_canonical_binary(1, 3)`.
+    pub fn and(&self, a: &Ciphertext, b: &Ciphertext, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::and(), &[a.clone(), b.clone()], encoding_out)
+    }
+
+    /// Inputs encoded with `new_canonical_binary(1, 3)`.
+    pub fn or(&self, a: &Ciphertext, b: &Ciphertext, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::or(), &[a.clone(), b.clone()], encoding_out)
+    }
+
+    /// Inputs encoded with `new_canonical_binary(1, 5)`.
+    pub fn maj(&self, a: &Ciphertext, b: &Ciphertext, c: &Ciphertext, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::maj(), &[a.clone(), b.clone(), c.clone()], encoding_out)
+    }
+
+    /// Inputs encoded with `new_canonical_binary(1, 5)`.
+    pub fn xor3(&self, a: &Ciphertext, b: &Ciphertext, c: &Ciphertext, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::xor3(), &[a.clone(), b.clone(), c.clone()], encoding_out)
+    }
+
+    /// `if condition {a} else {b}`, inputs encoded with `new_canonical_binary(1, 7)`.
+    pub fn mux(&self, condition: &Ciphertext, a: &Ciphertext, b: &Ciphertext, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::mux(), &[condition.clone(), a.clone(), b.clone()], encoding_out)
+    }
+
+    /// True when at least `k` inputs are true, inputs encoded with `Gadget::threshold(n, k).input_encoding()`.
+    pub fn threshold(&self, inputs: &[Ciphertext], k: usize, encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::threshold(inputs.len(), k), inputs, encoding_out)
+    }
+
+    pub fn and_n(&self, inputs: &[Ciphertext], encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::and_n(inputs.len()), inputs, encoding_out)
+    }
+
+    pub fn or_n(&self, inputs: &[Ciphertext], encoding_out: &Encoding) -> Ciphertext {
+        self.apply_gadget(&Gadget::or_n(inputs.len()), inputs, encoding_out)
+    }
+}
@@ -7,17 +7,132 @@
 
 
 use itertools::Itertools;
+use rayon::prelude::*;
 
+use crate::core_crypto::commons::math::random::Seed;
 use crate::odd::prelude::*;
 use crate::odd::client_key::ClientKey;
 pub use crate::odd::engine::bootstrapping::ServerKey;
+pub use gadgets::Gadget;
 use crate::odd::engine::{
     OddEngine, WithThreadLocalEngine,
 };
 
 
+/// Counters of the expensive primitives, only compiled with the `pbs-stats` feature.
+///
+/// Every keyswitch, PBS, MVB and packing keyswitch is counted globally, and also per call site
+/// (the line of the `odd` engine which triggered it) to know where the bootstrappings of a
+/// circuit come from.
+#[cfg(feature = "pbs-stats")]
+pub mod pbs_stats {
+    use std::collections::BTreeMap;
+    use std::panic::Location;
+    use std::sync::atomic::AtomicU64;
+    pub use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    pub static KS_COUNT: AtomicU64 = AtomicU64::new(0);
+    pub static PBS_COUNT: AtomicU64 = AtomicU64::new(0);
+    pub static MVB_COUNT: AtomicU64 = AtomicU64::new(0);
+    pub static PACKING_KS_COUNT: AtomicU64 = AtomicU64::new(0);
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Operation {
+        Keyswitch,
+        Pbs,
+        Mvb,
+        PackingKeyswitch,
+    }
+
+    impl Operation {
+        fn global_counter(self) -> &'static AtomicU64 {
+            match self {
+                Self::Keyswitch => &KS_COUNT,
+                Self::Pbs => &PBS_COUNT,
+                Self::Mvb => &MVB_COUNT,
+                Self::PackingKeyswitch => &PACKING_KS_COUNT,
+            }
+        }
+    }
+
+    /// Number of times an operation has been triggered from a given line.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct CallSiteCount {
+        pub file: &'static str,
+        pub line: u32,
+        pub operation: Operation,
+        pub count: u64,
+    }
+
+    type CallSiteKey = (&'static str, u32, Operation);
+
+    static CALL_SITE_COUNTS: Mutex<BTreeMap<CallSiteKey, u64>> = Mutex::new(BTreeMap::new());
+
+    pub(crate) fn record(operation: Operation, count: u64, call_site: &'static Location<'static>) {
+        let _ = operation.global_counter().fetch_add(count, Ordering::Relaxed);
+        *CALL_SITE_COUNTS
+            .lock()
+            .unwrap()
+            .entry((call_site.file(), call_site.line(), operation))
+            .or_insert(0) += count;
+    }
+
+    pub fn get_ks_count() -> u64 {
+        KS_COUNT.load(Ordering::Relaxed)
+    }
+
+    pub fn get_pbs_count() -> u64 {
+        PBS_COUNT.load(Ordering::Relaxed)
+    }
+
+    pub fn get_mvb_count() -> u64 {
+        MVB_COUNT.load(Ordering::Relaxed)
+    }
+
+    pub fn get_packing_ks_count() -> u64 {
+        PACKING_KS_COUNT.load(Ordering::Relaxed)
+    }
+
+    /// Return the counts per call site, sorted by file and line.
+    pub fn get_call_site_counts() -> Vec<CallSiteCount> {
+        CALL_SITE_COUNTS
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&(file, line, operation), &count)| CallSiteCount {
+                file,
+                line,
+                operation,
+                count,
+            })
+            .collect()
+    }
+
+    /// Reset all the counters, global and per call site.
+    pub fn reset_pbs_count() {
+        KS_COUNT.store(0, Ordering::Relaxed);
+        PBS_COUNT.store(0, Ordering::Relaxed);
+        MVB_COUNT.store(0, Ordering::Relaxed);
+        PACKING_KS_COUNT.store(0, Ordering::Relaxed);
+        CALL_SITE_COUNTS.lock().unwrap().clear();
+    }
+}
+#[cfg(feature = "pbs-stats")]
+pub use pbs_stats::*;
+
+mod gadgets;
+#[cfg(test)]
+mod tests;
+
+
 impl ServerKey {
 
+    /// Noiseless encryption of a message, which can be used as any other ciphertext.
+    pub fn trivial_encrypt_arithmetic(&self, message : u64, encoding : &Encoding) -> Ciphertext{
+        OddEngine::with_thread_local_mut(|engine| engine.trivial_encrypt_arithmetic(message, encoding, self))
+    }
+
     ///Arithmetic only : application of LUT from Zo to Zo
     pub fn apply_lut(&self, input : &Ciphertext, encoding_out : &Encoding, f : &dyn Fn(u64) -> u64) -> Ciphertext{
         OddEngine::with_thread_local_mut(|engine| engine.apply_lut(input, encoding_out, f, self))
@@ -46,8 +161,6 @@ impl ServerKey {
         encodings_out: &Vec<Encoding>,
         t: u64,
         f: &dyn Fn(u64) -> u64,
-        client_key_debug: &ClientKey,
-        log : bool
     ) -> Vec<Ciphertext> {
         let origin_submodulis: Vec<u64> = inputs
             .iter()
@@ -69,19 +182,100 @@ impl ServerKey {
         });
 
 
-        let r0 = OddEngine::with_thread_local_mut(|engine| {
-            engine.simple_tree_bootstrapping(&common_factor.clone(), inputs, &encodings_out[0], t, lut_f0, &self, client_key_debug, log)
-        });
-    
-        let r1 = OddEngine::with_thread_local_mut(|engine| {
-            engine.simple_tree_bootstrapping(&common_factor.clone(), inputs, &encodings_out[1], t, lut_f1, &self, client_key_debug, false)
-        });
+        // both digits only share the common factor, so their tree bootstrappings are independent
+        let (r0, r1) = rayon::join(
+            || OddEngine::with_thread_local_mut(|engine| {
+                engine.simple_tree_bootstrapping(&common_factor, inputs, &encodings_out[0], t, lut_f0, &self)
+            }),
+            || OddEngine::with_thread_local_mut(|engine| {
+                engine.simple_tree_bootstrapping(&common_factor, inputs, &encodings_out[1], t, lut_f1, &self)
+            }),
+        );
     
         vec![r1, r0]
     }
     
      
     
+    /// Blind evaluation of a table whose entries are encrypted : returns `table[index]`.
+    ///
+    /// The table needs one entry per element of Zo of the index encoding, and all its entries
+    /// must share the same encoding, which is also the encoding of the output. The modulus p of
+    /// the index encoding has to be odd.
+    pub fn apply_encrypted_lut(&self, index : &Ciphertext, table : &[Ciphertext]) -> Ciphertext{
+        OddEngine::with_thread_local_mut(|engine| engine.apply_encrypted_lut(index, table, self))
+    }
+
+    /// Evaluate a function of any number of encrypted inputs by tree bootstrapping.
+    ///
+    /// `f` receives the clear values of the inputs, in the same order as `inputs`. Its result is
+    /// decomposed in mixed radix over the origin moduli of `output_digit_encodings`, and one
+    /// ciphertext is returned per digit, **least significant digit first** : the digit `i` is
+    /// `f(x) / (o_0 * ... * o_{i-1}) % o_i`, encrypted with `output_digit_encodings[i]`.
+    ///
+    /// All the inputs must be encrypted with an odd modulus p, the output encodings must be
+    /// canonical, and `f` must stay below the product of the output origin moduli. The cost is one
+    /// common factor per distinct output modulus, then for each digit one MVB on the last input
+    /// and one bootstrapping per packed accumulator on the other inputs.
+    pub fn apply_multivariate_lut(&self, inputs : &[Ciphertext], f : &dyn Fn(&[u64]) -> u64, output_digit_encodings : &[Encoding]) -> Vec<Ciphertext>{
+        assert!(!inputs.is_empty(), "No multivariate lut without inputs");
+        assert!(!output_digit_encodings.is_empty(), "No multivariate lut without output digits");
+
+        let input_origin_moduli : Vec<u64> = inputs.iter()
+                                                .map(|c| match c {
+                                                    Ciphertext::EncodingEncrypted(_, encoding) => {
+                                                        assert!(encoding.get_modulus() % 2 == 1, "The inputs of a multivariate lut should be encoded with an odd modulus");
+                                                        encoding.get_origin_modulus()
+                                                    }
+                                                    Ciphertext::Trivial(_) => panic!("No multivariate lut with trivial ciphertexts (yet)"),
+                                                })
+                                                .collect();
+        assert!(output_digit_encodings.iter().all(|encoding| encoding.is_canonical()), "The output encodings should be canonical");
+
+        let output_origin_moduli : Vec<u64> = output_digit_encodings.iter().map(|encoding| encoding.get_origin_modulus()).collect();
+        let output_bound : u64 = output_origin_moduli.iter().product();
+
+        // the table of f, indexed by the mixed radix value of the inputs (last input least significant)
+        let table : Vec<u64> = input_origin_moduli.iter()
+                                        .map(|o| 0..*o)
+                                        .multi_cartesian_product()
+                                        .map(|x| {
+                                            let y = f(&x);
+                                            assert!(y < output_bound, "f({:?}) = {} does not fit in the output digits", x, y);
+                                            y
+                                        })
+                                        .collect();
+
+        let digit_luts : Vec<Vec<u64>> = output_origin_moduli.iter()
+                                        .scan(1u64, |weight, o| {
+                                            let lut = table.iter().map(|y| y / *weight % o).collect();
+                                            *weight *= o;
+                                            Some(lut)
+                                        })
+                                        .collect();
+
+        // the common factor only depends on the last input and on the output modulus
+        let last_input = inputs.last().unwrap();
+        let common_factors : Vec<(u64, _)> = output_digit_encodings.iter()
+                                        .unique_by(|encoding| encoding.get_modulus())
+                                        .collect_vec()
+                                        .into_par_iter()
+                                        .map(|encoding| (
+                                            encoding.get_modulus(),
+                                            OddEngine::with_thread_local_mut(|engine| engine.compute_common_factor(last_input, encoding, self))
+                                        ))
+                                        .collect();
+
+        output_digit_encodings.par_iter()
+            .zip(digit_luts.par_iter())
+            .map(|(encoding, lut)| {
+                let (_, common_factor) = common_factors.iter().find(|(p, _)| *p == encoding.get_modulus()).unwrap();
+                OddEngine::with_thread_local_mut(|engine| engine.multivariate_tree_bootstrapping(common_factor, inputs, encoding, lut, self))
+            })
+            .collect()
+    }
+
+
     ///Encoding Switching : universal
     pub fn encoding_switching_lut(&self, input : &Ciphertext, encoding_out : &Encoding) -> Ciphertext{
         OddEngine::with_thread_local_mut(|engine| engine.apply_lut(input, encoding_out, &|x|{x}, self))
@@ -129,11 +323,84 @@ impl ServerKey {
 
 
 
+/// Batched evaluation: each input is processed independently on the rayon thread pool.
+///
+/// Every rayon worker uses its own thread-local [`OddEngine`], so buffers and random generators
+/// are never shared between threads. Use [`with_thread_count`] to choose how many threads are
+/// used by these methods.
+impl ServerKey {
+
+    pub fn apply_lut_batch(&self, inputs : &[Ciphertext], encoding_out : &Encoding, f : &(dyn Fn(u64) -> u64 + Sync)) -> Vec<Ciphertext>{
+        inputs.par_iter()
+            .map(|input| self.apply_lut(input, encoding_out, f))
+            .collect()
+    }
+
+    pub fn mvb_batch(&self, inputs : &[Ciphertext], encodings_out : &Vec<Encoding>, fis : &Vec<Box<dyn Fn(u64) -> u64>>) -> Vec<Vec<Ciphertext>>{
+        assert_eq!(encodings_out.len(), fis.len());
+
+        // the closures are not required to be Sync, so the lookup tables are built before going parallel
+        let lut_fis : Vec<Vec<Vec<u64>>> = inputs.iter()
+                                                .map(|input| match input{
+                                                    Ciphertext::EncodingEncrypted(_, encoding) => fis.iter()
+                                                                                                    .map(|fi| (0..encoding.get_origin_modulus()).map(fi).collect())
+                                                                                                    .collect(),
+                                                    _ => panic!("No mvb with trivial ciphertexts")
+                                                })
+                                                .collect();
+
+        inputs.par_iter()
+            .zip(lut_fis.par_iter())
+            .map(|(input, lut_fis)| OddEngine::with_thread_local_mut(|engine| engine.mvb(input, encodings_out, lut_fis, self)))
+            .collect()
+    }
+
+    pub fn full_tree_bootstrapping_batch(
+        &self,
+        inputs: &[Vec<Ciphertext>],
+        encodings_out: &Vec<Encoding>,
+        t: u64,
+        f: &(dyn Fn(u64) -> u64 + Sync),
+    ) -> Vec<Vec<Ciphertext>> {
+        inputs.par_iter()
+            .map(|input| self.full_tree_bootstrapping(input, encodings_out, t, f))
+            .collect()
+    }
+}
+
+
+/// Run `func` on a dedicated rayon thread pool of `num_threads` threads.
+///
+/// All the batched methods of the [`ServerKey`] called from `func` are spread over this pool
+/// instead of the global one.
+pub fn with_thread_count<R, F>(num_threads: usize, func: F) -> R
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .expect("Failed to build the rayon thread pool")
+        .install(func)
+}
+
+
+
+
 impl ServerKey {
     pub fn new(cks: &ClientKey) -> Self {
         OddEngine::with_thread_local_mut(|engine| engine.create_server_key(cks))
     }
 
+    /// Deterministically generate a server key from a seed.
+    ///
+    /// The seed should not be the one of the client key, whose generators would then be replayed:
+    /// [`crate::odd::gen_keys_with_seed`] draws both seeds from a single deterministic seeder.
+    pub fn new_with_seed(cks: &ClientKey, seed: Seed) -> Self {
+        OddEngine::new_from_seed(seed).create_server_key(cks)
+    }
+
     pub fn trivial_encrypt(&self, message: u64) -> Ciphertext {
         Ciphertext::Trivial(message)
     }
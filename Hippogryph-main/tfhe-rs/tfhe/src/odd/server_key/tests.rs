@@ -0,0 +1,335 @@
+use rand::Rng;
+
+use crate::odd::keycache::KEY_CACHE;
+use crate::odd::prelude::*;
+
+/// Number of random tables evaluated per modulus
+#[cfg(not(tarpaulin))]
+const NB_TESTS: usize = 4;
+#[cfg(tarpaulin)]
+const NB_TESTS: usize = 1;
+
+fn test_encrypted_lut(parameters: CustomOddParameters, encoding_index: &Encoding, encoding_out: &Encoding) {
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let mut rng = rand::thread_rng();
+
+    let o_index = encoding_index.get_origin_modulus();
+    let o_out = encoding_out.get_origin_modulus();
+
+    for _ in 0..NB_TESTS {
+        let clear_table: Vec<u64> = (0..o_index).map(|_| rng.gen_range(0..o_out)).collect();
+        let table: Vec<Ciphertext> = clear_table
+            .iter()
+            .map(|x| cks.encrypt_arithmetic(*x, encoding_out))
+            .collect();
+
+        for index in 0..o_index {
+            let ct_index = cks.encrypt_arithmetic(index, encoding_index);
+            let ct_result = sks.apply_encrypted_lut(&ct_index, &table);
+            assert_eq!(cks.decrypt(&ct_result), clear_table[index as usize]);
+        }
+    }
+}
+
+#[test]
+fn test_encrypted_lut_binary_index() {
+    test_encrypted_lut(
+        PARAMETERS_40,
+        &Encoding::new_canonical_binary(1, 3),
+        &Encoding::new_canonical(4, vec![0, 1, 2, 3], 5),
+    );
+}
+
+#[test]
+fn test_encrypted_lut_mod_5() {
+    test_encrypted_lut(
+        PARAMETERS_40,
+        &Encoding::new_canonical(5, (0..5).collect(), 5),
+        &Encoding::new_canonical(5, (0..5).collect(), 5),
+    );
+}
+
+#[test]
+fn test_encrypted_lut_mod_7() {
+    test_encrypted_lut(
+        PARAMETERS_40,
+        &Encoding::new_canonical(7, (0..7).collect(), 7),
+        &Encoding::parity_encoding(),
+    );
+}
+
+#[test]
+fn test_encrypted_lut_mod_11_sparse_index() {
+    // not every element of Z11 encodes an index
+    test_encrypted_lut(
+        PARAMETERS_40,
+        &Encoding::new_canonical(4, vec![0, 3, 5, 9], 11),
+        &Encoding::new_canonical(3, vec![0, 1, 2], 7),
+    );
+}
+
+#[test]
+fn test_encrypted_lut_mod_17() {
+    // the nibble encoding used by the AES S-box
+    test_encrypted_lut(
+        PARAMETERS_40,
+        &Encoding::new_canonical(16, (0..16).collect(), 17),
+        &Encoding::new_canonical(16, (0..16).collect(), 17),
+    );
+}
+
+fn test_multivariate_lut(
+    parameters: CustomOddParameters,
+    input_encodings: &[Encoding],
+    f: &dyn Fn(&[u64]) -> u64,
+    output_digit_encodings: &[Encoding],
+) {
+    let keys = KEY_CACHE.get_from_param(parameters);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..NB_TESTS {
+        let clear_inputs: Vec<u64> = input_encodings
+            .iter()
+            .map(|encoding| rng.gen_range(0..encoding.get_origin_modulus()))
+            .collect();
+        let inputs: Vec<Ciphertext> = clear_inputs
+            .iter()
+            .zip(input_encodings)
+            .map(|(x, encoding)| cks.encrypt_arithmetic(*x, encoding))
+            .collect();
+
+        let digits = sks.apply_multivariate_lut(&inputs, f, output_digit_encodings);
+        assert_eq!(digits.len(), output_digit_encodings.len());
+
+        // the digits are returned least significant first
+        let (result, _) = digits.iter().zip(output_digit_encodings).fold(
+            (0, 1),
+            |(acc, weight), (digit, encoding)| {
+                (acc + cks.decrypt(digit) * weight, weight * encoding.get_origin_modulus())
+            },
+        );
+        assert_eq!(result, f(&clear_inputs), "inputs : {clear_inputs:?}");
+    }
+}
+
+#[test]
+fn test_multivariate_lut_product_mod_5() {
+    let encoding = Encoding::new_canonical(5, (0..5).collect(), 5);
+    test_multivariate_lut(
+        PARAMETERS_40,
+        &[encoding.clone(), encoding.clone()],
+        &|x| x[0] * x[1],
+        &[encoding.clone(), encoding],
+    );
+}
+
+#[test]
+fn test_multivariate_lut_mixed_moduli() {
+    // comparison bit in the parity encoding, then the sum in Z7
+    test_multivariate_lut(
+        PARAMETERS_40,
+        &[
+            Encoding::new_canonical(3, vec![0, 2, 5], 7),
+            Encoding::new_canonical(4, vec![0, 1, 2, 3], 5),
+        ],
+        &|x| (x[0] < x[1]) as u64 + 2 * (x[0] + x[1]),
+        &[Encoding::parity_encoding(), Encoding::new_canonical(7, (0..7).collect(), 7)],
+    );
+}
+
+#[test]
+fn test_multivariate_lut_three_inputs() {
+    // number of bits set among three
+    let bit = Encoding::new_canonical_binary(1, 3);
+    test_multivariate_lut(
+        PARAMETERS_40,
+        &[bit.clone(), bit.clone(), bit],
+        &|x| x.iter().sum(),
+        &[Encoding::new_canonical(4, vec![0, 1, 2, 3], 5)],
+    );
+}
+
+#[test]
+fn test_apply_lut_batch() {
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let encoding = Encoding::new_canonical(5, (0..5).collect(), 5);
+    let f = |x: u64| x * x % 5;
+
+    let clear_inputs: Vec<u64> = (0..8).map(|i| i % 5).collect();
+    let inputs: Vec<Ciphertext> = clear_inputs
+        .iter()
+        .map(|x| cks.encrypt_arithmetic(*x, &encoding))
+        .collect();
+
+    let sequential: Vec<u64> = inputs
+        .iter()
+        .map(|input| cks.decrypt(&sks.apply_lut(input, &encoding, &f)))
+        .collect();
+    let batch: Vec<u64> = sks
+        .apply_lut_batch(&inputs, &encoding, &f)
+        .iter()
+        .map(|ct| cks.decrypt(ct))
+        .collect();
+    let batch_two_threads: Vec<u64> = with_thread_count(2, || sks.apply_lut_batch(&inputs, &encoding, &f))
+        .iter()
+        .map(|ct| cks.decrypt(ct))
+        .collect();
+
+    assert_eq!(sequential, clear_inputs.iter().map(|x| f(*x)).collect::<Vec<_>>());
+    assert_eq!(batch, sequential);
+    assert_eq!(batch_two_threads, sequential);
+}
+
+#[test]
+fn test_full_tree_bootstrapping_batch() {
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let encoding = Encoding::new_canonical(16, (0..16).collect(), 17);
+    let encodings_out = vec![encoding.clone(); 2];
+    let f = |x: u64| (7 * x + 3) % 256;
+
+    // one byte per input, as two nibbles most significant first
+    let clear_bytes: Vec<u64> = vec![0x00, 0x5a, 0xff];
+    let inputs: Vec<Vec<Ciphertext>> = clear_bytes
+        .iter()
+        .map(|byte| {
+            vec![
+                cks.encrypt_arithmetic(byte >> 4, &encoding),
+                cks.encrypt_arithmetic(byte & 0xf, &encoding),
+            ]
+        })
+        .collect();
+    let decrypt_byte = |nibbles: &Vec<Ciphertext>| 16 * cks.decrypt(&nibbles[0]) + cks.decrypt(&nibbles[1]);
+
+    let sequential: Vec<u64> = inputs
+        .iter()
+        .map(|input| decrypt_byte(&sks.full_tree_bootstrapping(input, &encodings_out, 256, &f)))
+        .collect();
+    let batch: Vec<u64> = sks
+        .full_tree_bootstrapping_batch(&inputs, &encodings_out, 256, &f)
+        .iter()
+        .map(decrypt_byte)
+        .collect();
+    let batch_two_threads: Vec<u64> =
+        with_thread_count(2, || sks.full_tree_bootstrapping_batch(&inputs, &encodings_out, 256, &f))
+            .iter()
+            .map(decrypt_byte)
+            .collect();
+
+    assert_eq!(sequential, clear_bytes.iter().map(|x| f(*x)).collect::<Vec<_>>());
+    assert_eq!(batch, sequential);
+    assert_eq!(batch_two_threads, sequential);
+}
+
+// Whether some coefficients in Zp allow to compute f with a single bootstrapping
+fn exists_gadget(arity: usize, modulus: u64, f: &dyn Fn(&[bool]) -> bool) -> bool {
+    let assignments: Vec<Vec<bool>> = (0..1u64 << arity)
+        .map(|a| (0..arity).map(|i| (a >> i) & 1 == 1).collect())
+        .collect();
+    (0..modulus.pow(arity as u32)).any(|index| {
+        let coefficients: Vec<u64> = (0..arity).map(|i| index / modulus.pow(i as u32) % modulus).collect();
+        let mut image = vec![None; modulus as usize];
+        assignments.iter().all(|x| {
+            let sum = coefficients.iter().zip(x).filter(|(_, xi)| **xi).map(|(c, _)| c).sum::<u64>() % modulus;
+            *image[sum as usize].get_or_insert(f(x)) == f(x)
+        })
+    })
+}
+
+fn test_gadget(gadget: &Gadget, f: &dyn Fn(&[bool]) -> bool) {
+    // no smaller odd modulus would do
+    (3..gadget.modulus())
+        .step_by(2)
+        .for_each(|p| assert!(!exists_gadget(gadget.arity(), p, f), "Z{} is enough", p));
+
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let input_encoding = gadget.input_encoding();
+
+    for encoding_out in [Encoding::parity_encoding(), Encoding::new_canonical_binary(1, 3)] {
+        for assignment in 0..1u64 << gadget.arity() {
+            let x: Vec<bool> = (0..gadget.arity()).map(|i| (assignment >> i) & 1 == 1).collect();
+            let inputs: Vec<Ciphertext> = x
+                .iter()
+                .map(|xi| cks.encrypt_arithmetic(*xi as u64, &input_encoding))
+                .collect();
+            let result = sks.apply_gadget(gadget, &inputs, &encoding_out);
+            assert_eq!(cks.decrypt(&result), f(&x) as u64, "inputs : {x:?}");
+        }
+    }
+}
+
+#[test]
+fn test_and_gadget() {
+    test_gadget(&Gadget::and(), &|x| x[0] && x[1]);
+}
+
+#[test]
+fn test_or_gadget() {
+    test_gadget(&Gadget::or(), &|x| x[0] || x[1]);
+}
+
+#[test]
+fn test_maj_gadget() {
+    test_gadget(&Gadget::maj(), &|x| x.iter().filter(|xi| **xi).count() >= 2);
+}
+
+#[test]
+fn test_xor3_gadget() {
+    test_gadget(&Gadget::xor3(), &|x| x[0] ^ x[1] ^ x[2]);
+}
+
+#[test]
+fn test_mux_gadget() {
+    test_gadget(&Gadget::mux(), &|x| if x[0] { x[1] } else { x[2] });
+}
+
+#[test]
+fn test_and_n_gadget() {
+    for n in 2..=5 {
+        test_gadget(&Gadget::and_n(n), &|x| x.iter().all(|xi| *xi));
+    }
+}
+
+#[test]
+fn test_or_n_gadget() {
+    for n in 2..=5 {
+        test_gadget(&Gadget::or_n(n), &|x| x.iter().any(|xi| *xi));
+    }
+}
+
+#[test]
+fn test_threshold_gadget() {
+    for k in 1..=4 {
+        test_gadget(&Gadget::threshold(4, k), &|x| x.iter().filter(|xi| **xi).count() >= k);
+    }
+}
+
+#[test]
+#[should_panic(expected = "constant function")]
+fn test_constant_gadget() {
+    let _ = Gadget::new(vec![1, 1], 3, &|_| true);
+}
+
+#[test]
+fn test_gadget_methods() {
+    let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+    let (cks, sks) = (keys.client_key(), keys.server_key());
+    let encoding_out = Encoding::parity_encoding();
+    let bit = |x: u64, p: u64| cks.encrypt_arithmetic(x, &Encoding::new_canonical_binary(1, p));
+
+    for a in 0..2 {
+        for b in 0..2 {
+            assert_eq!(cks.decrypt(&sks.and(&bit(a, 3), &bit(b, 3), &encoding_out)), a & b);
+            assert_eq!(cks.decrypt(&sks.or(&bit(a, 3), &bit(b, 3), &encoding_out)), a | b);
+            for c in 0..2 {
+                assert_eq!(cks.decrypt(&sks.maj(&bit(a, 5), &bit(b, 5), &bit(c, 5), &encoding_out)), (a + b + c >= 2) as u64);
+                assert_eq!(cks.decrypt(&sks.xor3(&bit(a, 5), &bit(b, 5), &bit(c, 5), &encoding_out)), a ^ b ^ c);
+                assert_eq!(cks.decrypt(&sks.mux(&bit(a, 7), &bit(b, 7), &bit(c, 7), &encoding_out)), if a == 1 { b } else { c });
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Zero-knowledge proofs of well-formed odd encryptions.
+//!
+//! A server receiving odd ciphertexts from an untrusted client, such as an encrypted AES key,
+//! cannot check by itself that they encrypt valid elements of Zo under the claimed [`Encoding`].
+//! As in `shortint::ciphertext::zk`, the client encrypts with a [`CompactPublicKey`] and attaches
+//! a `pke_v2` proof that each plaintext lies in the range committed in the [`CompactPkeCrs`].
+//!
+//! The proof bounds the plaintexts to `[0, t / 2^k)` with delta = 2^64 / t, so only the encodings
+//! where this range is exactly the allowed set can be proven: the canonical encodings mapping
+//! each i of Zo to i in Zp, with o and p powers of two. This covers the parity encoding of the
+//! AES bits (o = p = 2) and the radix-like encodings such as Z4 in Z8. The encoding is also
+//! bound to the proof through its metadata, a list cannot be replayed under another encoding.
+//!
+//! The odd moduli used by the bootstrappings, such as the nibbles in Z17, are rejected with an
+//! error: 2^64 / p is not an integer for an odd p, so the proven range cannot be put on the
+//! plaintexts of Zp. Such values are sent as proven bits and recomposed by the server.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core_crypto::prelude::{
+    expand_lwe_compact_ciphertext_list, verify_lwe_compact_ciphertext_list, CiphertextModulus,
+    ContiguousEntityContainer, LweCiphertextCount, LweCiphertextList, LweCiphertextOwned, LweCompactCiphertextListOwned,
+    LweCompactPublicKeyOwned,
+};
+use crate::odd::engine::{OddEngine, WithThreadLocalEngine};
+use crate::odd::prelude::*;
+use crate::zk::{
+    CompactPkeCrs, CompactPkeProof, ZkComputeLoad, ZkMSBZeroPaddingBitCount, ZkVerificationOutcome,
+};
+
+/// Returns the number of most significant bits of Zp that the proof checks to be zero.
+///
+/// # Errors
+///
+/// - if p is odd, as no delta puts the elements of Zp on the torus as integers;
+/// - if the allowed set of the encoding is not a range the proof can express.
+fn msb_zero_padding_bit_count(encoding: &Encoding) -> crate::Result<u64> {
+    let o = encoding.get_origin_modulus();
+    let p = encoding.get_modulus();
+
+    if p % 2 == 1 {
+        return Err(crate::Error::new(format!(
+            "Cannot prove encryptions under the odd modulus p = {p}, only powers of two are \
+            supported"
+        )));
+    }
+
+    let is_identity = encoding.is_canonical()
+        && (0..o).all(|i| encoding.get_part_single_value_if_canonical(i) == i);
+
+    if !(is_identity && o.is_power_of_two() && p.is_power_of_two() && o <= p) {
+        return Err(crate::Error::new(format!(
+            "Cannot prove encryptions under an encoding of Z{o} in Z{p} which is not the \
+            identity of a power of two range"
+        )));
+    }
+
+    Ok((p / o).ilog2() as u64)
+}
+
+// The proof only sees the range, the encoding is prepended to the user metadata so that a
+// proven list cannot be claimed under another encoding with the same range
+fn bind_encoding_to_metadata(encoding: &Encoding, metadata: &[u8]) -> Vec<u8> {
+    let mut bound_metadata = Vec::with_capacity(16 + metadata.len());
+    bound_metadata.extend_from_slice(&encoding.get_origin_modulus().to_le_bytes());
+    bound_metadata.extend_from_slice(&encoding.get_modulus().to_le_bytes());
+    bound_metadata.extend_from_slice(metadata);
+    bound_metadata
+}
+
+impl CompactPkeCrs {
+    /// Construct the CRS proving encryptions under `encoding` with a public key of the given
+    /// parameters.
+    ///
+    /// max_num_message is how many message a single proof can prove.
+    ///
+    /// # Errors
+    ///
+    /// If the encoding cannot be proven, in particular if its modulus p is odd.
+    pub fn from_odd_params(
+        parameters: &CustomOddParameters,
+        pk_parameters: OddCompactPublicKeyParameters,
+        encoding: &Encoding,
+        max_num_message: LweCiphertextCount,
+    ) -> crate::Result<Self> {
+        let padding_bit_count = msb_zero_padding_bit_count(encoding)?;
+
+        let size = parameters
+            .glwe_dimension
+            .to_equivalent_lwe_dimension(parameters.polynomial_size);
+
+        OddEngine::with_thread_local_mut(|engine| {
+            Self::new(
+                size,
+                max_num_message,
+                pk_parameters.encryption_noise_distribution,
+                CiphertextModulus::new_native(),
+                encoding.get_modulus(),
+                ZkMSBZeroPaddingBitCount(padding_bit_count),
+                &mut engine.random_generator,
+            )
+        })
+    }
+}
+
+/// A public key encrypting odd ciphertexts under the big key of a client key.
+///
+/// Only the big encryption key choice is supported, the expanded ciphertexts are then ready to
+/// be bootstrapped.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompactPublicKey {
+    pub(crate) key: LweCompactPublicKeyOwned<u64>,
+    pub parameters: OddCompactPublicKeyParameters,
+}
+
+impl CompactPublicKey {
+    pub fn new(cks: &ClientKey, parameters: OddCompactPublicKeyParameters) -> Self {
+        assert_eq!(
+            cks.parameters.encryption_key_choice,
+            EncryptionKeyChoice::Big,
+            "Compact public key encryption is only compatible with ciphertext in post PBS dimension"
+        );
+
+        let key = OddEngine::with_thread_local_mut(|engine| {
+            engine.new_compact_public_key(cks, parameters.encryption_noise_distribution)
+        });
+
+        Self { key, parameters }
+    }
+
+    /// Encrypts the elements of Zo under `encoding` and proves that each of them is a valid
+    /// element of the encoding.
+    ///
+    /// The messages are split in as many proven lists as the crs and the key dimension require.
+    ///
+    /// # Errors
+    ///
+    /// If the encoding cannot be proven, as for [`CompactPkeCrs::from_odd_params`], or if a
+    /// message is not an element of Zo.
+    pub fn encrypt_and_prove(
+        &self,
+        messages: &[u64],
+        encoding: &Encoding,
+        crs: &CompactPkeCrs,
+        metadata: &[u8],
+        load: ZkComputeLoad,
+    ) -> crate::Result<ProvenCompactCiphertextList> {
+        msb_zero_padding_bit_count(encoding)?;
+        if let Some(message) = messages.iter().find(|m| **m >= encoding.get_origin_modulus()) {
+            return Err(crate::Error::new(format!(
+                "Message {message} is not an element of Z{}",
+                encoding.get_origin_modulus()
+            )));
+        }
+
+        // The encoding is the identity, the elements of Zo are their own values in Zp
+        let delta = 1u64 << (64 - encoding.get_modulus().ilog2());
+        let bound_metadata = bind_encoding_to_metadata(encoding, metadata);
+
+        // Both the compact key and the proof bound the number of messages of a list
+        let message_chunk_size = crs.max_num_messages().0.min(self.key.lwe_dimension().0);
+
+        let proved_lists = messages
+            .chunks(message_chunk_size)
+            .map(|message_chunk| {
+                OddEngine::with_thread_local_mut(|engine| {
+                    engine.encrypt_and_prove_compact(
+                        &self.key,
+                        message_chunk,
+                        delta,
+                        self.parameters.encryption_noise_distribution,
+                        crs,
+                        &bound_metadata,
+                        load,
+                    )
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(ProvenCompactCiphertextList {
+            proved_lists,
+            encoding: encoding.clone(),
+        })
+    }
+}
+
+/// A list of compact odd ciphertexts sharing an encoding, with their zero-knowledge proofs.
+///
+/// The proofs can only be generated during the encryption with a [`CompactPublicKey`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProvenCompactCiphertextList {
+    pub(crate) proved_lists: Vec<(LweCompactCiphertextListOwned<u64>, CompactPkeProof)>,
+    pub(crate) encoding: Encoding,
+}
+
+impl ProvenCompactCiphertextList {
+    pub fn ciphertext_count(&self) -> usize {
+        self.proved_lists
+            .iter()
+            .map(|(list, _)| list.lwe_ciphertext_count().0)
+            .sum()
+    }
+
+    pub fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    /// Verifies the proofs, the crs being the one the server built for the encoding it expects.
+    pub fn verify(
+        &self,
+        crs: &CompactPkeCrs,
+        public_key: &CompactPublicKey,
+        metadata: &[u8],
+    ) -> ZkVerificationOutcome {
+        let bound_metadata = bind_encoding_to_metadata(&self.encoding, metadata);
+
+        let all_valid = self.proved_lists.par_iter().all(|(ct_list, proof)| {
+            verify_lwe_compact_ciphertext_list(ct_list, &public_key.key, proof, crs, &bound_metadata)
+                .is_valid()
+        });
+
+        if all_valid {
+            ZkVerificationOutcome::Valid
+        } else {
+            ZkVerificationOutcome::Invalid
+        }
+    }
+
+    /// Verifies the proofs and the claimed encoding, then expands the list into ciphertexts
+    /// ready for evaluation.
+    pub fn verify_and_expand(
+        &self,
+        crs: &CompactPkeCrs,
+        public_key: &CompactPublicKey,
+        expected_encoding: &Encoding,
+        metadata: &[u8],
+    ) -> crate::Result<Vec<Ciphertext>> {
+        if self.encoding != *expected_encoding {
+            return Err("The list was not encrypted under the expected encoding".into());
+        }
+
+        if self.verify(crs, public_key, metadata).is_invalid() {
+            return Err(crate::ErrorKind::InvalidZkProof.into());
+        }
+
+        // We can call the function as we have verified the proofs
+        Ok(self.expand_without_verification())
+    }
+
+    #[doc(hidden)]
+    /// This function allows to expand a ciphertext without verifying the associated proof.
+    ///
+    /// If you are here you were probably looking for it: use at your own risks.
+    pub fn expand_without_verification(&self) -> Vec<Ciphertext> {
+        self.proved_lists
+            .iter()
+            .flat_map(|(ct_list, _proof)| {
+                let mut expanded = LweCiphertextList::new(
+                    0u64,
+                    ct_list.lwe_size(),
+                    ct_list.lwe_ciphertext_count(),
+                    ct_list.ciphertext_modulus(),
+                );
+                expand_lwe_compact_ciphertext_list(&mut expanded, ct_list);
+
+                expanded
+                    .iter()
+                    .map(|lwe| {
+                        Ciphertext::EncodingEncrypted(
+                            LweCiphertextOwned::from_container(
+                                lwe.as_ref().to_vec(),
+                                lwe.ciphertext_modulus(),
+                            ),
+                            self.encoding.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odd::keycache::KEY_CACHE;
+
+    #[test]
+    fn test_odd_proven_compact_encryption() {
+        let keys = KEY_CACHE.get_from_param(PARAMETERS_40);
+        let (cks, sks) = (keys.client_key(), keys.server_key());
+
+        let public_key = CompactPublicKey::new(cks, COMPACT_PK_PARAMETERS_40);
+        let encoding = Encoding::parity_encoding();
+        let crs = CompactPkeCrs::from_odd_params(
+            &PARAMETERS_40,
+            COMPACT_PK_PARAMETERS_40,
+            &encoding,
+            LweCiphertextCount(16),
+        )
+        .unwrap();
+        let metadata = [b'o', b'd', b'd'];
+
+        let key_bits: Vec<u64> = (0..16).map(|i| (i * 7 + 3) % 5 % 2).collect();
+        let proven_list = public_key
+            .encrypt_and_prove(&key_bits, &encoding, &crs, &metadata, ZkComputeLoad::Proof)
+            .unwrap();
+
+        assert!(proven_list.verify(&crs, &public_key, &metadata).is_valid());
+        assert!(proven_list.verify(&crs, &public_key, b"other").is_invalid());
+        assert!(proven_list
+            .verify_and_expand(&crs, &public_key, &Encoding::new_canonical_binary(1, 4), &metadata)
+            .is_err());
+
+        let cts = proven_list
+            .verify_and_expand(&crs, &public_key, &encoding, &metadata)
+            .unwrap();
+        assert_eq!(cts.len(), key_bits.len());
+
+        // The expanded ciphertexts go through the bootstrapping like fresh ones, the parity
+        // inputs only bootstrap to negacyclic outputs
+        let not_encoding = Encoding::new_canonical(2, vec![7, 1], 8);
+        for (ct, bit) in cts.iter().zip(key_bits.iter()) {
+            assert_eq!(cks.decrypt(ct), *bit);
+            let not = sks.apply_lut(ct, &not_encoding, &|x| 1 - x);
+            assert_eq!(cks.decrypt(&not), 1 - bit);
+        }
+
+        // the odd moduli are rejected, whatever the encoding
+        for encoding in [
+            Encoding::new_canonical(3, vec![0, 1, 2], 7),
+            Encoding::new_canonical(16, (0..16).collect(), 17),
+        ] {
+            assert!(CompactPkeCrs::from_odd_params(
+                &PARAMETERS_40,
+                COMPACT_PK_PARAMETERS_40,
+                &encoding,
+                LweCiphertextCount(16),
+            )
+            .is_err());
+            assert!(public_key
+                .encrypt_and_prove(&[1], &encoding, &crs, &metadata, ZkComputeLoad::Proof)
+                .is_err());
+        }
+    }
+}
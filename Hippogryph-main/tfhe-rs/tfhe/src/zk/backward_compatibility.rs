@@ -0,0 +1,20 @@
+use tfhe_versionable::VersionsDispatch;
+
+use super::{CompactPkeCrs, CompactPkeProof, CompactPkeZkScheme};
+
+#[derive(VersionsDispatch)]
+pub enum CompactPkeZkSchemeVersions {
+    V0(CompactPkeZkScheme),
+}
+
+#[derive(VersionsDispatch)]
+#[allow(clippy::large_enum_variant)]
+pub enum CompactPkeProofVersions {
+    V0(CompactPkeProof),
+}
+
+#[derive(VersionsDispatch)]
+#[allow(clippy::large_enum_variant)]
+pub enum CompactPkeCrsVersions {
+    V0(CompactPkeCrs),
+}
@@ -0,0 +1,409 @@
+//! Wrappers around the `tfhe-zk-pok` proofs of compact public key encryption.
+//!
+//! The `pke` (legacy v1) and `pke_v2` schemes are hidden behind [`CompactPkeCrs`] and
+//! [`CompactPkeProof`], so that the encryption and verification algorithms of `core_crypto` do
+//! not depend on the scheme that was picked when the CRS was generated.
+
+mod backward_compatibility;
+
+use crate::conformance::ParameterSetConformant;
+use crate::core_crypto::commons::math::random::{BoundedDistribution, RandomGenerator};
+use crate::core_crypto::prelude::*;
+use crate::named::Named;
+use backward_compatibility::{CompactPkeCrsVersions, CompactPkeProofVersions, CompactPkeZkSchemeVersions};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::Bound;
+use tfhe_versionable::Versionize;
+use tfhe_zk_pok::proofs::{pke, pke_v2};
+
+pub use tfhe_zk_pok::proofs::ComputeLoad as ZkComputeLoad;
+
+type Curve = tfhe_zk_pok::curve_api::Bls12_446;
+
+/// The number of most significant bits of the plaintexts that are proven to be zero.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ZkMSBZeroPaddingBitCount(pub u64);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ZkVerificationOutcome {
+    /// The proof and its entity were valid
+    Valid,
+    /// The proof and its entity were not
+    Invalid,
+}
+
+impl ZkVerificationOutcome {
+    pub fn is_valid(self) -> bool {
+        matches!(self, Self::Valid)
+    }
+
+    pub fn is_invalid(self) -> bool {
+        matches!(self, Self::Invalid)
+    }
+}
+
+/// The scheme used to prove a compact public key encryption.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Versionize)]
+#[versionize(CompactPkeZkSchemeVersions)]
+pub enum CompactPkeZkScheme {
+    V1,
+    V2,
+}
+
+/// A proof that the plaintexts of a compact ciphertext list are in the range given by a
+/// [`CompactPkeCrs`].
+#[derive(Clone, Debug, Serialize, Deserialize, Versionize)]
+#[versionize(CompactPkeProofVersions)]
+#[allow(clippy::large_enum_variant)]
+pub enum CompactPkeProof {
+    PkeV1(pke::Proof<Curve>),
+    PkeV2(pke_v2::Proof<Curve>),
+}
+
+impl Named for CompactPkeProof {
+    const NAME: &'static str = "zk::CompactPkeProof";
+}
+
+impl ParameterSetConformant for CompactPkeProof {
+    type ParameterSet = CompactPkeZkScheme;
+
+    fn is_conformant(&self, parameter_set: &Self::ParameterSet) -> bool {
+        match (self, parameter_set) {
+            (Self::PkeV1(proof), CompactPkeZkScheme::V1) => proof.is_usable(),
+            (Self::PkeV2(proof), CompactPkeZkScheme::V2) => proof.is_usable(),
+            _ => false,
+        }
+    }
+}
+
+/// The common reference string of the proofs of compact public key encryption.
+///
+/// It fixes the lwe dimension, the noise bound, the moduli and the maximum number of messages
+/// of the lists that can be proven with it.
+#[derive(Clone, Debug, Serialize, Deserialize, Versionize)]
+#[versionize(CompactPkeCrsVersions)]
+#[allow(clippy::large_enum_variant)]
+pub enum CompactPkeCrs {
+    PkeV1(pke::PublicParams<Curve>),
+    PkeV2(pke_v2::PublicParams<Curve>),
+}
+
+impl Named for CompactPkeCrs {
+    const NAME: &'static str = "zk::CompactPkeCrs";
+}
+
+impl CompactPkeCrs {
+    /// Prepare the parameters of the CRS : the lwe dimension, the number of messages, the noise
+    /// bound, q (0 for the native modulus) and the plaintext modulus t.
+    fn prepare_crs_parameters<Scalar, NoiseDistribution>(
+        lwe_dim: LweDimension,
+        max_num_message: LweCiphertextCount,
+        noise_distribution: NoiseDistribution,
+        ciphertext_modulus: CiphertextModulus<Scalar>,
+        plaintext_modulus: Scalar,
+    ) -> crate::Result<(usize, usize, u64, u64, u64)>
+    where
+        Scalar: UnsignedInteger + CastInto<u64> + Debug,
+        NoiseDistribution: BoundedDistribution<Scalar::Signed>,
+    {
+        // b is the exclusive bound of the noise, which lies in [-(b - 1), b - 1]
+        let b = match noise_distribution.high_bound() {
+            Bound::Included(high_bound) => high_bound + Scalar::Signed::ONE,
+            Bound::Excluded(high_bound) => high_bound,
+            Bound::Unbounded => {
+                return Err("Cannot prove an encryption with an unbounded noise distribution".into())
+            }
+        };
+        let low_bound = match noise_distribution.low_bound() {
+            Bound::Included(low_bound) => low_bound,
+            Bound::Excluded(low_bound) => low_bound + Scalar::Signed::ONE,
+            Bound::Unbounded => {
+                return Err("Cannot prove an encryption with an unbounded noise distribution".into())
+            }
+        };
+        if b + low_bound != Scalar::Signed::ONE {
+            return Err("Cannot prove an encryption with a noise distribution which is not \
+                centered around zero"
+                .into());
+        }
+
+        let q = if ciphertext_modulus.is_native_modulus() {
+            match Scalar::BITS.cmp(&64) {
+                Ordering::Greater => {
+                    return Err("Zero knowledge proof do not support ciphertext modulus > 64 bits"
+                        .into())
+                }
+                Ordering::Equal => 0u64,
+                Ordering::Less => 1u64 << Scalar::BITS,
+            }
+        } else {
+            let custom_modulus = ciphertext_modulus.get_custom_modulus();
+            if custom_modulus > u64::MAX as u128 {
+                return Err(
+                    "Zero knowledge proof do not support ciphertext modulus > 64 bits".into(),
+                );
+            }
+            custom_modulus as u64
+        };
+
+        Ok((
+            lwe_dim.0,
+            max_num_message.0,
+            b.into_unsigned().cast_into(),
+            q,
+            plaintext_modulus.cast_into(),
+        ))
+    }
+
+    /// Generates a CRS of the `pke_v2` scheme.
+    pub fn new<Scalar, NoiseDistribution>(
+        lwe_dim: LweDimension,
+        max_num_message: LweCiphertextCount,
+        noise_distribution: NoiseDistribution,
+        ciphertext_modulus: CiphertextModulus<Scalar>,
+        plaintext_modulus: Scalar,
+        msbs_zero_padding_bit_count: ZkMSBZeroPaddingBitCount,
+        rng: &mut impl RngCore,
+    ) -> crate::Result<Self>
+    where
+        Scalar: UnsignedInteger + CastInto<u64> + Debug,
+        NoiseDistribution: BoundedDistribution<Scalar::Signed>,
+    {
+        let (d, k, b, q, t) = Self::prepare_crs_parameters(
+            lwe_dim,
+            max_num_message,
+            noise_distribution,
+            ciphertext_modulus,
+            plaintext_modulus,
+        )?;
+
+        // pke_v2 takes the inclusive bound of the noise
+        Ok(Self::PkeV2(pke_v2::crs_gen(
+            d,
+            k,
+            b - 1,
+            q,
+            t,
+            msbs_zero_padding_bit_count.0,
+            rng,
+        )))
+    }
+
+    /// Generates a CRS of the legacy `pke` (v1) scheme.
+    pub fn new_legacy_v1<Scalar, NoiseDistribution>(
+        lwe_dim: LweDimension,
+        max_num_message: LweCiphertextCount,
+        noise_distribution: NoiseDistribution,
+        ciphertext_modulus: CiphertextModulus<Scalar>,
+        plaintext_modulus: Scalar,
+        msbs_zero_padding_bit_count: ZkMSBZeroPaddingBitCount,
+        rng: &mut impl RngCore,
+    ) -> crate::Result<Self>
+    where
+        Scalar: UnsignedInteger + CastInto<u64> + Debug,
+        NoiseDistribution: BoundedDistribution<Scalar::Signed>,
+    {
+        let (d, k, b, q, t) = Self::prepare_crs_parameters(
+            lwe_dim,
+            max_num_message,
+            noise_distribution,
+            ciphertext_modulus,
+            plaintext_modulus,
+        )?;
+
+        Ok(Self::PkeV1(pke::crs_gen(
+            d,
+            k,
+            b,
+            q,
+            t,
+            msbs_zero_padding_bit_count.0,
+            rng,
+        )))
+    }
+
+    pub fn scheme_version(&self) -> CompactPkeZkScheme {
+        match self {
+            Self::PkeV1(_) => CompactPkeZkScheme::V1,
+            Self::PkeV2(_) => CompactPkeZkScheme::V2,
+        }
+    }
+
+    pub fn lwe_dimension(&self) -> LweDimension {
+        match self {
+            Self::PkeV1(public_params) => LweDimension(public_params.d),
+            Self::PkeV2(public_params) => LweDimension(public_params.d),
+        }
+    }
+
+    /// Maximum number of messages that can be proven in a single list using this CRS
+    pub fn max_num_messages(&self) -> LweCiphertextCount {
+        match self {
+            Self::PkeV1(public_params) => LweCiphertextCount(public_params.k),
+            Self::PkeV2(public_params) => LweCiphertextCount(public_params.k),
+        }
+    }
+
+    /// Lower bound of the noise that the proof does not accept anymore
+    pub fn exclusive_max_noise(&self) -> u64 {
+        match self {
+            Self::PkeV1(public_params) => public_params.exclusive_max_noise(),
+            Self::PkeV2(public_params) => public_params.exclusive_max_noise(),
+        }
+    }
+
+    pub fn plaintext_modulus(&self) -> u64 {
+        match self {
+            Self::PkeV1(public_params) => public_params.t,
+            Self::PkeV2(public_params) => public_params.t,
+        }
+    }
+
+    pub fn ciphertext_modulus<Scalar: UnsignedInteger>(&self) -> CiphertextModulus<Scalar> {
+        let q = match self {
+            Self::PkeV1(public_params) => public_params.q,
+            Self::PkeV2(public_params) => public_params.q,
+        };
+
+        if q == 0 {
+            CiphertextModulus::new_native()
+        } else {
+            CiphertextModulus::new(q as u128)
+        }
+    }
+
+    /// Proves that the encryption of `messages` into `lwe_compact_list`, with the given random
+    /// vector and noises, is well formed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<Scalar, KeyCont, MessageCont, ListCont, G>(
+        &self,
+        compact_public_key: &LweCompactPublicKey<KeyCont>,
+        messages: &MessageCont,
+        lwe_compact_list: &LweCompactCiphertextList<ListCont>,
+        binary_random_vector: &[Scalar],
+        mask_noise: &[Scalar],
+        body_noise: &[Scalar],
+        metadata: &[u8],
+        load: ZkComputeLoad,
+        random_generator: &mut RandomGenerator<G>,
+    ) -> CompactPkeProof
+    where
+        Scalar: UnsignedInteger,
+        i64: CastFrom<Scalar>,
+        KeyCont: Container<Element = Scalar>,
+        MessageCont: Container<Element = Scalar>,
+        ListCont: Container<Element = Scalar>,
+        G: ByteRandomGenerator,
+    {
+        let to_i64 = |values: &[Scalar]| -> Vec<i64> {
+            values.iter().copied().map(i64::cast_from).collect()
+        };
+
+        let key_mask = to_i64(compact_public_key.get_mask().as_ref());
+        let key_body = to_i64(compact_public_key.get_body().as_ref());
+        let ct_mask = to_i64(lwe_compact_list.get_mask_list().as_ref());
+        let ct_body = to_i64(lwe_compact_list.get_body_list().as_ref());
+        let binary_random_vector = to_i64(binary_random_vector);
+        let mask_noise = to_i64(mask_noise);
+        let messages = to_i64(messages.as_ref());
+        let body_noise = to_i64(body_noise);
+
+        match self {
+            Self::PkeV1(public_params) => {
+                let (public_commit, private_commit) = pke::commit(
+                    key_mask,
+                    key_body,
+                    ct_mask,
+                    ct_body,
+                    binary_random_vector,
+                    mask_noise,
+                    messages,
+                    body_noise,
+                    public_params,
+                    random_generator,
+                );
+
+                CompactPkeProof::PkeV1(pke::prove(
+                    (public_params, &public_commit),
+                    &private_commit,
+                    metadata,
+                    load,
+                    random_generator,
+                ))
+            }
+            Self::PkeV2(public_params) => {
+                let (public_commit, private_commit) = pke_v2::commit(
+                    key_mask,
+                    key_body,
+                    ct_mask,
+                    ct_body,
+                    binary_random_vector,
+                    mask_noise,
+                    messages,
+                    body_noise,
+                    public_params,
+                    random_generator,
+                );
+
+                CompactPkeProof::PkeV2(pke_v2::prove(
+                    (public_params, &public_commit),
+                    &private_commit,
+                    metadata,
+                    load,
+                    random_generator,
+                ))
+            }
+        }
+    }
+
+    /// Verifies a proof of the encryption of `lwe_compact_list`.
+    ///
+    /// A proof generated with another scheme than the one of the CRS is invalid.
+    pub fn verify<Scalar, ListCont, KeyCont>(
+        &self,
+        lwe_compact_list: &LweCompactCiphertextList<ListCont>,
+        compact_public_key: &LweCompactPublicKey<KeyCont>,
+        proof: &CompactPkeProof,
+        metadata: &[u8],
+    ) -> ZkVerificationOutcome
+    where
+        Scalar: UnsignedInteger,
+        i64: CastFrom<Scalar>,
+        ListCont: Container<Element = Scalar>,
+        KeyCont: Container<Element = Scalar>,
+    {
+        if Scalar::BITS > 64 {
+            return ZkVerificationOutcome::Invalid;
+        }
+
+        let to_i64 = |values: &[Scalar]| -> Vec<i64> {
+            values.iter().copied().map(i64::cast_from).collect()
+        };
+
+        let key_mask = to_i64(compact_public_key.get_mask().as_ref());
+        let key_body = to_i64(compact_public_key.get_body().as_ref());
+        let ct_mask = to_i64(lwe_compact_list.get_mask_list().as_ref());
+        let ct_body = to_i64(lwe_compact_list.get_body_list().as_ref());
+
+        let res = match (self, proof) {
+            (Self::PkeV1(public_params), CompactPkeProof::PkeV1(proof)) => {
+                let public_commit = pke::PublicCommit::new(key_mask, key_body, ct_mask, ct_body);
+                pke::verify(proof, (public_params, &public_commit), metadata)
+            }
+            (Self::PkeV2(public_params), CompactPkeProof::PkeV2(proof)) => {
+                let public_commit =
+                    pke_v2::PublicCommit::new(key_mask, key_body, ct_mask, ct_body);
+                pke_v2::verify(proof, (public_params, &public_commit), metadata)
+            }
+            _ => Err(()),
+        };
+
+        match res {
+            Ok(()) => ZkVerificationOutcome::Valid,
+            Err(()) => ZkVerificationOutcome::Invalid,
+        }
+    }
+}